@@ -10,7 +10,7 @@ use figments::prelude::Hsv;
 use log::{info, warn};
 use rgb::{Grb,Rgb};
 use figments::{mappings::linear::LinearSpace, prelude::*};
-use figments::liber8tion::trig::sin8;
+use figments::liber8tion::trig::Trig8;
 use figments_render::{output::Brightness, power::AsMilliwatts, smart_leds::PowerManagedWriter};
 use core::num::Wrapping;
 use figments_sample_shaders::*;
@@ -86,7 +86,7 @@ async fn layer_task(mut layers: [BufferedSurface<FrameNumber, LinearSpace, Rgb<u
     loop {
         // Adjust the opacity for each layer using a basic oscilating wave function based on layer order
         for (idx, layer) in layers.iter_mut().enumerate() {
-            layer.set_opacity(sin8(frame_idx.wrapping_mul(idx + 2)));
+            layer.set_opacity(frame_idx.wrapping_mul(idx + 2).sin8());
         }
 
         background_color.hue = background_color.hue.wrapping_add(21);
@@ -117,8 +117,10 @@ async fn render_task(mut surfaces: BufferedSurfacePool<FrameNumber, LinearSpace,
 
     // Change this to adjust the power available; the USB spec says 500ma is the standard limit,
     // but sometimes you can draw more from a power brick.
-    // We also need to take a small 100ma off the top for the MCU, therefore we aim for 400ma.
-    const POWER_MA : u32 = 400;
+    const POWER_MA : u32 = 500;
+
+    // The MCU itself also needs about 100ma, which is taken off the top of the LED power budget.
+    const MCU_MA : u32 = 100;
 
     // You probably don't need to change these values, unless your LED strip is somehow not 5 volts
     const POWER_VOLTS : u32 = 5;
@@ -141,6 +143,7 @@ async fn render_task(mut surfaces: BufferedSurfacePool<FrameNumber, LinearSpace,
 
     // Stick a power management API on top of it
     let mut writer = PowerManagedWriter::new(target, MAX_POWER_MW);
+    writer.set_overhead_mw(POWER_VOLTS * MCU_MA);
 
     // We use this so we only print out our stats once every second
     let mut last_print = 0;
@@ -148,16 +151,13 @@ async fn render_task(mut surfaces: BufferedSurfacePool<FrameNumber, LinearSpace,
     loop {
         let start = Instant::now();
 
-        // Clear the pixbuf back to a blank slate
-        pixbuf = [Default::default(); NUM_LEDS];
-
         let frame = (Instant::now().as_millis() / ANIMATION_FRAME_TIME.as_millis()) as usize;
 
         // Apply any changes that the other layer task might have prepared
         surfaces.commit();
 
-        // Render the layers to the pixbuf
-        surfaces.render_to(&mut pixbuf, &FrameNumber(frame));
+        // Clear the pixbuf back to a blank slate, then render the layers to it
+        surfaces.render_frame(&mut pixbuf, &FrameNumber(frame));
         let draw_time = start.elapsed();
 
         // Finally, write out the rendered frame
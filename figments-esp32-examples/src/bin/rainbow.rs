@@ -13,8 +13,8 @@ use esp_hal::{clock::CpuClock, delay::Delay, main, rmt::Rmt, time::{Instant, Rat
 use log::info;
 use rgb::{Grb,Rgb};
 use figments::prelude::*;
-use figments::liber8tion::trig::*;
-use figments_render::{output::Brightness, power::AsMilliwatts, smart_leds::PowerManagedWriter};
+use figments::liber8tion::trig::Trig8;
+use figments_render::{output::{Brightness, Output}, power::AsMilliwatts, smart_leds::SmartLedsOutput};
 use core::num::Wrapping;
 
 use esp_hal_smartled::{smart_led_buffer, SmartLedsAdapter};
@@ -58,32 +58,32 @@ fn main() -> ! {
     // then change this to new_with_color(). Either way, a color conversion happens at rendering time in the most efficient way possible, which could mean no conversion at all.
     let mut target = SmartLedsAdapter::new(rmt_channel, p.GPIO5, &mut rmt_buffer);
 
-    // LEDs can get extremely bright very quickly. Sticking a power management API on top of it prevents brownouts.
-    let mut writer = PowerManagedWriter::new(target, MAX_POWER_MW);
+    // Next, lets create a pixbuf that we will be drawing everything into before it is sent out to the hardware.
+    // We can change this to specify a custom color format, or we can let the trait system figure out which is the most compatible with the code that generates your images, and what the hardware expects.
+    // This minimizes the need for expensive color conversions and lets you write your graphics in any color format or space you want, as long as it can eventually get converted to the driver's format.
+    let mut pixbuf = [Default::default(); NUM_LEDS];
+
+    // Finally, the target and pixbuf are combined into an Output. LEDs can get extremely bright very quickly, so the output also manages the power consumption to prevent brownouts.
+    let mut output = SmartLedsOutput::new(target, &mut pixbuf, MAX_POWER_MW);
 
     // This value is used as the 'seed' for rendering each frame, allowing us to do things like run the animation backwards, frames for double FPS, or even use system uptime for more human-paced animations
     // Try setting it to Instant::now() inside the loop, for example.
     let mut frame = Wrapping(0);
 
-    // Finally, lets create a pixbuf that we will be drawing everything into before it is sent out to the hardware.
-    // We can change this to specify a custom color format, or we can let the trait system figure out which is the most compatible with the code that generates your images, and what the hardware expects.
-    // This minimizes the need for expensive color conversions and lets you write your graphics in any color format or space you want, as long as it can eventually get converted to the driver's format.
-    let mut pixbuf = [Default::default(); NUM_LEDS];
-
     loop {
         // Clear the pixbuf to black
-        pixbuf = [Default::default(); NUM_LEDS];
+        *output.pixbuf() = [Default::default(); NUM_LEDS];
 
         // Mark the time we start rendering for performance reporting
         let start = Instant::now();
 
         // Render the frame to the pixbuf by sampling the entire pixbuf and manipulating each pixel in the buffer
-        for (coords, pix) in pixbuf.sample(&Rectangle::everything()) {
+        for (coords, pix) in output.sample(&Rectangle::everything()) {
             // Calculate the color for this pixel using some fun wave functions that take coordinates along the pixel strip as an input
             let rendered = Rgb::new(
-                sin8(coords.x.wrapping_mul(3).wrapping_add(frame.0).wrapping_add(coords.x)),
-                cos8(coords.x.wrapping_mul(5).wrapping_sub(frame.0).wrapping_add(coords.x)),
-                sin8(coords.x.wrapping_mul(2).wrapping_add(frame.0).wrapping_add(coords.x))
+                coords.x.wrapping_mul(3).wrapping_add(frame.0).wrapping_add(coords.x).sin8().to_raw(),
+                coords.x.wrapping_mul(5).wrapping_sub(frame.0).wrapping_add(coords.x).cos8().to_raw(),
+                coords.x.wrapping_mul(2).wrapping_add(frame.0).wrapping_add(coords.x).sin8().to_raw()
             );
 
             // We apply a color format conversion here from RGB to whatever the hardware ends up spporting via into()
@@ -93,14 +93,16 @@ fn main() -> ! {
         let draw_time = start.elapsed();
 
         // Adjust the brightness along a sine wave pattern so the whole display fades in and out
-        writer.controls().set_brightness(sin8(frame.0 / 3));
+        if let Some(controls) = output.controls() {
+            controls.set_brightness((frame.0 / 3).sin8());
+        }
 
         // Finally, write out the rendered frame
-        writer.write(&pixbuf).expect("Failed to write to LEDs!");
+        output.commit().expect("Failed to write to LEDs!");
 
         // Print out our rendering times
         let write_time = start.elapsed();
-        info!("draw={draw_time} write={write_time} total={} power={}mw / {MAX_POWER_MW}mw", draw_time + write_time, writer.max_mw());
+        info!("draw={draw_time} write={write_time} total={} power={}mw / {MAX_POWER_MW}mw", draw_time + write_time, output.max_mw());
 
         // Increment the frame counter
         frame += 1;
@@ -9,8 +9,8 @@ use figments::prelude::Hsv;
 use log::info;
 use rgb::{Grb,Rgb};
 use figments::{mappings::linear::LinearSpace, prelude::*};
-use figments::liber8tion::trig::sin8;
-use figments_render::{output::Brightness, power::AsMilliwatts, smart_leds::PowerManagedWriter};
+use figments::liber8tion::trig::Trig8;
+use figments_render::{frame::Frame, output::Brightness, power::AsMilliwatts, smart_leds::PowerManagedWriter, stats::FrameTimer};
 use core::num::Wrapping;
 use figments_sample_shaders::*;
 
@@ -41,8 +41,10 @@ fn main() -> ! {
 
     // Change this to adjust the power available; the USB spec says 500ma is the standard limit,
     // but sometimes you can draw more from a power brick.
-    // We also need to take a small 100ma off the top for the MCU, therefore we aim for 400ma.
-    const POWER_MA : u32 = 400;
+    const POWER_MA : u32 = 500;
+
+    // The MCU itself also needs about 100ma, which is taken off the top of the LED power budget.
+    const MCU_MA : u32 = 100;
 
     // You probably don't need to change these values, unless your LED strip is somehow not 5 volts
     const POWER_VOLTS : u32 = 5;
@@ -64,18 +66,23 @@ fn main() -> ! {
 
     // Stick a power management API on top of it
     let mut writer = PowerManagedWriter::new(target, MAX_POWER_MW);
+    writer.set_overhead_mw(POWER_VOLTS * MCU_MA);
 
     let mut surfaces = BufferedSurfacePool::default();
 
-    // Our scene will have three separate layers that have their opacities animated around based on the frame.
     // Layers are rendered from first to last, meaning the first layer is the 'bottom' layer on top of which others are drawn.
+    // A glowing background color is always visible below all the other layers, so it is created first
+    let mut background_color = Hsv::new(0, 255, 255);
+    let mut background = SurfaceBuilder::build(&mut surfaces).shader(ColorGlow { color: background_color }).finish().unwrap();
+
+    // On top of it, our scene will have separate layers that have their opacities animated around based on the frame.
     let mut layers = [
         SurfaceBuilder::build(&mut surfaces).shader(RgbWaves::default()).finish().unwrap(),
         SurfaceBuilder::build(&mut surfaces).shader(Thinking::default()).finish().unwrap(),
     ];
 
-    // Additionally, a glowing background color is always visible below all the layers, so we draw it separately
-    let mut background_shader = ColorGlow { color: Hsv::new(0, 255, 255) };
+    // The frame ties the surfaces, pixbuf, and writer together, and takes care of committing surface changes, clearing the pixbuf, rendering, and writing it out in the right order
+    let mut display = Frame::new(&mut surfaces, &mut pixbuf, writer);
 
     // This value is used as the 'seed' for rendering each frame, allowing us to do things like run the animation backwards, frames for double FPS, or even use system uptime for more human-paced animations
     // Try setting it to Instant::now() inside the loop, for example.
@@ -83,42 +90,37 @@ fn main() -> ! {
 
     let mut last_rotation = 0;
 
-    loop {
-        let start = Instant::now();
-
-        // Clear the pixbuf back to a blank slate
-        pixbuf = [Default::default(); NUM_LEDS];
+    // Keeps track of how long it takes to render and write out each frame
+    let mut timer: FrameTimer<_> = FrameTimer::new(|| Instant::now().duration_since_epoch().as_micros());
 
+    loop {
         frame.0 = Instant::now().duration_since_epoch().as_millis() as usize / 100;
 
-        // Draw the background shader which is always visible
-        Painter::<_, _, Rgb<u8>>::paint(&mut pixbuf, &background_shader, &FrameNumber(frame.0), &Rectangle::everything());
+        // To change the properties of a shader, we must re-upload the entire shader back into the surface's memory, which is picked up when the frame is drawn
+        background.set_shader(ColorGlow { color: background_color });
 
         // Adjust the opacity for each layer using a basic oscilating wave function based on layer order
         for (idx, layer) in layers.iter_mut().enumerate() {
-            layer.set_opacity(sin8(frame.0.wrapping_mul(idx + 2)));
+            layer.set_opacity(frame.0.wrapping_mul(idx + 2).sin8());
         }
 
-        surfaces.commit();
-
-        // Render the layers to the pixbuf
-        surfaces.render_to(&mut pixbuf, &FrameNumber(frame.0));
-        let draw_time = start.elapsed();
+        // Render the layers to the pixbuf. Without the timer, this and the flush below could be a single call to display.render()
+        timer.render(|| display.draw(&FrameNumber(frame.0)));
 
         // Finally, write out the rendered frame
-        writer.write(&pixbuf).expect("Failed to write to LEDs!");
-        let flush_time = start.elapsed();
+        timer.flush(|| display.flush()).expect("Failed to write to LEDs!");
 
         let cur_second = Instant::now().duration_since_epoch().as_secs();
         if last_rotation != cur_second {
-            info!("frame={frame:?} draw={draw_time} flush={flush_time} total={} power={}mw", draw_time + flush_time, writer.max_mw());
+            let stats = timer.stats();
+            info!("frame={frame:?} draw={}us flush={}us fps={} power={}mw", stats.avg_render_us, stats.avg_flush_us, stats.fps(), display.writer().max_mw());
             // Set a different color on the colorglow shader every couple of frames
-            //background_shader.color.hue = background_shader.color.hue.wrapping_add(rng.random() as u8);
+            //background_color.hue = background_color.hue.wrapping_add(rng.random() as u8);
 
             last_rotation = cur_second;
         }
 
-        background_shader.color.hue = background_shader.color.hue.wrapping_add(1);
+        background_color.hue = background_color.hue.wrapping_add(1);
 
         Delay::new().delay_millis(13);
 
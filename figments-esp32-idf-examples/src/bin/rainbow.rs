@@ -1,5 +1,5 @@
 use esp_idf_svc::hal::prelude::Peripherals;
-use figments::{liber8tion::trig::{cos8, sin8}, prelude::*};
+use figments::{liber8tion::trig::Trig8, prelude::*};
 use rgb::Grb;
 use ws2812_esp32_rmt_driver::Ws2812Esp32Rmt;
 
@@ -40,8 +40,8 @@ fn main() {
             let angle_x = coords.x.wrapping_mul(3).wrapping_add(coords.y.wrapping_mul(3)).wrapping_add(frame);
             let angle_y = coords.y.wrapping_mul(3).wrapping_add(coords.x.wrapping_mul(3)).wrapping_add(frame.wrapping_div(2));
             *pix = Grb::new_grb(
-                sin8(angle_y),
-                cos8(angle_x),
+                angle_y.sin8().to_raw(),
+                angle_x.cos8().to_raw(),
                 angle_x.wrapping_add(angle_y) as u8
             ).into();
         }
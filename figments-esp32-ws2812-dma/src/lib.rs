@@ -1,6 +1,9 @@
 #![no_std]
 
+use core::marker::PhantomData;
+
 use rgb::Rgb;
+use figments::pixels::Rgbw;
 use esp_hal::{Async, Blocking};
 use esp_hal::dma::DmaDescriptor;
 use esp_hal::spi::master::SpiDma;
@@ -21,68 +24,272 @@ impl<T: Copy, const TX_SIZE: usize> DmaBuffers<T, TX_SIZE> {
     }
 }
 
-struct SpiPixelWriter<'a> {
+/// Returns the number of bytes of DMA buffer needed to send `num_leds` pixels with the default [Ws2812] timing
+pub const fn dma_buffer_size(num_leds: usize) -> usize {
+    dma_buffer_size_with_timing::<Ws2812>(num_leds)
+}
+
+/// Returns the number of bytes of DMA buffer needed to send `num_leds` pixels with a specific [Timing]
+///
+/// No extra space is needed for the reset period, as the SPI line idles low once the transfer finishes.
+pub const fn dma_buffer_size_with_timing<T: Timing>(num_leds: usize) -> usize {
+    dma_buffer_size_with_format::<T, Grb>(num_leds)
+}
+
+/// Returns the number of bytes of DMA buffer needed to send `num_leds` pixels with a specific [Timing] and [ColorFormat], such as [Grbw] for SK6812 strips
+pub const fn dma_buffer_size_with_format<T: Timing, F: ColorFormat>(num_leds: usize) -> usize {
+    SpiPixelWriter::<T, F>::required_buffer_len(num_leds)
+}
+
+/// Creates a [DmaBuffers] that is correctly sized for a number of LEDs, similar to `smart_led_buffer!`
+///
+/// An optional [Timing] can be given as the second argument, eg `ws2812_dma_buffers!(NUM_LEDS, Ws2811)`, followed by an optional [ColorFormat], eg `ws2812_dma_buffers!(NUM_LEDS, Ws2812, Grbw)`.
+#[macro_export]
+macro_rules! ws2812_dma_buffers {
+    ($num_leds:expr) => {
+        $crate::ws2812_dma_buffers!($num_leds, $crate::Ws2812)
+    };
+    ($num_leds:expr, $timing:ty) => {
+        $crate::ws2812_dma_buffers!($num_leds, $timing, $crate::Grb)
+    };
+    ($num_leds:expr, $timing:ty, $format:ty) => {
+        $crate::DmaBuffers::<u8, { $crate::dma_buffer_size_with_format::<$timing, $format>($num_leds) }>::new(0)
+    };
+}
+
+/// Describes the bit timing of a WS2812-like protocol in terms of SPI bits
+///
+/// Each data bit sent to the LEDs is expanded into a symbol of [Timing::SYMBOL_BITS] SPI bits, where the line is held high for the first [Timing::T0H_BITS] or [Timing::T1H_BITS] bits and low for the rest. The SPI clock must be configured to [Timing::SPI_FREQUENCY_HZ], which is [Timing::SYMBOL_BITS] times the data rate of the LEDs.
+///
+/// Custom timings can be made for chips that need a different ratio of high and low pulses:
+///
+/// ```
+/// use figments_esp32_ws2812_dma::Timing;
+///
+/// /// WS2815 at 800kHz, with a 375ns T0H and a 1µs T1H
+/// struct Ws2815;
+///
+/// impl Timing for Ws2815 {
+///     const SYMBOL_BITS: u32 = 8;
+///     const T0H_BITS: u32 = 2;
+///     const T1H_BITS: u32 = 6;
+/// }
+///
+/// assert_eq!(Ws2815::SPI_FREQUENCY_HZ, 6_400_000);
+/// ```
+pub trait Timing {
+    /// Number of SPI bits used to encode a single data bit, up to 8
+    const SYMBOL_BITS: u32;
+    /// Number of SPI bits the line is held high when sending a zero
+    const T0H_BITS: u32;
+    /// Number of SPI bits the line is held high when sending a one
+    const T1H_BITS: u32;
+    /// The SPI clock that the symbols are meant to be sent at, which defaults to the 800kHz data rate of most chips
+    const SPI_FREQUENCY_HZ: u32 = Self::SYMBOL_BITS * 800_000;
+
+    /// Returns the SPI symbol for a single data bit
+    #[inline(always)]
+    fn symbol(bit: bool) -> u8 {
+        let high_bits = if bit { Self::T1H_BITS } else { Self::T0H_BITS };
+        (((1u16 << high_bits) - 1) << (Self::SYMBOL_BITS - high_bits)) as u8
+    }
+}
+
+/// Timing for WS2812 and compatible chips running at 800kHz, which requires a 3.2MHz SPI clock
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Ws2812;
+
+impl Timing for Ws2812 {
+    const SYMBOL_BITS: u32 = 4;
+    const T0H_BITS: u32 = 1;
+    const T1H_BITS: u32 = 3;
+}
+
+/// Timing for WS2811 and other chips running in the 400kHz slow mode, which requires a 3.2MHz SPI clock
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Ws2811;
+
+impl Timing for Ws2811 {
+    const SYMBOL_BITS: u32 = 8;
+    const T0H_BITS: u32 = 2;
+    const T1H_BITS: u32 = 4;
+    const SPI_FREQUENCY_HZ: u32 = 3_200_000;
+}
+
+/// Timing for WS2812B chips, which requires a 6.4MHz SPI clock
+///
+/// The finer symbols give a 312ns T0H and a 781ns T1H, which is closer to the 400ns and 800ns in the datasheet than [Ws2812].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Ws2812b;
+
+impl Timing for Ws2812b {
+    const SYMBOL_BITS: u32 = 8;
+    const T0H_BITS: u32 = 2;
+    const T1H_BITS: u32 = 5;
+}
+
+/// Timing for SK6812 chips, which have shorter high pulses than WS2812 and require a 6.4MHz SPI clock
+///
+/// This gives a 312ns T0H and a 625ns T1H, against the 300ns and 600ns in the datasheet.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Sk6812;
+
+impl Timing for Sk6812 {
+    const SYMBOL_BITS: u32 = 8;
+    const T0H_BITS: u32 = 2;
+    const T1H_BITS: u32 = 4;
+}
+
+/// Describes the color channels of each pixel, and the order they are sent to the LEDs in
+pub trait ColorFormat {
+    /// The color type accepted by the writer
+    type Color: Copy;
+    /// Number of 8 bit channels sent for each pixel
+    const CHANNELS: usize;
+
+    /// Calls `f` with each channel of the color, in the order they are sent to the LEDs
+    fn for_each_channel(color: Self::Color, f: impl FnMut(u8));
+}
+
+/// Three channels sent as green, red, then blue, which is used by WS2812 and most of its clones
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Grb;
+
+impl ColorFormat for Grb {
+    type Color = Rgb<u8>;
+    const CHANNELS: usize = 3;
+
+    #[inline(always)]
+    fn for_each_channel(color: Self::Color, mut f: impl FnMut(u8)) {
+        f(color.g);
+        f(color.r);
+        f(color.b);
+    }
+}
+
+/// Four channels sent as green, red, blue, then white, which is used by SK6812 RGBW strips
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Grbw;
+
+impl ColorFormat for Grbw {
+    type Color = Rgbw<u8>;
+    const CHANNELS: usize = 4;
+
+    #[inline(always)]
+    fn for_each_channel(color: Self::Color, mut f: impl FnMut(u8)) {
+        f(color.g);
+        f(color.r);
+        f(color.b);
+        f(color.w);
+    }
+}
+
+struct SpiPixelWriter<'a, T: Timing, F: ColorFormat> {
     idx: usize,
-    data: &'a mut [u8]
+    data: &'a mut [u8],
+    pending: u32,
+    pending_bits: u32,
+    timing: PhantomData<T>,
+    format: PhantomData<F>
 }
 
-impl<'a> SpiPixelWriter<'a> {
+impl<'a, T: Timing, F: ColorFormat> SpiPixelWriter<'a, T, F> {
     const fn new(data: &'a mut [u8]) -> Self {
         Self {
             idx: 0,
-            data
+            data,
+            pending: 0,
+            pending_bits: 0,
+            timing: PhantomData,
+            format: PhantomData
         }
     }
 
-    #[inline(always)]
-    fn write_byte(&mut self, mut data: u8) {
-        let patterns = [0b1000_1000, 0b1000_1110, 0b11101000, 0b11101110];
+    /// Returns the number of bytes needed to encode `num_leds` pixels
+    const fn required_buffer_len(num_leds: usize) -> usize {
+        // Each pixel has a number of color channels of eight bits each
+        (num_leds * F::CHANNELS * 8 * T::SYMBOL_BITS as usize).div_ceil(8)
+    }
 
-        if self.idx > self.data.len() - 4 {
-            return;
+    #[inline(always)]
+    fn write_symbol(&mut self, symbol: u8) {
+        self.pending = (self.pending << T::SYMBOL_BITS) | symbol as u32;
+        self.pending_bits += T::SYMBOL_BITS;
+        while self.pending_bits >= 8 {
+            self.pending_bits -= 8;
+            if self.idx < self.data.len() {
+                self.data[self.idx] = (self.pending >> self.pending_bits) as u8;
+                self.idx += 1;
+            }
         }
-        for _ in 0..4 {
-            let bits = (data & 0b1100_0000) >> 6;
-            self.data[self.idx] = patterns[bits as usize];
-            self.idx += 1;
-            data <<= 2;
+    }
+
+    #[inline(always)]
+    fn write_byte(&mut self, mut data: u8) {
+        for _ in 0..8 {
+            self.write_symbol(T::symbol(data & 0b1000_0000 != 0));
+            data <<= 1;
         }
     }
 
-    fn write<T, I>(&mut self, iterator: T) -> usize
+    fn write<I, C>(&mut self, iterator: I) -> usize
     where
-        T: IntoIterator<Item = I>,
-        I: Into<Rgb<u8>> {
+        I: IntoIterator<Item = C>,
+        C: Into<F::Color> {
 
         for pix in iterator {
-            let color = pix.into();
-            self.write_byte(color.g);
-            self.write_byte(color.r);
-            self.write_byte(color.b);
+            F::for_each_channel(pix.into(), |channel| self.write_byte(channel));
         }
 
         self.idx
     }
 }
 
-pub struct Esp32Ws2812SpiDmaWriter<Spi, Buffer> {
+pub struct Esp32Ws2812SpiDmaWriter<Spi, Buffer, T: Timing = Ws2812, F: ColorFormat = Grb> {
     spi: Option<Spi>,
-    spi_buf: Option<Buffer>
+    spi_buf: Option<Buffer>,
+    timing: PhantomData<T>,
+    format: PhantomData<F>
 }
 
 impl<Spi, Buffer> Esp32Ws2812SpiDmaWriter<Spi, Buffer> {
     pub const fn new(spi: Spi, spi_buf: Buffer) -> Self {
+        Self::new_with_timing(spi, spi_buf)
+    }
+}
+
+impl<Spi, Buffer, T: Timing> Esp32Ws2812SpiDmaWriter<Spi, Buffer, T> {
+    /// Creates a new writer that uses a specific [Timing], eg for WS2811 chips
+    pub const fn new_with_timing(spi: Spi, spi_buf: Buffer) -> Self {
+        Self::new_with_format(spi, spi_buf)
+    }
+}
+
+impl<Spi, Buffer> Esp32Ws2812SpiDmaWriter<Spi, Buffer, Ws2812, Grbw> {
+    /// Creates a new writer for SK6812 RGBW strips, which send a fourth byte for the white LED
+    ///
+    /// The buffer must be sized for the extra channel, eg with `ws2812_dma_buffers!(NUM_LEDS, Ws2812, Grbw)`.
+    pub const fn new_rgbw(spi: Spi, spi_buf: Buffer) -> Self {
+        Self::new_with_format(spi, spi_buf)
+    }
+}
+
+impl<Spi, Buffer, T: Timing, F: ColorFormat> Esp32Ws2812SpiDmaWriter<Spi, Buffer, T, F> {
+    /// Creates a new writer that uses a specific [Timing] and [ColorFormat]
+    pub const fn new_with_format(spi: Spi, spi_buf: Buffer) -> Self {
         Self {
             spi: Some(spi),
-            spi_buf: Some(spi_buf)
+            spi_buf: Some(spi_buf),
+            timing: PhantomData,
+            format: PhantomData
         }
     }
 }
 
-impl SmartLedsWrite for Esp32Ws2812SpiDmaWriter<SpiDma<'_, Blocking>, DmaTxBuf> {
+impl<Tm: Timing, F: ColorFormat> SmartLedsWrite for Esp32Ws2812SpiDmaWriter<SpiDma<'_, Blocking>, DmaTxBuf, Tm, F> {
     type Error = esp_hal::spi::Error;
     
-    type Color = Rgb<u8>;
+    type Color = F::Color;
     
     fn write<T, I>(&mut self, iterator: T) -> Result<(), Self::Error>
     where
@@ -90,7 +297,7 @@ impl SmartLedsWrite for Esp32Ws2812SpiDmaWriter<SpiDma<'_, Blocking>, DmaTxBuf>
         I: Into<Self::Color> {
 
         let mut spi_buf = self.spi_buf.take().unwrap();
-        let mut writer = SpiPixelWriter::new(spi_buf.as_mut_slice());
+        let mut writer = SpiPixelWriter::<Tm, F>::new(spi_buf.as_mut_slice());
 
         let idx = writer.write(iterator);
         spi_buf.set_length(idx);
@@ -115,10 +322,10 @@ impl SmartLedsWrite for Esp32Ws2812SpiDmaWriter<SpiDma<'_, Blocking>, DmaTxBuf>
 }
 
 
-impl SmartLedsWriteAsync for Esp32Ws2812SpiDmaWriter<SpiDma<'_, Blocking>, DmaTxBuf> {
+impl<Tm: Timing, F: ColorFormat> SmartLedsWriteAsync for Esp32Ws2812SpiDmaWriter<SpiDma<'_, Blocking>, DmaTxBuf, Tm, F> {
     type Error = esp_hal::spi::Error;
     
-    type Color = Rgb<u8>;
+    type Color = F::Color;
     
     async fn write<T, I>(&mut self, iterator: T) -> Result<(), Self::Error>
     where
@@ -129,10 +336,10 @@ impl SmartLedsWriteAsync for Esp32Ws2812SpiDmaWriter<SpiDma<'_, Blocking>, DmaTx
     }
 }
 
-impl SmartLedsWriteAsync for Esp32Ws2812SpiDmaWriter<SpiDma<'_, Async>, DmaTxBuf> {
+impl<Tm: Timing, F: ColorFormat> SmartLedsWriteAsync for Esp32Ws2812SpiDmaWriter<SpiDma<'_, Async>, DmaTxBuf, Tm, F> {
     type Error = esp_hal::spi::Error;
     
-    type Color = Rgb<u8>;
+    type Color = F::Color;
     
     async fn write<T, I>(&mut self, iterator: T) -> Result<(), Self::Error>
     where
@@ -140,7 +347,7 @@ impl SmartLedsWriteAsync for Esp32Ws2812SpiDmaWriter<SpiDma<'_, Async>, DmaTxBuf
         I: Into<Self::Color> {
 
         let mut spi_buf = self.spi_buf.take().unwrap();
-        let mut writer = SpiPixelWriter::new(spi_buf.as_mut_slice());
+        let mut writer = SpiPixelWriter::<Tm, F>::new(spi_buf.as_mut_slice());
 
         let idx = writer.write(iterator);
         spi_buf.set_length(idx);
@@ -163,4 +370,100 @@ impl SmartLedsWriteAsync for Esp32Ws2812SpiDmaWriter<SpiDma<'_, Async>, DmaTxBuf
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode<T: Timing>(pixels: &[Rgb<u8>], buf: &mut [u8]) -> usize {
+        SpiPixelWriter::<T, Grb>::new(buf).write(pixels.iter().copied())
+    }
+
+    #[test]
+    fn test_ws2812_patterns() {
+        let mut buf = [0; 12];
+        let len = encode::<Ws2812>(&[Rgb::new(0b0000_0000, 0b1111_1111, 0b0110_0110)], &mut buf);
+        assert_eq!(len, 12);
+        // Green is sent first, then red, then blue
+        assert_eq!(buf[..4], [0b1110_1110; 4]);
+        assert_eq!(buf[4..8], [0b1000_1000; 4]);
+        assert_eq!(buf[8..], [0b1000_1110, 0b1110_1000, 0b1000_1110, 0b1110_1000]);
+    }
+
+    /// Returns the SPI bits that encode two data bits
+    fn symbol_pair<T: Timing>(bits: u8) -> u16 {
+        ((T::symbol(bits & 0b10 != 0) as u16) << T::SYMBOL_BITS) | T::symbol(bits & 0b01 != 0) as u16
+    }
+
+    fn assert_monotonic_patterns<T: Timing>(name: &str) {
+        let patterns: [u16; 4] = core::array::from_fn(|bits| symbol_pair::<T>(bits as u8));
+        assert!(patterns.windows(2).all(|pair| pair[0] < pair[1]), "The {name} patterns for 0b00..0b11 should be distinct and increasing: {patterns:?}");
+        assert!(T::T0H_BITS > 0 && T::T1H_BITS < T::SYMBOL_BITS, "Every {name} symbol should start high and end low");
+    }
+
+    #[test]
+    fn test_presets_produce_distinct_patterns() {
+        assert_monotonic_patterns::<Ws2812>("WS2812");
+        assert_monotonic_patterns::<Ws2811>("WS2811");
+        assert_monotonic_patterns::<Ws2812b>("WS2812B");
+        assert_monotonic_patterns::<Sk6812>("SK6812");
+
+        assert_eq!(Ws2812::SPI_FREQUENCY_HZ, 3_200_000);
+        assert_eq!(Ws2811::SPI_FREQUENCY_HZ, 3_200_000);
+        assert_eq!(Ws2812b::SPI_FREQUENCY_HZ, 6_400_000);
+        assert_eq!(Sk6812::SPI_FREQUENCY_HZ, 6_400_000);
+        assert_eq!(symbol_pair::<Ws2812b>(0b01), 0b1100_0000_1111_1000);
+    }
+
+    #[test]
+    fn test_symbol_length_differs_between_presets() {
+        let pixels = [Rgb::new(0xff, 0x00, 0xaa); 3];
+        let mut ws2812_buf = [0; 128];
+        let mut ws2811_buf = [0; 128];
+
+        let ws2812_len = encode::<Ws2812>(&pixels, &mut ws2812_buf);
+        let ws2811_len = encode::<Ws2811>(&pixels, &mut ws2811_buf);
+
+        assert_eq!(ws2812_len, pixels.len() * 3 * 8 * Ws2812::SYMBOL_BITS as usize / 8);
+        assert_eq!(ws2811_len, pixels.len() * 3 * 8 * Ws2811::SYMBOL_BITS as usize / 8);
+        assert_ne!(ws2812_len, ws2811_len);
+
+        // Red is full brightness, and so should produce the one symbol for each bit
+        assert_eq!(ws2811_buf[8..16], [0b1111_0000; 8]);
+    }
+
+    #[test]
+    fn test_dma_buffer_size_fits_encoded_pixels() {
+        let pixels = [Rgb::new(0xff, 0x00, 0xaa); 5];
+        let mut buffers = ws2812_dma_buffers!(5);
+        assert_eq!(buffers.tx_buffer.len(), dma_buffer_size(5));
+        assert_eq!(encode::<Ws2812>(&pixels, &mut buffers.tx_buffer), SpiPixelWriter::<Ws2812, Grb>::required_buffer_len(pixels.len()));
+
+        let mut buffers = ws2812_dma_buffers!(5, Ws2811);
+        assert_eq!(buffers.tx_buffer.len(), dma_buffer_size_with_timing::<Ws2811>(5));
+        assert_eq!(encode::<Ws2811>(&pixels, &mut buffers.tx_buffer), SpiPixelWriter::<Ws2811, Grb>::required_buffer_len(pixels.len()));
+    }
+
+    #[test]
+    fn test_rgbw_patterns() {
+        let mut buf = [0; 16];
+        let len = SpiPixelWriter::<Ws2812, Grbw>::new(&mut buf).write([Rgbw::new(0b1111_1111, 0b0000_0000, 0b0110_0110, 0b1010_1010)]);
+        assert_eq!(len, 16);
+        assert_eq!(len, dma_buffer_size_with_format::<Ws2812, Grbw>(1));
+        // Green is sent first, then red, then blue, then white
+        assert_eq!(buf[..4], [0b1000_1000; 4]);
+        assert_eq!(buf[4..8], [0b1110_1110; 4]);
+        assert_eq!(buf[8..12], [0b1000_1110, 0b1110_1000, 0b1000_1110, 0b1110_1000]);
+        assert_eq!(buf[12..], [0b1110_1000; 4]);
+
+        let buffers = ws2812_dma_buffers!(5, Ws2812, Grbw);
+        assert_eq!(buffers.tx_buffer.len(), 5 * 16, "Each RGBW pixel should expand into 16 bytes instead of 12");
+    }
+
+    #[test]
+    fn test_writer_does_not_overflow() {
+        let mut buf = [0; 10];
+        let len = encode::<Ws2812>(&[Rgb::new(0xff, 0xff, 0xff); 2], &mut buf);
+        assert_eq!(len, buf.len());
+    }
+}
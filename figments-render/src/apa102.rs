@@ -0,0 +1,164 @@
+use embedded_hal::spi::SpiBus;
+use rgb::Rgb;
+use smart_leds_trait::SmartLedsWrite;
+
+/// The highest value of the 5 bit global brightness of an APA102 LED
+pub const APA102_MAX_BRIGHTNESS: u8 = 0x1F;
+
+/// A [SmartLedsWrite] target for APA102 and SK9822 strips, which are driven by the data and clock lines of any [SpiBus]
+///
+/// Every LED also has a 5 bit global brightness that dims it in hardware, without losing any of the 8 bit color depth. This is set with [Apa102SpiWriter::set_global_brightness], which is applied on top of the brightness and power limiting of a [PowerManagedWriter](crate::smart_leds::PowerManagedWriter).
+///
+/// Each write sends a start frame of 32 zero bits, then a 32 bit frame for every LED with a header of `0xE0 | brightness` followed by the blue, green and red channels. The end frame is made of zeros that clock the data through the rest of the strip, which needs half a clock for every LED, plus the 32 bits that SK9822 chips need to latch the colors.
+pub struct Apa102SpiWriter<Spi> {
+    spi: Spi,
+    brightness: u8
+}
+
+impl<Spi> Apa102SpiWriter<Spi> {
+    /// Creates a new writer at full global brightness
+    pub const fn new(spi: Spi) -> Self {
+        Self {
+            spi,
+            brightness: APA102_MAX_BRIGHTNESS
+        }
+    }
+
+    /// Sets the 5 bit global brightness of every LED, from 0 to [APA102_MAX_BRIGHTNESS]. Higher values are clamped.
+    pub fn set_global_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness.min(APA102_MAX_BRIGHTNESS);
+    }
+
+    /// Returns the 5 bit global brightness of every LED
+    pub const fn global_brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Unwraps the underlying SPI bus
+    pub fn into_inner(self) -> Spi {
+        self.spi
+    }
+}
+
+impl<Spi: SpiBus<u8>> SmartLedsWrite for Apa102SpiWriter<Spi> {
+    type Error = Spi::Error;
+
+    type Color = Rgb<u8>;
+
+    fn write<T, I>(&mut self, iterator: T) -> Result<(), Self::Error>
+    where
+        T: IntoIterator<Item = I>,
+        I: Into<Self::Color> {
+        self.spi.write(&[0x00; 4])?;
+
+        let header = 0xE0 | self.brightness;
+        let mut count = 0;
+        for pix in iterator {
+            let color = pix.into();
+            self.spi.write(&[header, color.b, color.g, color.r])?;
+            count += 1;
+        }
+
+        // Zeros can never be mistaken for the start of an LED frame, which always begins with three set bits
+        self.spi.write(&[0x00; 4])?;
+        for _ in 0..(count as usize).div_ceil(16) {
+            self.spi.write(&[0x00])?;
+        }
+        self.spi.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_hal::spi::ErrorType;
+
+    use crate::smart_leds::PowerManagedWriter;
+    use crate::power::AsMilliwatts;
+
+    struct MockSpi {
+        written: [u8; 64],
+        count: usize
+    }
+
+    impl Default for MockSpi {
+        fn default() -> Self {
+            Self { written: [0; 64], count: 0 }
+        }
+    }
+
+    impl MockSpi {
+        fn written(&self) -> &[u8] {
+            &self.written[..self.count]
+        }
+    }
+
+    impl ErrorType for MockSpi {
+        type Error = Infallible;
+    }
+
+    impl SpiBus<u8> for MockSpi {
+        fn read(&mut self, _words: &mut [u8]) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+            self.written[self.count..self.count + words.len()].copy_from_slice(words);
+            self.count += words.len();
+            Ok(())
+        }
+
+        fn transfer(&mut self, _read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+            self.write(write)
+        }
+
+        fn transfer_in_place(&mut self, _words: &mut [u8]) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_frame_structure() {
+        let mut writer = Apa102SpiWriter::new(MockSpi::default());
+        writer.set_global_brightness(7);
+        writer.write([Rgb::new(0x11u8, 0x22, 0x33), Rgb::new(0xFF, 0x00, 0x80), Rgb::new(0, 0, 0)]).unwrap();
+
+        assert_eq!(writer.into_inner().written(), &[
+            // Start frame
+            0x00, 0x00, 0x00, 0x00,
+            // Each LED is sent as brightness, blue, green, red
+            0xE7, 0x33, 0x22, 0x11,
+            0xE7, 0x80, 0x00, 0xFF,
+            0xE7, 0x00, 0x00, 0x00,
+            // The SK9822 latch, followed by enough clocks to push the data through 3 LEDs
+            0x00, 0x00, 0x00, 0x00,
+            0x00
+        ]);
+
+        let mut writer = Apa102SpiWriter::new(MockSpi::default());
+        writer.set_global_brightness(200);
+        assert_eq!(writer.global_brightness(), APA102_MAX_BRIGHTNESS, "The brightness should be clamped to 5 bits");
+        writer.write(core::iter::empty::<Rgb<u8>>()).unwrap();
+        assert_eq!(writer.into_inner().written(), &[0x00; 8], "An empty strip should only get the start and end frames");
+    }
+
+    #[test]
+    fn test_power_managed_apa102() {
+        let budget = [Rgb::new(255u8, 255, 255); 3].as_milliwatts() / 2;
+        let mut writer = PowerManagedWriter::new(Apa102SpiWriter::new(MockSpi::default()), budget);
+        writer.write(&[Rgb::new(255u8, 255, 255); 3]).unwrap();
+
+        let spi = writer.into_inner().into_inner();
+        let frames = &spi.written()[4..16];
+        for frame in frames.chunks(4) {
+            assert_eq!(frame[0], 0xFF, "The global brightness should still be at full");
+            let pixel = Rgb::new(frame[3], frame[2], frame[1]);
+            assert!(pixel.r < 255 && pixel.r > 64, "The colors should have been limited by the power budget: {pixel:?}");
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use core::ops::Mul;
+
+use rgb::Rgb;
+
+use figments::{liber8tion::interpolate::Fract8, mappings::linear::LinearSpace, prelude::*};
+
+use crate::{gamma::{GammaCurve, WithGamma}, output::{Brightness, GammaCorrected, Output}};
+
+/// The number of bit-planes in one full binary code modulation cycle, one for each bit of a color channel
+pub const BIT_PLANES: u8 = 8;
+
+/// Hardware that can only turn each color channel of each pixel fully on or off, such as LEDs that are bit-banged over plain GPIO
+pub trait BitPlaneTarget {
+    type Error;
+
+    /// Displays a single bit-plane, and holds it for a length of time proportional to `1 << bit`
+    fn show_plane(&mut self, bit: u8, plane: impl Iterator<Item = Rgb<bool>>) -> Result<(), Self::Error>;
+}
+
+/// Returns the given bit of every color channel, which is one of the bit-planes used for binary code modulation
+///
+/// ```
+/// use figments_render::bcm::bit_plane;
+/// use rgb::Rgb;
+///
+/// let pixels = [Rgb::new(0b0000_0001u8, 0b1000_0000, 0b1000_0001)];
+/// assert!(bit_plane(&pixels, 0).eq([Rgb::new(true, false, true)]));
+/// assert!(bit_plane(&pixels, 7).eq([Rgb::new(false, true, true)]));
+/// ```
+pub fn bit_plane<P: Copy + Into<Rgb<u8>>>(pixels: &[P], bit: u8) -> impl Iterator<Item = Rgb<bool>> + use<'_, P> {
+    let mask = 1 << bit;
+    pixels.iter().map(move |pixel| {
+        let rgb: Rgb<u8> = (*pixel).into();
+        Rgb::new(rgb.r & mask != 0, rgb.g & mask != 0, rgb.b & mask != 0)
+    })
+}
+
+/// The brightness and gamma controls for a [BcmOutput]
+#[derive(Debug)]
+pub struct BcmControls {
+    brightness: Fract8,
+    is_on: bool,
+    gamma_curve: GammaCurve
+}
+
+impl Default for BcmControls {
+    fn default() -> Self {
+        Self {
+            brightness: Fract8::MAX,
+            is_on: true,
+            gamma_curve: GammaCurve::default()
+        }
+    }
+}
+
+impl BcmControls {
+    /// Applies the brightness and gamma curve to a single pixel
+    #[inline]
+    pub fn apply<Color>(&self, pixel: Color) -> Color where Color: WithGamma + Mul<Fract8, Output = Color> {
+        let brightness = if self.is_on { self.brightness } else { Fract8::MIN };
+        (pixel * brightness).with_gamma(&self.gamma_curve)
+    }
+}
+
+impl Brightness for BcmControls {
+    fn set_brightness(&mut self, brightness: Fract8) {
+        self.brightness = brightness;
+    }
+
+    fn set_on(&mut self, is_on: bool) {
+        self.is_on = is_on;
+    }
+}
+
+impl GammaCorrected for BcmControls {
+    fn set_gamma(&mut self, gamma: GammaCurve) {
+        self.gamma_curve = gamma;
+    }
+}
+
+/// An [Output] that fakes grayscale on on/off hardware with binary code modulation
+///
+/// Each commit runs one full cycle of [BIT_PLANES] bit-planes, where each plane is held for twice as long as the previous one. When the cycle is repeated quickly enough, the eye averages the planes back into the full color of each pixel.
+pub struct BcmOutput<'a, T, Pixbuf> {
+    target: T,
+    controls: BcmControls,
+    pixbuf: &'a mut Pixbuf,
+    adjusted: Pixbuf
+}
+
+impl<'a, T, Pixel: Copy, const PIXEL_COUNT: usize> BcmOutput<'a, T, [Pixel; PIXEL_COUNT]> {
+    /// Creates a new output that displays the pixbuf on the given target
+    pub fn new(target: T, pixbuf: &'a mut [Pixel; PIXEL_COUNT]) -> Self {
+        Self {
+            target,
+            controls: BcmControls::default(),
+            adjusted: *pixbuf,
+            pixbuf
+        }
+    }
+
+    pub const fn pixbuf(&mut self) -> &mut [Pixel; PIXEL_COUNT] {
+        self.pixbuf
+    }
+}
+
+impl<'a, T: BitPlaneTarget, Color, const PIXEL_COUNT: usize> Output<'a, LinearSpace> for BcmOutput<'a, T, [Color; PIXEL_COUNT]> where Color: 'a + Copy + Into<Rgb<u8>> + WithGamma + Mul<Fract8, Output = Color> {
+    type Error = T::Error;
+
+    type Controls = BcmControls;
+
+    fn commit(&mut self) -> Result<(), Self::Error> {
+        // Brightness and gamma are applied once up front, instead of once for every plane
+        for (adjusted, pixel) in self.adjusted.iter_mut().zip(self.pixbuf.iter()) {
+            *adjusted = self.controls.apply(*pixel);
+        }
+        for bit in 0..BIT_PLANES {
+            self.target.show_plane(bit, bit_plane(&self.adjusted, bit))?;
+        }
+        Ok(())
+    }
+
+    fn controls(&mut self) -> Option<&mut Self::Controls> {
+        Some(&mut self.controls)
+    }
+}
+
+impl<'a, T, Color, const PIXEL_COUNT: usize> Sample<'a, LinearSpace> for BcmOutput<'a, T, [Color; PIXEL_COUNT]> where Color: 'a {
+    type Output = Color;
+
+    fn sample(&mut self, rect: &Rectangle<LinearSpace>) -> impl Iterator<Item = (Coordinates<LinearSpace>, &'a mut Self::Output)> {
+        self.pixbuf.sample(rect)
+    }
+
+    fn bounds(&self) -> Rectangle<LinearSpace> {
+        self.pixbuf.bounds()
+    }
+
+    fn pixel_count(&self) -> usize {
+        PIXEL_COUNT
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::convert::Infallible;
+
+    /// Accumulates how long each channel of each pixel has been lit, in units of the shortest plane
+    struct MockTarget<const PIXEL_COUNT: usize> {
+        lit_time: [Rgb<u32>; PIXEL_COUNT],
+        total_time: u32
+    }
+
+    impl<const PIXEL_COUNT: usize> Default for MockTarget<PIXEL_COUNT> {
+        fn default() -> Self {
+            Self {
+                lit_time: [Rgb::new(0, 0, 0); PIXEL_COUNT],
+                total_time: 0
+            }
+        }
+    }
+
+    impl<const PIXEL_COUNT: usize> BitPlaneTarget for MockTarget<PIXEL_COUNT> {
+        type Error = Infallible;
+
+        fn show_plane(&mut self, bit: u8, plane: impl Iterator<Item = Rgb<bool>>) -> Result<(), Self::Error> {
+            let weight = 1 << bit;
+            for (lit, pixel) in self.lit_time.iter_mut().zip(plane) {
+                lit.r += pixel.r as u32 * weight;
+                lit.g += pixel.g as u32 * weight;
+                lit.b += pixel.b as u32 * weight;
+            }
+            self.total_time += weight;
+            Ok(())
+        }
+    }
+
+    impl<const PIXEL_COUNT: usize> MockTarget<PIXEL_COUNT> {
+        /// Returns the brightness of each pixel as the eye would see it, averaged over every cycle so far
+        fn perceived(&self) -> [Rgb<u8>; PIXEL_COUNT] {
+            let cycles = self.total_time / 255;
+            core::array::from_fn(|idx| {
+                let lit = self.lit_time[idx];
+                Rgb::new((lit.r / cycles) as u8, (lit.g / cycles) as u8, (lit.b / cycles) as u8)
+            })
+        }
+    }
+
+    #[test]
+    fn test_planes_reconstruct_brightness() {
+        let mut pixbuf: [Rgb<u8>; 256] = core::array::from_fn(|idx| Rgb::new(idx as u8, 255 - idx as u8, (idx as u8).wrapping_mul(37)));
+        let expected = pixbuf;
+        let mut output = BcmOutput::new(MockTarget::default(), &mut pixbuf);
+
+        for _ in 0..3 {
+            output.commit().unwrap();
+        }
+
+        assert_eq!(output.target.total_time, 3 * 255, "Every cycle should add up to the full range of a channel");
+        assert_eq!(output.target.perceived(), expected);
+    }
+
+    #[test]
+    fn test_controls_dim_the_planes() {
+        let mut pixbuf = [Rgb::new(255u8, 128, 0); 4];
+        let mut output = BcmOutput::new(MockTarget::default(), &mut pixbuf);
+
+        output.controls().unwrap().set_brightness(Fract8::from_raw(128));
+        output.commit().unwrap();
+        assert_eq!(output.target.perceived()[0], Rgb::new(255, 128, 0) * Fract8::from_raw(128));
+
+        output.controls().unwrap().set_on(false);
+        output.commit().unwrap();
+        assert_eq!(output.target.lit_time, [Rgb::new(128, 64, 0); 4], "A cycle while turned off should not light anything");
+    }
+}
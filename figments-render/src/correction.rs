@@ -0,0 +1,188 @@
+use rgb::{Bgr, Grb, Rgb};
+
+use figments::{liber8tion::interpolate::Fract8, pixels::{Gray, Rgbw}};
+
+/// Scale factors for each color channel, which correct the white balance of LEDs or tint them to match a light source, like FastLED's `setCorrection` and `setTemperature`
+///
+/// The presets are the same as FastLED's. The LED corrections compensate for green and blue LEDs that are brighter than the red ones, while the color temperatures simulate the color of a light source. Both can be used at once with [ColorCorrection::combined].
+///
+/// ```
+/// use figments_render::correction::{ColorCorrection, WithCorrection};
+/// use rgb::Rgb;
+///
+/// let warm = Rgb::new(255u8, 255, 255).with_correction(&ColorCorrection::TUNGSTEN_40W);
+/// assert_eq!(warm, Rgb::new(255, 197, 143));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorCorrection {
+    /// The scale of the red channel
+    pub r: Fract8,
+    /// The scale of the green channel
+    pub g: Fract8,
+    /// The scale of the blue channel
+    pub b: Fract8
+}
+
+impl Default for ColorCorrection {
+    fn default() -> Self {
+        Self::UNCORRECTED
+    }
+}
+
+/// Multiplies two fractions, rounding so that multiplying by [Fract8::MAX] never changes the other one
+#[inline]
+const fn mul_round(a: Fract8, b: Fract8) -> Fract8 {
+    Fract8::from_raw(((a.to_raw() as u16 * b.to_raw() as u16 + 127) / 255) as u8)
+}
+
+impl ColorCorrection {
+    /// Creates a correction from the scale of each channel
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r: Fract8::from_raw(r), g: Fract8::from_raw(g), b: Fract8::from_raw(b) }
+    }
+
+    const fn from_hex(color: u32) -> Self {
+        Self::new((color >> 16) as u8, (color >> 8) as u8, color as u8)
+    }
+
+    /// Leaves every color as it is
+    pub const UNCORRECTED: Self = Self::from_hex(0xFFFFFF);
+
+    /// The typical correction for SMD5050 LEDs
+    pub const TYPICAL_SMD5050: Self = Self::from_hex(0xFFB0F0);
+    /// The typical correction for LED strips, which are usually built from SMD5050 LEDs
+    pub const TYPICAL_LED_STRIP: Self = Self::from_hex(0xFFB0F0);
+    /// The typical correction for 8mm through hole "pixels"
+    pub const TYPICAL_8MM_PIXEL: Self = Self::from_hex(0xFFE08C);
+    /// The typical correction for strings of pixels, which are usually built from 8mm pixels
+    pub const TYPICAL_PIXEL_STRING: Self = Self::from_hex(0xFFE08C);
+
+    /// The color of a candle, at 1900K
+    pub const CANDLE: Self = Self::from_hex(0xFF9329);
+    /// The color of a 40W tungsten bulb, at 2600K
+    pub const TUNGSTEN_40W: Self = Self::from_hex(0xFFC58F);
+    /// The color of a 100W tungsten bulb, at 2850K
+    pub const TUNGSTEN_100W: Self = Self::from_hex(0xFFD6AA);
+    /// The color of a halogen bulb, at 3200K
+    pub const HALOGEN: Self = Self::from_hex(0xFFF1E0);
+    /// The color of a carbon arc lamp, at 5200K
+    pub const CARBON_ARC: Self = Self::from_hex(0xFFFAF4);
+    /// The color of the sun at noon, at 5400K
+    pub const HIGH_NOON_SUN: Self = Self::from_hex(0xFFFFFB);
+    /// The color of direct sunlight, at 6000K, which leaves every color as it is
+    pub const DIRECT_SUNLIGHT: Self = Self::from_hex(0xFFFFFF);
+    /// The color of an overcast sky, at 7000K
+    pub const OVERCAST_SKY: Self = Self::from_hex(0xC9E2FF);
+    /// The color of a clear blue sky, at 20000K
+    pub const CLEAR_BLUE_SKY: Self = Self::from_hex(0x409CFF);
+
+    /// The color of a warm fluorescent tube
+    pub const WARM_FLUORESCENT: Self = Self::from_hex(0xFFF4E5);
+    /// The color of a standard fluorescent tube
+    pub const STANDARD_FLUORESCENT: Self = Self::from_hex(0xF4FFFA);
+    /// The color of a cool white fluorescent tube
+    pub const COOL_WHITE_FLUORESCENT: Self = Self::from_hex(0xD4EBFF);
+    /// The color of a full spectrum fluorescent tube
+    pub const FULL_SPECTRUM_FLUORESCENT: Self = Self::from_hex(0xFFF4F2);
+    /// The color of a fluorescent grow light
+    pub const GROW_LIGHT_FLUORESCENT: Self = Self::from_hex(0xFFEFF7);
+    /// The color of a black light
+    pub const BLACK_LIGHT_FLUORESCENT: Self = Self::from_hex(0xA700FF);
+    /// The color of a mercury vapor lamp
+    pub const MERCURY_VAPOR: Self = Self::from_hex(0xD8F7FF);
+    /// The color of a sodium vapor lamp
+    pub const SODIUM_VAPOR: Self = Self::from_hex(0xFFD1B2);
+    /// The color of a metal halide lamp
+    pub const METAL_HALIDE: Self = Self::from_hex(0xF2FCFF);
+    /// The color of a high pressure sodium lamp
+    pub const HIGH_PRESSURE_SODIUM: Self = Self::from_hex(0xFFB74C);
+
+    /// Applies both corrections at once, such as an LED correction along with a color temperature
+    pub const fn combined(self, other: ColorCorrection) -> Self {
+        Self { r: mul_round(self.r, other.r), g: mul_round(self.g, other.g), b: mul_round(self.b, other.b) }
+    }
+
+    /// Scales every channel by a brightness, so the correction and the brightness can be applied to a pixel with a single multiply
+    pub const fn scaled(self, brightness: Fract8) -> Self {
+        Self { r: mul_round(self.r, brightness), g: mul_round(self.g, brightness), b: mul_round(self.b, brightness) }
+    }
+}
+
+/// Pixel formats that can have a [ColorCorrection] applied to them
+pub trait WithCorrection {
+    /// Scales each color channel by the matching channel of the correction
+    fn with_correction(self, correction: &ColorCorrection) -> Self;
+}
+
+impl WithCorrection for Rgb<u8> {
+    #[inline]
+    fn with_correction(self, correction: &ColorCorrection) -> Self {
+        Rgb::new(self.r * correction.r, self.g * correction.g, self.b * correction.b)
+    }
+}
+
+impl WithCorrection for Grb<u8> {
+    #[inline]
+    fn with_correction(self, correction: &ColorCorrection) -> Self {
+        Grb::new_grb(self.g * correction.g, self.r * correction.r, self.b * correction.b)
+    }
+}
+
+impl WithCorrection for Bgr<u8> {
+    #[inline]
+    fn with_correction(self, correction: &ColorCorrection) -> Self {
+        Bgr::new_bgr(self.b * correction.b, self.g * correction.g, self.r * correction.r)
+    }
+}
+
+/// The white LED has its own color that the correction doesn't describe, so it is only scaled by the dimmest channel to keep the overall brightness in line with the colors
+impl WithCorrection for Rgbw<u8> {
+    #[inline]
+    fn with_correction(self, correction: &ColorCorrection) -> Self {
+        let white = correction.r.min(correction.g).min(correction.b);
+        Rgbw::new(self.r * correction.r, self.g * correction.g, self.b * correction.b, self.w * white)
+    }
+}
+
+/// Like the white channel of [Rgbw], a monochrome LED is only scaled by the dimmest channel of the correction
+impl WithCorrection for Gray<u8> {
+    #[inline]
+    fn with_correction(self, correction: &ColorCorrection) -> Self {
+        Gray::new(self.v * correction.r.min(correction.g).min(correction.b))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_presets_tint_white() {
+        let white = Rgb::new(255u8, 255, 255);
+        let tungsten = white.with_correction(&ColorCorrection::TUNGSTEN_40W);
+        assert!(tungsten.b < tungsten.g && tungsten.g < tungsten.r, "Tungsten light should be warmer, with less blue than red: {tungsten:?}");
+        let sky = white.with_correction(&ColorCorrection::CLEAR_BLUE_SKY);
+        assert!(sky.r < sky.b, "A clear sky should be cooler, with less red than blue: {sky:?}");
+
+        assert_eq!(white.with_correction(&ColorCorrection::UNCORRECTED), white);
+        assert_eq!(ColorCorrection::default(), ColorCorrection::DIRECT_SUNLIGHT);
+    }
+
+    #[test]
+    fn test_combined_corrections() {
+        let strip = ColorCorrection::TYPICAL_LED_STRIP;
+        assert_eq!(strip.combined(ColorCorrection::UNCORRECTED), strip, "Combining with no correction should not change anything");
+        assert_eq!(strip.scaled(Fract8::MAX), strip);
+        assert_eq!(strip.scaled(Fract8::MIN), ColorCorrection::new(0, 0, 0));
+
+        let warm_strip = strip.combined(ColorCorrection::TUNGSTEN_40W);
+        assert_eq!(warm_strip, ColorCorrection::new(255, 136, 135));
+
+        // Every channel of every format is corrected the same way
+        let color = Rgb::new(200u8, 100, 50);
+        let corrected = color.with_correction(&warm_strip);
+        assert_eq!(Grb::new_grb(100u8, 200, 50).with_correction(&warm_strip), Grb::new_grb(corrected.g, corrected.r, corrected.b));
+        assert_eq!(Bgr::new_bgr(50u8, 100, 200).with_correction(&warm_strip), Bgr::new_bgr(corrected.b, corrected.g, corrected.r));
+        assert_eq!(Rgbw::new(200u8, 100, 50, 255).with_correction(&warm_strip), Rgbw::new(corrected.r, corrected.g, corrected.b, 135));
+    }
+}
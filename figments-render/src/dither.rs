@@ -0,0 +1,76 @@
+use rgb::{Bgr, Grb, Rgb};
+
+use figments::liber8tion::interpolate::Fract8;
+use figments::pixels::{Gray, Rgbw};
+
+use crate::correction::ColorCorrection;
+use crate::gamma::GammaCurve;
+
+/// Pixel formats that can be scaled by a brightness and gamma corrected while carrying the rounding error of each channel over to the next frame
+///
+/// Scaling a dim color truncates each channel, so low brightnesses can only show a few distinct levels. Temporal dithering keeps the remainder that was lost in the previous frame, so the level that is displayed averages out to the exact value over several frames.
+///
+/// This is meant to be the very last step before the pixels are sent to the hardware. The scaled value is gamma corrected by interpolating between the two neighbouring levels of the curve, as the curve would otherwise round them back to the same value.
+pub trait Dither: Copy {
+    /// Scales each channel of the pixel by the matching channel of `scale` and then maps it through `curve`, using and then updating the rounding error that is left over from the previous frame. Each color channel uses its own entry in `error`.
+    ///
+    /// Every frame is within one step of the curve of `pixel.with_correction(scale).with_gamma(curve)`, which is what it averages out to when the scaling doesn't need to round.
+    ///
+    /// Like [WithCorrection](crate::correction::WithCorrection), white and monochrome channels are scaled by the dimmest channel of `scale`.
+    fn scale_dithered(self, scale: &ColorCorrection, curve: &GammaCurve, error: &mut [u8; 4]) -> Self;
+}
+
+/// Scales and gamma corrects a single channel, carrying the remainder of the division over to the next frame
+#[inline]
+fn dither_channel(value: u8, scale: Fract8, curve: &GammaCurve, error: &mut u8) -> u8 {
+    // The same truncated level that scaling without dithering produces, along with how far it is towards the next one
+    let level = value * scale;
+    let remainder = (value as u16 * scale.to_raw() as u16).saturating_sub(level as u16 * 255).min(254) as u8;
+    let exact = curve.fine(level, remainder) as u32 + *error as u32;
+    *error = (exact % 255) as u8;
+    (exact / 255).min(255) as u8
+}
+
+macro_rules! dither_impl {
+    ($color_type:tt $($component:ident $channel:literal),+) => {
+        impl Dither for $color_type<u8> {
+            #[inline]
+            fn scale_dithered(self, scale: &ColorCorrection, curve: &GammaCurve, error: &mut [u8; 4]) -> Self {
+                let scales = [scale.r, scale.g, scale.b, scale.r.min(scale.g).min(scale.b)];
+                Self {
+                    $($component: dither_channel(self.$component, scales[$channel], curve, &mut error[$channel])),+
+                }
+            }
+        }
+    };
+}
+
+dither_impl!(Rgb r 0, g 1, b 2);
+dither_impl!(Grb g 1, r 0, b 2);
+dither_impl!(Bgr b 2, g 1, r 0);
+dither_impl!(Rgbw r 0, g 1, b 2, w 3);
+dither_impl!(Gray v 3);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dithering_averages_to_exact_value() {
+        let mut error = [0; 4];
+        // 10 * 100 / 255 is 3.92, which plain scaling truncates to 3
+        let scale = ColorCorrection::new(100, 100, 100);
+        let frames: [Rgbw<u8>; 255] = core::array::from_fn(|_| Rgbw::new(10, 0, 255, 1).scale_dithered(&scale, &GammaCurve::default(), &mut error));
+        let total = frames.iter().fold([0u32; 4], |total, pixel| [total[0] + pixel.r as u32, total[1] + pixel.g as u32, total[2] + pixel.b as u32, total[3] + pixel.w as u32]);
+        assert_eq!(total, [1000, 0, 25500, 100], "Every channel should add up to exactly value * scale over 255 frames");
+        assert!(frames.iter().all(|pixel| pixel.r == 3 || pixel.r == 4), "Each frame should be within one level of the exact value");
+    }
+
+    #[test]
+    fn test_dithering_scales_each_channel() {
+        let mut error = [0; 4];
+        let scale = ColorCorrection::new(255, 128, 0);
+        assert_eq!(Rgb::new(200u8, 200, 200).scale_dithered(&scale, &GammaCurve::default(), &mut error), Rgb::new(200, 100, 0));
+        assert_eq!(Rgbw::new(0u8, 0, 0, 200).scale_dithered(&scale, &GammaCurve::default(), &mut [0; 4]).w, 0, "The white channel should follow the dimmest channel");
+    }
+}
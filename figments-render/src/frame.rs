@@ -0,0 +1,149 @@
+use figments::prelude::*;
+
+#[cfg(feature="smart-leds")]
+use smart_leds_trait::SmartLedsWrite;
+#[cfg(feature="smart-leds")]
+use crate::{output::HardwareColor, smart_leds::PowerManagedWriter};
+
+/// Anything that can send a finished pixbuf out to the hardware
+///
+/// This is implemented for [PowerManagedWriter], and for any closure that takes the pixbuf and returns a [Result].
+pub trait FrameWriter<Pixbuf: ?Sized> {
+    type Error;
+
+    /// Writes out the pixbuf
+    fn write_frame(&mut self, pixbuf: &Pixbuf) -> Result<(), Self::Error>;
+}
+
+impl<Pixbuf: ?Sized, E, F: FnMut(&Pixbuf) -> Result<(), E>> FrameWriter<Pixbuf> for F {
+    type Error = E;
+
+    fn write_frame(&mut self, pixbuf: &Pixbuf) -> Result<(), Self::Error> {
+        self(pixbuf)
+    }
+}
+
+#[cfg(feature="smart-leds")]
+impl<T: SmartLedsWrite, Pixbuf: AsRef<[T::Color]> + ?Sized> FrameWriter<Pixbuf> for PowerManagedWriter<'_, T> where T::Color: HardwareColor {
+    type Error = T::Error;
+
+    fn write_frame(&mut self, pixbuf: &Pixbuf) -> Result<(), Self::Error> {
+        self.write(pixbuf)
+    }
+}
+
+/// Bundles a [BufferedSurfacePool], the pixbuf it renders into, and the writer that sends the pixbuf out to hardware, so each frame of the application loop is a single call to [Frame::render]
+///
+/// Every frame commits the pending surface changes, clears the pixbuf, composites the surfaces, and then writes out the pixbuf, in that order. The coordinate mapping comes from the [Sample] implementation of the pixbuf.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments_render::frame::Frame;
+/// use rgb::Rgb;
+///
+/// let mut pool: BufferedSurfacePool<(), _, Rgb<u8>> = BufferedSurfacePool::default();
+/// let mut pixbuf = [Rgb::new(0u8, 0, 0); 8];
+/// let mut frame = Frame::new(&mut pool, &mut pixbuf, |pixels: &[Rgb<u8>; 8]| {
+///     assert_eq!(pixels[0], Rgb::new(255, 0, 0));
+///     Ok::<_, ()>(())
+/// });
+///
+/// let _surface = SurfaceBuilder::build(frame.pool())
+///     .shader(|_: &_, _: &()| Rgb::new(255, 0, 0))
+///     .finish()
+///     .unwrap();
+/// frame.render(&()).unwrap();
+/// ```
+pub struct Frame<'a, U, Space: CoordinateSpace, Pixel, SH, Pixbuf: ?Sized, W> {
+    pool: &'a mut BufferedSurfacePool<U, Space, Pixel, SH>,
+    pixbuf: &'a mut Pixbuf,
+    writer: W
+}
+
+impl<'a, U: 'static, Space: CoordinateSpace, Pixel, SH, Pixbuf: ?Sized, W> Frame<'a, U, Space, Pixel, SH, Pixbuf, W> {
+    /// Creates a new frame loop out of a surface pool, a pixbuf, and a writer
+    pub fn new(pool: &'a mut BufferedSurfacePool<U, Space, Pixel, SH>, pixbuf: &'a mut Pixbuf, writer: W) -> Self {
+        Self {
+            pool,
+            pixbuf,
+            writer
+        }
+    }
+
+    /// Returns the surface pool, for creating new surfaces
+    pub fn pool(&mut self) -> &mut BufferedSurfacePool<U, Space, Pixel, SH> {
+        self.pool
+    }
+
+    /// Returns the writer, for adjusting its controls or reading its stats
+    pub fn writer(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Commits any pending surface changes, then clears the pixbuf and composites the surfaces into it, without writing anything out
+    pub fn draw<HwPixel>(&mut self, uniforms: &U)
+        where
+            Pixel: Copy + Fract8Ops + 'static,
+            SH: Shader<U, Space, Pixel>,
+            BufferedSurfacePool<U, Space, Pixel, SH>: RenderSource<U, Space, Pixel, HwPixel>,
+            Pixbuf: for<'b> Sample<'b, Space, Output = HwPixel>,
+            HwPixel: Default + 'static {
+        self.pool.commit();
+        self.pool.render_frame(&mut *self.pixbuf, uniforms);
+    }
+
+    /// Writes out the pixbuf as it was left by the previous [Frame::draw]
+    pub fn flush(&mut self) -> Result<(), W::Error> where W: FrameWriter<Pixbuf> {
+        self.writer.write_frame(self.pixbuf)
+    }
+
+    /// Draws a complete frame and writes it out
+    pub fn render<HwPixel>(&mut self, uniforms: &U) -> Result<(), W::Error>
+        where
+            Pixel: Copy + Fract8Ops + 'static,
+            SH: Shader<U, Space, Pixel>,
+            BufferedSurfacePool<U, Space, Pixel, SH>: RenderSource<U, Space, Pixel, HwPixel>,
+            Pixbuf: for<'b> Sample<'b, Space, Output = HwPixel>,
+            HwPixel: Default + 'static,
+            W: FrameWriter<Pixbuf> {
+        self.draw(uniforms);
+        self.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use figments::liber8tion::interpolate::Fract8;
+    use rgb::Rgb;
+
+    #[test]
+    fn test_frame_clears_renders_and_writes() {
+        let mut pool: BufferedSurfacePool<u8, _, Rgb<u8>> = BufferedSurfacePool::default();
+        let mut pixbuf = [Rgb::new(1u8, 2, 3); 8];
+        let mut written = [None; 4];
+        let mut writes = 0;
+        let mut frame = Frame::new(&mut pool, &mut pixbuf, |pixels: &[Rgb<u8>; 8]| {
+            written[writes] = Some(*pixels);
+            writes += 1;
+            Ok::<_, ()>(())
+        });
+
+        // Surfaces that are created after the frame still get committed before the first render
+        let mut surface = SurfaceBuilder::build(frame.pool())
+            .rect(Rectangle::new_from_coordinates(0, 0, 3, 0))
+            .shader(|_: &_, value: &u8| Rgb::new(*value, 0, 0))
+            .finish()
+            .unwrap();
+        frame.render(&10).unwrap();
+
+        surface.set_opacity(Fract8::MIN);
+        frame.render(&20).unwrap();
+
+        let red = Rgb::new(10, 0, 0);
+        let black = Rgb::new(0, 0, 0);
+        assert_eq!(written[0], Some([red, red, red, red, black, black, black, black]), "The leftover pixels should be cleared before the first frame");
+        assert_eq!(written[1], Some([black; 8]), "The opacity change should be committed before the second frame");
+        assert_eq!(written[2], None);
+    }
+}
@@ -2,22 +2,54 @@ use rgb::{Bgr, Grb, Rgb};
 use core::array;
 use core::ops::Index;
 
+use figments::liber8tion::interpolate::{Fract8, Fract8Ops};
+use figments::pixels::{Gray, Rgbw};
+
+use crate::correction::ColorCorrection;
+
 #[cfg(feature="micromath")]
 use micromath::F32Ext;
 
 #[derive(Debug)]
-pub struct GammaCurve([u8; 256]);
+pub struct GammaCurve {
+    levels: [u8; 256],
+    /// The same curve in 1/255ths of a level, for [Dither](crate::dither::Dither) to produce the levels in between
+    fine: [u16; 256]
+}
 
 impl GammaCurve {
     pub fn new(gamma: f32) -> Self {
-        Self(array::from_fn(|x| {
-            Self::gamma_for_value(x as u8, gamma)
-        }))
+        Self {
+            levels: array::from_fn(|x| Self::gamma_for_value(x as u8, gamma)),
+            fine: array::from_fn(|x| Self::fine_gamma_for_value(x as u8, gamma))
+        }
+    }
+
+    /// Creates a new curve that is interpolated between this curve and another, where a frac of 0 is this curve and 255 is the other curve
+    pub fn blend(&self, other: &GammaCurve, frac: Fract8) -> GammaCurve {
+        Self {
+            levels: array::from_fn(|x| self.levels[x].lerp8by8(other.levels[x], frac)),
+            fine: array::from_fn(|x| {
+                let (from, to) = (self.fine[x] as i32, other.fine[x] as i32);
+                (from + (to - from) * frac.to_raw() as i32 / 255) as u16
+            })
+        }
+    }
+
+    /// Returns the output of the curve for an input of `value` plus `remainder`/255ths of a level, in 1/255ths of a level
+    pub(crate) fn fine(&self, value: u8, remainder: u8) -> u16 {
+        let low = self.fine[value as usize] as i32;
+        let high = self.fine[value.saturating_add(1) as usize] as i32;
+        (low + ((high - low) * remainder as i32 + 127).div_euclid(255)) as u16
     }
 
     fn gamma_for_value(value: u8, gamma: f32) -> u8 {
         ((value as f32 / 255f32).powf(gamma) * 255f32 + 0.5) as u8
     }
+
+    fn fine_gamma_for_value(value: u8, gamma: f32) -> u16 {
+        ((value as f32 / 255f32).powf(gamma) * 255f32 * 255f32 + 0.5) as u16
+    }
 }
 
 impl Default for GammaCurve {
@@ -30,7 +62,7 @@ impl Index<usize> for GammaCurve {
     type Output = u8;
 
     fn index(&self, index: usize) -> &Self::Output {
-        &self.0[index]
+        &self.levels[index]
     }
 }
 
@@ -56,8 +88,87 @@ impl WithGamma for Bgr<u8> {
     }
 }
 
+impl WithGamma for Rgbw<u8> {
+    fn with_gamma(self, curve: &GammaCurve) -> Self {
+        Rgbw::new(curve[self.r as usize], curve[self.g as usize], curve[self.b as usize], curve[self.w as usize])
+    }
+}
+
+impl WithGamma for Gray<u8> {
+    fn with_gamma(self, curve: &GammaCurve) -> Self {
+        Gray::new(curve[self.v as usize])
+    }
+}
+
+/// Maps a scale factor through the curve, so that scaling a gamma corrected pixel by it has the same result as scaling the pixel by the original factor before gamma correction. Apart from rounding, this holds for the power curves made by [GammaCurve::new].
+impl WithGamma for ColorCorrection {
+    fn with_gamma(self, curve: &GammaCurve) -> Self {
+        ColorCorrection::new(curve[self.r.to_raw() as usize], curve[self.g.to_raw() as usize], curve[self.b.to_raw() as usize])
+    }
+}
+
 impl<T: WithGamma + Copy, const SIZE: usize> WithGamma for [T; SIZE] {
     fn with_gamma(self, curve: &GammaCurve) -> Self {
         array::from_fn(|x| { self[x].with_gamma(curve) })
     }
-}
\ No newline at end of file
+}
+
+/// Gamma correction that is applied in place, for buffers whose size is only known at runtime
+pub trait ApplyGamma {
+    fn apply_gamma(&mut self, curve: &GammaCurve);
+}
+
+impl<T: WithGamma + Copy> ApplyGamma for [T] {
+    fn apply_gamma(&mut self, curve: &GammaCurve) {
+        for pixel in self.iter_mut() {
+            *pixel = pixel.with_gamma(curve);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_blend_lands_between_curves() {
+        let identity = GammaCurve::new(1.0);
+        let corrected = GammaCurve::new(2.2);
+        let blended = identity.blend(&corrected, Fract8::from_raw(128));
+
+        for x in [16, 64, 128, 192, 240] {
+            assert!(blended[x] < identity[x] && blended[x] > corrected[x], "blended[{x}]={} is not between {} and {}", blended[x], identity[x], corrected[x]);
+        }
+
+        // The ends of the curves are identical, so blending does not change them
+        assert_eq!(blended[0], 0);
+        assert_eq!(blended[255], 255);
+    }
+
+    #[test]
+    fn test_blend_extremes() {
+        let identity = GammaCurve::new(1.0);
+        let corrected = GammaCurve::new(2.2);
+        assert_eq!(identity.blend(&corrected, Fract8::MIN).levels, identity.levels);
+        assert_eq!(identity.blend(&corrected, Fract8::MAX).levels, corrected.levels);
+    }
+
+    #[test]
+    fn test_apply_gamma_in_place() {
+        let original: [Rgb<u8>; 5] = array::from_fn(|x| Rgb::new(x as u8 * 60, 128, 255 - x as u8 * 60));
+        let mut storage = original;
+        let pixels: &mut [Rgb<u8>] = &mut storage[1..4];
+
+        pixels.apply_gamma(&GammaCurve::default());
+        assert_eq!(storage, original, "The identity curve should not change anything");
+
+        let corrected = GammaCurve::new(2.2);
+        storage[1..4].apply_gamma(&corrected);
+        assert_eq!(storage[0], original[0], "Pixels outside the slice should not be touched");
+        assert_eq!(storage[4], original[4], "Pixels outside the slice should not be touched");
+        for x in 1..4 {
+            assert_eq!(storage[x], original[x].with_gamma(&corrected));
+        }
+        assert_ne!(storage[1..4], original[1..4]);
+    }
+}
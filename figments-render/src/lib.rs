@@ -1,6 +1,17 @@
 #![no_std]
 
+#[cfg(feature="alloc")]
+extern crate alloc;
+
 pub mod power;
 pub mod gamma;
+pub mod correction;
+pub mod dither;
+pub mod bcm;
 pub mod output;
-pub mod smart_leds;
\ No newline at end of file
+pub mod smart_leds;
+#[cfg(feature="apa102")]
+pub mod apa102;
+pub mod stats;
+#[cfg(feature="alloc")]
+pub mod frame;
\ No newline at end of file
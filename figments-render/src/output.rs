@@ -2,7 +2,19 @@
 
 use figments::{liber8tion::interpolate::Fract8, prelude::*};
 
-use crate::gamma::GammaCurve;
+use core::ops::Mul;
+
+use crate::correction::WithCorrection;
+use crate::dither::Dither;
+use crate::gamma::{GammaCurve, WithGamma};
+use crate::power::AsMilliwatts;
+
+/// The pixel formats that can be sent out to hardware, with brightness, gamma correction, and power management applied along the way
+///
+/// This is implemented for every type that meets the requirements, so it never needs to be implemented by hand. Using the same format for the pixbuf and the hardware driver, such as `Grb<u8>` for most WS2812 strips, avoids any conversion at all. Shaders that produce `Rgb<u8>` can still draw into any of these formats, as they can all be created from `Rgb<u8>`.
+pub trait HardwareColor: Copy + From<Rgb<u8>> + AsMilliwatts + WithGamma + WithCorrection + Dither + Mul<Fract8, Output = Self> {}
+
+impl<T> HardwareColor for T where T: Copy + From<Rgb<u8>> + AsMilliwatts + WithGamma + WithCorrection + Dither + Mul<Fract8, Output = T> {}
 
 pub trait Brightness {
     fn set_brightness(&mut self, brightness: Fract8);
@@ -47,4 +59,21 @@ impl Brightness for NullControls {
 #[allow(unused_variables)]
 impl GammaCorrected for NullControls {
     fn set_gamma(&mut self, gamma: GammaCurve) {}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rgb::{Bgr, Grb};
+
+    fn assert_hardware_color<T: HardwareColor>() {}
+
+    #[test]
+    fn test_common_formats_are_hardware_colors() {
+        assert_hardware_color::<Rgb<u8>>();
+        assert_hardware_color::<Grb<u8>>();
+        assert_hardware_color::<Bgr<u8>>();
+        assert_hardware_color::<figments::pixels::Rgbw<u8>>();
+        assert_hardware_color::<figments::pixels::Gray<u8>>();
+    }
+}
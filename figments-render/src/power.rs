@@ -1,10 +1,15 @@
-use figments::liber8tion::interpolate::Fract8;
+use figments::{liber8tion::interpolate::Fract8, pixels::{Gray, Rgbw}};
 use rgb::{Grb, Rgb, Bgr};
 
 pub trait AsMilliwatts {
     fn as_milliwatts(&self) -> u32;
 }
 
+/// The most that [AsMilliwatts] can under-report the power of a single pixel by
+///
+/// Each channel is truncated to a whole milliwatt, which loses just under 1mW, and the widest pixel format is [Rgbw] with 4 channels.
+pub const TRUNCATION_MW_PER_PIXEL: u32 = 4;
+
 impl<T: Copy> AsMilliwatts for Bgr<T> where Rgb<T>: AsMilliwatts {
     fn as_milliwatts(&self) -> u32 {
         Rgb::new(self.r, self.g, self.b).as_milliwatts()
@@ -33,6 +38,35 @@ impl<T: Into<u32> + Copy> AsMilliwatts for Rgb<T> {
     }
 }
 
+/// The power drawn by the white LED of an SK6812 RGBW pixel at full brightness, which is a little more than any of the colors
+pub const SK6812_WHITE_MW: u32 = 18 * 5; //< 18mA @ 5v = 90mW
+
+/// Estimates the power drawn by an RGBW pixel, for strips where the white LED draws `white_mw` at full brightness
+///
+/// The [AsMilliwatts] implementation for [Rgbw] uses [SK6812_WHITE_MW].
+pub fn rgbw_milliwatts<T: Into<u32> + Copy>(pixel: &Rgbw<T>, white_mw: u32) -> u32 {
+    let white = (pixel.w.into() * white_mw).wrapping_shr(8);
+
+    Rgb::new(pixel.r, pixel.g, pixel.b).as_milliwatts() + white
+}
+
+impl<T: Into<u32> + Copy> AsMilliwatts for Rgbw<T> {
+    fn as_milliwatts(&self) -> u32 {
+        rgbw_milliwatts(self, SK6812_WHITE_MW)
+    }
+}
+
+/// The power drawn by a single color LED at full brightness, such as on warm white strips
+pub const MONOCHROME_MW: u32 = 20 * 5; //< 20mA @ 5v = 100mW
+
+impl<T: Into<u32> + Copy> AsMilliwatts for Gray<T> {
+    fn as_milliwatts(&self) -> u32 {
+        const DARK_MW : u32  =      5; //<  1mA @ 5v =  5mW
+
+        (self.v.into() * MONOCHROME_MW).wrapping_shr(8) + DARK_MW
+    }
+}
+
 impl<T> AsMilliwatts for [T] where T: AsMilliwatts {
     fn as_milliwatts(&self) -> u32 {
         self.iter().map(|p| { p.as_milliwatts() }).sum()
@@ -54,4 +88,38 @@ pub fn brightness_for_mw(total_mw: u32, target: Fract8, max_power: u32) -> Fract
     } else {
         target
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The largest amount that truncation loses from a pixel with a single lit channel, in 1/256ths of a milliwatt
+    fn worst_truncation(pixel: impl Fn(u32) -> (u32, u32)) -> u32 {
+        (0..=255).map(|value| {
+            let (truncated, exact) = pixel(value);
+            exact - truncated * 256
+        }).max().unwrap()
+    }
+
+    #[test]
+    fn test_truncation_fits_in_reserve() {
+        // Scaling a channel up by 256 makes the estimate exact, apart from the dark power that is added on top
+        let dark = Rgb::new(0u32, 0, 0).as_milliwatts();
+        let channels: [fn(u32) -> Rgbw<u32>; 4] = [
+            |v| Rgbw::new(v, 0, 0, 0),
+            |v| Rgbw::new(0, v, 0, 0),
+            |v| Rgbw::new(0, 0, v, 0),
+            |v| Rgbw::new(0, 0, 0, v)
+        ];
+        let total: u32 = channels.iter().map(|channel| worst_truncation(|value| {
+            let wide = channel(value * 256);
+            (channel(value).as_milliwatts(), wide.as_milliwatts() - dark + dark * 256)
+        })).sum();
+
+        assert!(total < TRUNCATION_MW_PER_PIXEL * 256, "An RGBW pixel can lose {total}/256mW, which exceeds the reserve");
+        assert!(total > (TRUNCATION_MW_PER_PIXEL - 1) * 256, "An RGBW pixel only loses {total}/256mW, so the reserve is larger than it needs to be");
+
+        let mono = worst_truncation(|value| (Gray::new(value).as_milliwatts(), Gray::new(value * 256).as_milliwatts() - dark + dark * 256));
+        assert!(mono < 256, "A single channel should lose less than 1mW");
+    }
+}
@@ -1,4 +1,4 @@
-use core::{marker::Copy, ops::Mul};
+use core::{marker::Copy, ops::{Mul, Range}};
 use core::convert::AsRef;
 use core::result::Result;
 use core::iter::Iterator;
@@ -7,36 +7,165 @@ use smart_leds_trait::{SmartLedsWrite, SmartLedsWriteAsync};
 
 use figments::{liber8tion::interpolate::Fract8, mappings::linear::LinearSpace, prelude::*};
 
-use crate::{gamma::{GammaCurve, WithGamma}, output::{Brightness, GammaCorrected, Output, OutputAsync}, power::*};
+use crate::{correction::{ColorCorrection, WithCorrection}, dither::Dither, gamma::{GammaCurve, WithGamma}, output::{Brightness, GammaCorrected, HardwareColor, Output, OutputAsync}, power::*};
 
+#[cfg(feature="alloc")]
+use alloc::vec::Vec;
+
+/// Where [PowerControls] keeps the rounding error of every channel of every pixel from the previous frame, when dithering is turned on
+#[derive(Debug)]
+enum DitherErrors<'a> {
+    /// Allocated by [PowerControls::set_dither], and resized to fit the pixbuf
+    #[cfg(feature="alloc")]
+    Owned(Vec<[u8; 4]>),
+    /// Provided by the caller with [PowerControls::set_dither_buffer], along with the number of pixels in the last frame so it can be reset when that changes
+    Borrowed(&'a mut [[u8; 4]], usize)
+}
+
+impl DitherErrors<'_> {
+    /// Returns the errors for a frame with the given number of pixels, resetting them first if the previous frame was a different size
+    fn for_pixels(&mut self, pixel_count: usize) -> &mut [[u8; 4]] {
+        match self {
+            #[cfg(feature="alloc")]
+            DitherErrors::Owned(errors) => {
+                if errors.len() != pixel_count {
+                    errors.clear();
+                    errors.resize(pixel_count, [0; 4]);
+                }
+                errors
+            },
+            DitherErrors::Borrowed(errors, len) => {
+                if *len != pixel_count {
+                    errors.fill([0; 4]);
+                    *len = pixel_count;
+                }
+                errors
+            }
+        }
+    }
+}
+
+/// Applies a scale and gamma curve to every pixel, dithering the ones that have an entry in `errors`
+fn scale_pixels<'b, Color: Dither + WithCorrection + WithGamma>(pixels: &'b [Color], scale: ColorCorrection, gamma_curve: &'b GammaCurve, errors: Option<&'b mut [[u8; 4]]>) -> impl Iterator<Item = Color> + 'b {
+    let mut errors = errors.map(|errors| errors.iter_mut());
+    pixels.iter().map(move |x| {
+        match errors.as_mut().and_then(Iterator::next) {
+            Some(error) => x.scale_dithered(&scale, gamma_curve, error),
+            None => x.with_correction(&scale).with_gamma(gamma_curve)
+        }
+    })
+}
+
+/// The brightness, color correction, gamma curve, and power budget that are applied to every frame on its way out to the hardware
+///
+/// The lifetime is that of the buffer given to [PowerControls::set_dither_buffer], which is `'static` when it isn't used.
 #[derive(Debug)]
-pub struct PowerControls {
+pub struct PowerControls<'a> {
     max_mw: u32,
     brightness: Fract8,
     is_on: bool,
     gamma_curve: GammaCurve,
-    cur_mw: u32
+    correction: ColorCorrection,
+    cur_mw: u32,
+    overhead_mw: u32,
+    /// The rounding error of every channel of every pixel from the previous frame, when dithering is turned on
+    dither_error: Option<DitherErrors<'a>>
 }
 
-impl PowerControls {
+impl<'a> PowerControls<'a> {
     pub fn new(max_mw: u32) -> Self {
         Self {
             max_mw,
             brightness: Fract8::MAX,
             is_on: true,
             gamma_curve: GammaCurve::default(),
-            cur_mw: 0
+            correction: ColorCorrection::UNCORRECTED,
+            cur_mw: 0,
+            overhead_mw: 0,
+            dither_error: None
         }
     }
 
-    pub fn iter_brightness<'a, Color, P: AsRef<[Color]> + ?Sized>(&'a mut self, pixbuf: &'a P) -> impl Iterator<Item = Color> + use<'a, Color, P> where Color: 'a + Copy + WithGamma + AsMilliwatts + Mul<Fract8, Output = Color> {
-        self.cur_mw = pixbuf.as_ref().iter().map(|x| { x.with_gamma(&self.gamma_curve).as_milliwatts() }).sum();
-        let b = brightness_for_mw(self.cur_mw, self.brightness, self.max_mw);
-        pixbuf.as_ref().iter().map(move |x| { x.with_gamma(&self.gamma_curve) * b })
+    /// Turns temporal dithering on or off, which hides the steps between dim brightness levels by carrying the rounding error of each pixel over to the next frame. See [Dither].
+    ///
+    /// The errors are tracked for every pixel, so they are reset whenever the number of pixels being written changes. Dithering only looks right when frames are written at a steady, fast rate.
+    #[cfg(feature="alloc")]
+    pub fn set_dither(&mut self, dither: bool) {
+        self.dither_error = match (dither, self.dither_error.take()) {
+            (true, Some(errors)) => Some(errors),
+            (true, None) => Some(DitherErrors::Owned(Vec::new())),
+            (false, _) => None
+        };
+    }
+
+    /// Turns temporal dithering on with a buffer for the rounding errors that is provided by the caller, such as an array on the stack or from a `StaticCell`, so it also works without an allocator. Passing None turns dithering off again.
+    ///
+    /// The buffer needs one entry for every pixel, and any pixels past the end of it are not dithered. Like [PowerControls::set_dither], it is reset whenever the number of pixels being written changes.
+    pub fn set_dither_buffer(&mut self, errors: Option<&'a mut [[u8; 4]]>) {
+        self.dither_error = errors.map(|errors| DitherErrors::Borrowed(errors, 0));
+    }
+
+    /// Sets the fixed amount of power used by everything other than the LEDs, such as the microcontroller, which is subtracted from the power budget
+    pub fn set_overhead_mw(&mut self, overhead_mw: u32) {
+        self.overhead_mw = overhead_mw;
+    }
+
+    /// Sets the [ColorCorrection] that every pixel is scaled by, along with the brightness. Use [ColorCorrection::combined] to correct the LEDs and set a color temperature at the same time.
+    pub fn set_correction(&mut self, correction: ColorCorrection) {
+        self.correction = correction;
+    }
+
+    /// Returns the power budget that is available to the LEDs, after subtracting the fixed overhead
+    pub const fn available_mw(&self) -> u32 {
+        self.max_mw.saturating_sub(self.overhead_mw)
+    }
+
+    /// Applies the brightness, power limiting, and gamma correction to a pixbuf, producing the final pixels to be sent to the hardware.
+    ///
+    /// The pipeline is applied in the order of color correction and brightness -> gamma. Power is measured after gamma correction, as that is what the LEDs actually draw current for.
+    ///
+    /// When dithering is turned on, each pixel goes through the same pipeline, but alternates between the neighbouring levels of the gamma curve to produce the levels in between the ones it would otherwise round to. See [Dither].
+    pub fn iter_brightness<'b, Color, P: AsRef<[Color]> + ?Sized>(&'b mut self, pixbuf: &'b P) -> impl Iterator<Item = Color> + use<'a, 'b, Color, P> where Color: 'b + Copy + WithGamma + WithCorrection + AsMilliwatts + Dither + Mul<Fract8, Output = Color> {
+        let pixels = pixbuf.as_ref();
+        let b = self.limit_brightness(pixels);
+        let errors = self.dither_error.as_mut().map(|errors| errors.for_pixels(pixels.len()));
+        // The brightness is folded into the correction, so both are applied with one multiply
+        scale_pixels(pixels, self.correction.scaled(b), &self.gamma_curve, errors)
+    }
+
+    /// Calculates the highest brightness that a set of pixels can be displayed at without exceeding the power budget
+    pub fn limit_brightness<Color>(&mut self, pixels: &[Color]) -> Fract8 where Color: Copy + WithGamma + WithCorrection + AsMilliwatts + Mul<Fract8, Output = Color> {
+        let scale = self.correction.scaled(self.brightness);
+        let (full_mw, requested_mw) = pixels.iter().fold((0, 0), |(full, requested), x| {
+            (
+                full + x.with_gamma(&self.gamma_curve).as_milliwatts(),
+                requested + x.with_correction(&scale).with_gamma(&self.gamma_curve).as_milliwatts()
+            )
+        });
+        self.cur_mw = full_mw;
+
+        // LEDs draw a small amount of power even when they are dark, which can't be reduced by lowering the brightness
+        let dark_mw = pixels.first().map_or(0, |x| (*x * Fract8::MIN).as_milliwatts()) * pixels.len() as u32;
+        // The estimates truncate each channel, so the power of the requested pixels can be under-reported by up to TRUNCATION_MW_PER_PIXEL. Scaling the brightness down scales that error along with the rest of the power, so the scaled down pixels never draw more than the reserve over the budget.
+        let lit_mw = requested_mw.saturating_sub(dark_mw);
+        let available_mw = self.available_mw().saturating_sub(dark_mw + TRUNCATION_MW_PER_PIXEL * pixels.len() as u32);
+
+        // Scaling down the brightness before gamma correction reduces the power by at least as much, since gamma curves only ever darken
+        if lit_mw > available_mw {
+            Fract8::from_raw((self.brightness.to_raw() as u32 * available_mw / lit_mw) as u8)
+        } else {
+            self.brightness
+        }
+    }
+
+    /// Applies a brightness from [PowerControls::limit_brightness], the color correction, and the gamma curve to a single pixel
+    #[inline]
+    pub fn apply<Color>(&self, pixel: Color, brightness: Fract8) -> Color where Color: WithGamma + WithCorrection {
+        pixel.with_correction(&self.correction.scaled(brightness)).with_gamma(&self.gamma_curve)
     }
 }
 
-impl Brightness for PowerControls {
+impl Brightness for PowerControls<'_> {
     fn set_brightness(&mut self, brightness: Fract8) {
         self.brightness = brightness;
     }
@@ -46,19 +175,19 @@ impl Brightness for PowerControls {
     }
 }
 
-impl GammaCorrected for PowerControls {
+impl GammaCorrected for PowerControls<'_> {
     fn set_gamma(&mut self, gamma: GammaCurve) {
         self.gamma_curve = gamma
     }
 }
 
 #[derive(Debug)]
-pub struct PowerManagedWriter<T> {
+pub struct PowerManagedWriter<'a, T> {
     target: T,
-    controls: PowerControls
+    controls: PowerControls<'a>
 }
 
-impl<T> PowerManagedWriter<T> {
+impl<'a, T> PowerManagedWriter<'a, T> {
     pub fn new(target: T, max_mw: u32) -> Self {
         Self {
             target,
@@ -66,7 +195,7 @@ impl<T> PowerManagedWriter<T> {
         }
     }
 
-    pub fn write<P: AsRef<[T::Color]> + ?Sized>(&mut self, pixbuf: &P) -> Result<(), T::Error> where T: SmartLedsWrite, T::Color: Mul<Fract8, Output = T::Color> + Copy + WithGamma + AsMilliwatts + core::fmt::Debug {
+    pub fn write<P: AsRef<[T::Color]> + ?Sized>(&mut self, pixbuf: &P) -> Result<(), T::Error> where T: SmartLedsWrite, T::Color: HardwareColor {
         if self.controls.is_on {
             self.target.write(self.controls.iter_brightness(pixbuf))
         } else {
@@ -75,7 +204,7 @@ impl<T> PowerManagedWriter<T> {
     }
 
 
-    pub async fn write_async<P: AsRef<[T::Color]> + ?Sized>(&mut self, pixbuf: &P) -> Result<(), T::Error> where T: SmartLedsWriteAsync, T::Color: Mul<Fract8, Output = T::Color> + Copy + WithGamma + AsMilliwatts + core::fmt::Debug {
+    pub async fn write_async<P: AsRef<[T::Color]> + ?Sized>(&mut self, pixbuf: &P) -> Result<(), T::Error> where T: SmartLedsWriteAsync, T::Color: HardwareColor {
         if self.controls.is_on {
             self.target.write(self.controls.iter_brightness(pixbuf)).await
         } else {
@@ -83,18 +212,31 @@ impl<T> PowerManagedWriter<T> {
         }
     }
 
-    pub fn controls(&mut self) -> &mut PowerControls {
+    pub fn controls(&mut self) -> &mut PowerControls<'a> {
         &mut self.controls
     }
 
+    /// Unwraps the underlying [SmartLedsWrite] target
+    pub fn into_inner(self) -> T {
+        self.target
+    }
+
+    /// Sets the fixed amount of power used by everything other than the LEDs, such as the microcontroller, which is subtracted from the power budget
+    pub fn set_overhead_mw(&mut self, overhead_mw: u32) {
+        self.controls.set_overhead_mw(overhead_mw);
+    }
+
     /// Returns the total power required to display the previous write at full brightness. This is /not/ the actual power consumption, only a theoretical maximum useful for designing power supplies.
     pub const fn max_mw(&self) -> u32 {
         self.controls.cur_mw
     }
 }
 
+/// An [Output] that renders into a pixbuf, then writes it out to any [SmartLedsWrite] target such as the `SmartLedsAdapter` from `esp-hal-smartled`
+///
+/// Writes go through a [PowerManagedWriter], so the brightness, gamma curve, and power budget are all available through [Output::controls].
 pub struct SmartLedsOutput<'a, T, Pixbuf> {
-    writer: PowerManagedWriter<T>,
+    writer: PowerManagedWriter<'a, T>,
     pixbuf: &'a mut Pixbuf,
     buf_idx: usize,
     clip: Rectangle<LinearSpace>
@@ -118,6 +260,16 @@ impl<'a, T, Pixel, const PIXEL_COUNT: usize> SmartLedsOutput<'a, T, [Pixel; PIXE
         self.clip = clip;
     }
 
+    /// Sets the fixed amount of power used by everything other than the LEDs, which is subtracted from the power budget
+    pub fn set_overhead_mw(&mut self, overhead_mw: u32) {
+        self.writer.set_overhead_mw(overhead_mw);
+    }
+
+    /// Returns the total power required to display the previous commit at full brightness
+    pub const fn max_mw(&self) -> u32 {
+        self.writer.max_mw()
+    }
+
     // TODO: We could just put this into a DoubleBufferedPixbuf, then there isn't a need to call this ever with SmartLedsOutput, as you could do output.pixbuf().swap(&mut next) with that.
     pub fn swap_buffer(&mut self, pixbuf: &'a mut [Pixel; PIXEL_COUNT]) -> &'a mut [Pixel; PIXEL_COUNT] {
         self.buf_idx = (self.buf_idx + 1) % self.pixbuf.as_ref().len();
@@ -125,10 +277,10 @@ impl<'a, T, Pixel, const PIXEL_COUNT: usize> SmartLedsOutput<'a, T, [Pixel; PIXE
     }
 }
 
-impl<'a, T: SmartLedsWrite + 'a, Pixbuf: AsRef<[T::Color]>> Output<'a, LinearSpace> for SmartLedsOutput<'a, T, Pixbuf> where Self: Sample<'a, LinearSpace>, T::Color: core::fmt::Debug + AsMilliwatts + Mul<Fract8, Output = T::Color> + Copy + WithGamma {
+impl<'a, T: SmartLedsWrite + 'a, Pixbuf: AsRef<[T::Color]>> Output<'a, LinearSpace> for SmartLedsOutput<'a, T, Pixbuf> where Self: Sample<'a, LinearSpace>, T::Color: HardwareColor {
     type Error = T::Error;
 
-    type Controls = PowerControls;
+    type Controls = PowerControls<'a>;
 
     fn commit(&mut self)  -> Result<(), Self::Error> {
         self.writer.write(&self.pixbuf)
@@ -139,10 +291,10 @@ impl<'a, T: SmartLedsWrite + 'a, Pixbuf: AsRef<[T::Color]>> Output<'a, LinearSpa
     }
 }
 
-impl<'a, T: SmartLedsWriteAsync + 'a, Pixbuf: AsRef<[T::Color]>> OutputAsync<'a, LinearSpace> for SmartLedsOutput<'a, T, Pixbuf> where Self: Sample<'a, LinearSpace>, T::Color: core::fmt::Debug + AsMilliwatts + Mul<Fract8, Output = T::Color> + Copy + WithGamma {
+impl<'a, T: SmartLedsWriteAsync + 'a, Pixbuf: AsRef<[T::Color]>> OutputAsync<'a, LinearSpace> for SmartLedsOutput<'a, T, Pixbuf> where Self: Sample<'a, LinearSpace>, T::Color: HardwareColor {
     type Error = T::Error;
 
-    type Controls = PowerControls;
+    type Controls = PowerControls<'a>;
 
     async fn commit_async(&mut self)  -> Result<(), Self::Error> {
         self.writer.write_async(&self.pixbuf).await
@@ -161,4 +313,510 @@ impl<'a, T, Color, const PIXEL_COUNT: usize> Sample<'a, LinearSpace> for SmartLe
         let end = self.clip.bottom_right.x.clamp(0, self.pixbuf.len() - 1);
         self.pixbuf[start..=end].sample(rect)
     }
-}
\ No newline at end of file
+
+    fn bounds(&self) -> Rectangle<LinearSpace> {
+        Rectangle::new_from_coordinates(0, 0, self.pixel_count().saturating_sub(1), 0)
+    }
+
+    fn pixel_count(&self) -> usize {
+        let start = self.clip.top_left.x.clamp(0, self.pixbuf.len() - 1);
+        let end = self.clip.bottom_right.x.clamp(0, self.pixbuf.len() - 1);
+        (end + 1).saturating_sub(start)
+    }
+}
+
+/// The reasons the ranges given to [CompositeOutput::new] can be rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompositeOutputError {
+    /// A range starts after it ends
+    Reversed {
+        /// The position of the target whose range is reversed
+        index: usize
+    },
+    /// A range extends past the end of the pixbuf
+    OutOfBounds {
+        /// The position of the target whose range doesn't fit
+        index: usize,
+        /// The number of pixels in the pixbuf
+        pixel_count: usize
+    },
+    /// Two ranges share some of the same pixels
+    Overlapping {
+        /// The position of the first target
+        first: usize,
+        /// The position of the target that overlaps with it
+        second: usize
+    }
+}
+
+impl core::fmt::Display for CompositeOutputError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CompositeOutputError::Reversed { index } => write!(f, "the range of output {index} starts after it ends"),
+            CompositeOutputError::OutOfBounds { index, pixel_count } => write!(f, "the range of output {index} does not fit within the {pixel_count} pixel pixbuf"),
+            CompositeOutputError::Overlapping { first, second } => write!(f, "the ranges of outputs {first} and {second} overlap")
+        }
+    }
+}
+
+/// An [Output] that spreads one logical display across several hardware targets, such as multiple strips that are each driven by their own pin
+///
+/// Each target is given a range of pixels from the shared pixbuf. Power management is applied across the whole display, so every target shares the same brightness and the total stays within a single power budget.
+pub struct CompositeOutput<'a, T, Pixbuf, const OUTPUTS: usize> {
+    targets: [(T, Range<usize>); OUTPUTS],
+    controls: PowerControls<'a>,
+    pixbuf: &'a mut Pixbuf
+}
+
+impl<'a, T, Pixel, const OUTPUTS: usize, const PIXEL_COUNT: usize> CompositeOutput<'a, T, [Pixel; PIXEL_COUNT], OUTPUTS> {
+    /// Creates a new output from a set of targets, along with the range of pixels within the pixbuf that each one displays
+    ///
+    /// Every range must fit within the pixbuf, and no two ranges may share a pixel. Pixels that aren't in any range are never displayed.
+    pub fn new(targets: [(T, Range<usize>); OUTPUTS], pixbuf: &'a mut [Pixel; PIXEL_COUNT], max_mw: u32) -> Result<Self, CompositeOutputError> {
+        for (index, (_, range)) in targets.iter().enumerate() {
+            if range.start > range.end {
+                return Err(CompositeOutputError::Reversed { index });
+            }
+            if range.end > PIXEL_COUNT {
+                return Err(CompositeOutputError::OutOfBounds { index, pixel_count: PIXEL_COUNT });
+            }
+            if let Some(first) = targets[..index].iter().position(|(_, other)| other.start < range.end && range.start < other.end) {
+                return Err(CompositeOutputError::Overlapping { first, second: index });
+            }
+        }
+
+        Ok(Self {
+            targets,
+            controls: PowerControls::new(max_mw),
+            pixbuf
+        })
+    }
+
+    pub const fn pixbuf(&mut self) -> &mut [Pixel; PIXEL_COUNT] {
+        self.pixbuf
+    }
+
+    /// Returns the total power required to display the previous frame across every target at full brightness
+    pub const fn full_brightness_mw(&self) -> u32 {
+        self.controls.cur_mw
+    }
+}
+
+impl<'a, T: SmartLedsWrite + 'a, Pixbuf: AsRef<[T::Color]>, const OUTPUTS: usize> Output<'a, LinearSpace> for CompositeOutput<'a, T, Pixbuf, OUTPUTS> where Self: Sample<'a, LinearSpace>, T::Color: HardwareColor {
+    type Error = T::Error;
+
+    type Controls = PowerControls<'a>;
+
+    fn commit(&mut self) -> Result<(), Self::Error> {
+        let pixels = self.pixbuf.as_ref();
+        if !self.controls.is_on {
+            let controls = &self.controls;
+            for (target, range) in self.targets.iter_mut() {
+                target.write(pixels[range.clone()].iter().map(|x| controls.apply(*x, Fract8::MIN)))?;
+            }
+            return Ok(());
+        }
+
+        let scale = self.controls.correction.scaled(self.controls.limit_brightness(pixels));
+        let gamma_curve = &self.controls.gamma_curve;
+        // The errors are tracked across the whole pixbuf, so each target dithers its own range of them
+        let mut errors = self.controls.dither_error.as_mut().map(|errors| errors.for_pixels(pixels.len()));
+        for (target, range) in self.targets.iter_mut() {
+            let range_errors = errors.as_deref_mut().map(|errors| {
+                let len = errors.len();
+                &mut errors[range.start.min(len)..range.end.min(len)]
+            });
+            target.write(scale_pixels(&pixels[range.clone()], scale, gamma_curve, range_errors))?;
+        }
+        Ok(())
+    }
+
+    fn controls(&mut self) -> Option<&mut Self::Controls> {
+        Some(&mut self.controls)
+    }
+}
+
+impl<'a, T, Color, const OUTPUTS: usize, const PIXEL_COUNT: usize> Sample<'a, LinearSpace> for CompositeOutput<'a, T, [Color; PIXEL_COUNT], OUTPUTS> where Color: 'a {
+    type Output = Color;
+
+    fn sample(&mut self, rect: &figments::prelude::Rectangle<LinearSpace>) -> impl Iterator<Item = (figments::prelude::Coordinates<LinearSpace>, &'a mut Self::Output)> {
+        self.pixbuf.sample(rect)
+    }
+
+    fn bounds(&self) -> Rectangle<LinearSpace> {
+        self.pixbuf.bounds()
+    }
+
+    fn pixel_count(&self) -> usize {
+        PIXEL_COUNT
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::convert::Infallible;
+
+    #[derive(Default)]
+    struct MockWriter<C = Rgb<u8>> {
+        written: [C; 16],
+        count: usize
+    }
+
+    impl<C> SmartLedsWrite for MockWriter<C> {
+        type Error = Infallible;
+        type Color = C;
+
+        fn write<T, I>(&mut self, iterator: T) -> Result<(), Self::Error>
+        where
+            T: IntoIterator<Item = I>,
+            I: Into<Self::Color> {
+            self.count = 0;
+            for (idx, pix) in iterator.into_iter().enumerate() {
+                self.written[idx] = pix.into();
+                self.count += 1;
+            }
+            Ok(())
+        }
+    }
+
+    impl<C> MockWriter<C> {
+        fn written(&self) -> &[C] {
+            &self.written[..self.count]
+        }
+    }
+
+    #[test]
+    fn test_power_budget_is_respected() {
+        for color in [Rgb::new(255, 255, 255), Rgb::new(255, 0, 0), Rgb::new(40, 200, 90), Rgb::new(3, 3, 3)] {
+            for gamma in [1.0, 2.2] {
+                for max_mw in [50, 200, 500, 1000, 3000] {
+                    let mut writer = PowerManagedWriter::new(MockWriter::default(), max_mw);
+                    writer.controls().set_gamma(GammaCurve::new(gamma));
+                    writer.write(&[color; 16]).unwrap();
+
+                    let actual_mw = writer.target.written().as_milliwatts();
+                    let dark_mw = 16 * Rgb::new(0u8, 0, 0).as_milliwatts();
+                    assert!(actual_mw <= max_mw.max(dark_mw), "{color:?} with gamma={gamma} draws {actual_mw}mw which exceeds the budget of {max_mw}mw");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rgbw_power_budget_is_respected() {
+        use figments::pixels::Rgbw;
+
+        let full_white: Rgbw<u8> = Rgbw::new(0, 0, 0, 255);
+        let full_rgb: Rgbw<u8> = Rgbw::new(255, 255, 255, 0);
+        let dark_mw = 16 * Rgbw::new(0u8, 0, 0, 0).as_milliwatts();
+        for color in [full_white, full_rgb, Rgbw::new(255, 255, 255, 255), Rgbw::new(10, 0, 30, 200)] {
+            for max_mw in [50, 200, 500, 1000, 3000] {
+                let mut writer = PowerManagedWriter::new(MockWriter::<Rgbw<u8>>::default(), max_mw);
+                writer.write(&[color; 16]).unwrap();
+
+                let actual_mw = writer.target.written().as_milliwatts();
+                assert!(actual_mw <= max_mw.max(dark_mw), "{color:?} draws {actual_mw}mw which exceeds the budget of {max_mw}mw");
+            }
+        }
+
+        // The white channel is dimmed along with the colors, just like a full RGB frame is
+        const MAX_MW: u32 = 500;
+        let mut writer = PowerManagedWriter::new(MockWriter::<Rgbw<u8>>::default(), MAX_MW);
+        writer.write(&[full_white; 16]).unwrap();
+        let white = writer.target.written()[0];
+        writer.write(&[full_rgb; 16]).unwrap();
+        let rgb = writer.target.written()[0];
+        assert!(white.w > 0 && white.w < 255, "The white channel should be dimmed to fit the budget: {white:?}");
+        assert!(rgb.r > 0 && rgb.r < 255 && rgb.w == 0, "The color channels should be dimmed to fit the budget: {rgb:?}");
+    }
+
+    #[test]
+    fn test_power_budget_is_respected_at_every_boundary() {
+        // Every budget between dark and full brightness, so the truncation of every possible brightness is covered
+        for color in [Rgbw::new(255u8, 255, 255, 255), Rgbw::new(51, 153, 225, 0), Rgbw::new(200, 100, 75, 130)] {
+            for gamma in [1.0, 2.2] {
+                let curve = GammaCurve::new(gamma);
+                let dark_mw = [color * Fract8::MIN; 16].as_milliwatts();
+                let full_mw = [color.with_gamma(&curve); 16].as_milliwatts();
+                for max_mw in dark_mw..=full_mw {
+                    let mut errors = [[0; 4]; 16];
+                    let mut writer = PowerManagedWriter::new(MockWriter::<Rgbw<u8>>::default(), max_mw);
+                    writer.controls().set_gamma(GammaCurve::new(gamma));
+                    writer.controls().set_dither_buffer(Some(&mut errors));
+                    for _ in 0..4 {
+                        writer.write(&[color; 16]).unwrap();
+                        let actual_mw = writer.target.written().as_milliwatts();
+                        assert!(actual_mw <= max_mw, "{color:?} with gamma={gamma} draws {actual_mw}mw which exceeds the budget of {max_mw}mw");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_brightness_is_applied_before_gamma() {
+        let mut writer = PowerManagedWriter::new(MockWriter::default(), u32::MAX);
+        writer.controls().set_gamma(GammaCurve::new(2.0));
+        writer.controls().set_brightness(Fract8::from_raw(128));
+        writer.write(&[Rgb::new(255, 255, 255)]).unwrap();
+
+        // Half brightness, then squared by the gamma curve
+        assert_eq!(writer.target.written(), &[Rgb::new(64, 64, 64)]);
+    }
+
+    #[test]
+    fn test_overhead_shrinks_budget() {
+        const MAX_MW: u32 = 2000;
+        const OVERHEAD_MW: u32 = 500;
+        let frame = [Rgb::new(255, 255, 255); 16];
+
+        let mut writer = PowerManagedWriter::new(MockWriter::default(), MAX_MW);
+        writer.write(&frame).unwrap();
+        let without_overhead = writer.target.written().as_milliwatts();
+
+        writer.set_overhead_mw(OVERHEAD_MW);
+        assert_eq!(writer.controls().available_mw(), MAX_MW - OVERHEAD_MW);
+        writer.write(&frame).unwrap();
+        let with_overhead = writer.target.written().as_milliwatts();
+
+        assert!(without_overhead <= MAX_MW);
+        assert!(with_overhead <= MAX_MW - OVERHEAD_MW, "Frame draws {with_overhead}mw, which exceeds the budget after overhead");
+        assert!(with_overhead < without_overhead);
+    }
+
+    #[test]
+    fn test_smart_leds_output_commits_through_controls() {
+        let mut pixbuf = [Rgb::new(0u8, 0, 0); 16];
+        let mut output = SmartLedsOutput::new(MockWriter::default(), &mut pixbuf, u32::MAX);
+
+        output.fill(&|coords: &Coordinates<LinearSpace>, _: &()| Rgb::new(coords.x as u8 * 16, 255, 0), &());
+        output.commit().unwrap();
+        assert!(output.writer.target.written().iter().copied().eq((0..16).map(|x| Rgb::new(x * 16, 255, 0))), "The whole pixbuf should be written: {:?}", output.writer.target.written());
+        assert!(output.max_mw() > 0);
+
+        let controls = Output::controls(&mut output).unwrap();
+        controls.set_brightness(Fract8::from_raw(128));
+        output.commit().unwrap();
+        assert_eq!(output.writer.target.written()[0], Rgb::new(0, 128, 0), "Brightness from the controls should be applied on commit");
+
+        Output::controls(&mut output).unwrap().set_on(false);
+        output.commit().unwrap();
+        assert!(output.writer.target.written().iter().all(|pix| *pix == Rgb::new(0, 0, 0)), "Turning the output off should blank every pixel");
+    }
+
+    #[test]
+    fn test_composite_output_distributes_ranges() {
+        let mut pixbuf = [Rgb::new(0u8, 0, 0); 24];
+        let mut output = CompositeOutput::new([
+            (MockWriter::default(), 0..8),
+            (MockWriter::default(), 8..16),
+            (MockWriter::default(), 16..24)
+        ], &mut pixbuf, u32::MAX).unwrap();
+
+        output.fill(&|coords: &Coordinates<LinearSpace>, _: &()| Rgb::new(coords.x as u8 * 10, 0, 0), &());
+        output.commit().unwrap();
+
+        for (idx, (target, range)) in output.targets.iter().enumerate() {
+            assert_eq!(target.written().len(), range.len());
+            assert!(target.written().iter().copied().eq(range.clone().map(|x| Rgb::new(x as u8 * 10, 0, 0))), "Output {idx} did not receive pixels {range:?}: {:?}", target.written());
+        }
+    }
+
+    #[test]
+    fn test_composite_output_shares_power_budget() {
+        const MAX_MW: u32 = 500;
+        let mut pixbuf = [Rgb::new(255u8, 255, 255); 32];
+        let mut output = CompositeOutput::new([
+            (MockWriter::default(), 0..16),
+            (MockWriter::default(), 16..32)
+        ], &mut pixbuf, MAX_MW).unwrap();
+        output.commit().unwrap();
+
+        let total_mw: u32 = output.targets.iter().map(|(target, _)| target.written().as_milliwatts()).sum();
+        assert!(total_mw <= MAX_MW, "The outputs draw {total_mw}mw together, which exceeds the budget of {MAX_MW}mw");
+        assert_eq!(output.targets[0].0.written(), output.targets[1].0.written(), "Every output should be dimmed by the same amount");
+        assert_eq!(output.full_brightness_mw(), [Rgb::new(255u8, 255, 255); 32].as_milliwatts(), "The full brightness power should cover every target");
+    }
+
+    #[test]
+    fn test_composite_output_rejects_bad_ranges() {
+        let mut pixbuf = [Rgb::new(0u8, 0, 0); 16];
+        let mock = MockWriter::<Rgb<u8>>::default;
+        let reversed = Range { start: 8, end: 4 };
+        assert_eq!(CompositeOutput::new([(mock(), 0..4), (mock(), reversed)], &mut pixbuf, u32::MAX).err(), Some(CompositeOutputError::Reversed { index: 1 }));
+        assert_eq!(CompositeOutput::new([(mock(), 0..8), (mock(), 8..17)], &mut pixbuf, u32::MAX).err(), Some(CompositeOutputError::OutOfBounds { index: 1, pixel_count: 16 }));
+        assert_eq!(CompositeOutput::new([(mock(), 0..8), (mock(), 12..16), (mock(), 7..10)], &mut pixbuf, u32::MAX).err(), Some(CompositeOutputError::Overlapping { first: 0, second: 2 }));
+        assert!(CompositeOutput::new([(mock(), 0..8), (mock(), 8..8), (mock(), 12..16)], &mut pixbuf, u32::MAX).is_ok(), "Empty ranges and gaps between the ranges should be allowed");
+    }
+
+    #[cfg(feature="alloc")]
+    #[test]
+    fn test_dithering_averages_dim_colors() {
+        const FRAMES: u32 = 255;
+        let dim = [Rgb::new(10u8, 3, 200); 16];
+        let brightness = Fract8::from_raw(20);
+
+        let average = |dither: bool| {
+            let mut writer = PowerManagedWriter::new(MockWriter::default(), u32::MAX);
+            writer.controls().set_brightness(brightness);
+            writer.controls().set_dither(dither);
+            let mut total = [0u32; 3];
+            for _ in 0..FRAMES {
+                writer.write(&dim).unwrap();
+                let pixel = writer.target.written()[5];
+                total = [total[0] + pixel.r as u32, total[1] + pixel.g as u32, total[2] + pixel.b as u32];
+            }
+            total.map(|channel| channel as f32 / FRAMES as f32)
+        };
+
+        // The exact value of each channel, which can't be displayed in a single frame
+        let ideal = [10.0 * 20.0 / 255.0, 3.0 * 20.0 / 255.0, 200.0 * 20.0 / 255.0];
+        let dithered = average(true);
+        let truncated = average(false);
+        for channel in 0..3 {
+            assert!((dithered[channel] - ideal[channel]).abs() < 0.01, "Channel {channel} averaged {} instead of {}", dithered[channel], ideal[channel]);
+        }
+        assert!((truncated[0] - ideal[0]).abs() > 0.5, "Without dithering, the red channel should be stuck at a lower level: {truncated:?}");
+
+        // Changing the number of pixels starts over with a fresh set of errors
+        let mut writer = PowerManagedWriter::new(MockWriter::default(), u32::MAX);
+        writer.controls().set_dither(true);
+        writer.controls().set_brightness(Fract8::from_raw(128));
+        writer.write(&[Rgb::new(1u8, 1, 1); 16]).unwrap();
+        writer.write(&[Rgb::new(1u8, 1, 1); 8]).unwrap();
+        assert_eq!(writer.target.written(), &[Rgb::new(0, 0, 0); 8], "The error from the longer frame should have been thrown away");
+        writer.write(&[Rgb::new(1u8, 1, 1); 8]).unwrap();
+        assert_eq!(writer.target.written(), &[Rgb::new(1, 1, 1); 8]);
+    }
+
+    #[test]
+    fn test_dithering_with_borrowed_buffer() {
+        let mut errors = [[0; 4]; 8];
+        let mut writer = PowerManagedWriter::new(MockWriter::default(), u32::MAX);
+        writer.controls().set_brightness(Fract8::from_raw(128));
+        writer.controls().set_dither_buffer(Some(&mut errors));
+        writer.write(&[Rgb::new(1u8, 1, 1); 16]).unwrap();
+        assert_eq!(writer.target.written(), &[Rgb::new(0, 0, 0); 16]);
+        writer.write(&[Rgb::new(1u8, 1, 1); 16]).unwrap();
+        assert_eq!(&writer.target.written()[..8], &[Rgb::new(1, 1, 1); 8], "The error from the previous frame should be carried over");
+        assert_eq!(&writer.target.written()[8..], &[Rgb::new(0, 0, 0); 8], "Pixels past the end of the buffer should not be dithered");
+
+        // Changing the number of pixels starts over with a fresh set of errors
+        writer.write(&[Rgb::new(1u8, 1, 1); 16]).unwrap();
+        writer.write(&[Rgb::new(1u8, 1, 1); 4]).unwrap();
+        assert_eq!(writer.target.written(), &[Rgb::new(0, 0, 0); 4], "The error from the longer frame should have been thrown away");
+        writer.write(&[Rgb::new(1u8, 1, 1); 4]).unwrap();
+        assert_eq!(writer.target.written(), &[Rgb::new(1, 1, 1); 4]);
+
+        writer.controls().set_dither_buffer(None);
+        writer.write(&[Rgb::new(1u8, 1, 1); 16]).unwrap();
+        writer.write(&[Rgb::new(1u8, 1, 1); 16]).unwrap();
+        assert_eq!(writer.target.written(), &[Rgb::new(0, 0, 0); 16], "Turning dithering off should stop carrying the error over");
+    }
+
+    #[test]
+    fn test_dithering_after_gamma_averages_to_ideal_output() {
+        const FRAMES: u32 = 255;
+        const GAMMA: f32 = 2.0;
+        let dim = [Rgb::new(100u8, 60, 200); 8];
+        let brightness = Fract8::from_raw(32);
+
+        let average = |dither: bool| {
+            let mut errors = [[0; 4]; 8];
+            let mut writer = PowerManagedWriter::new(MockWriter::default(), u32::MAX);
+            writer.controls().set_gamma(GammaCurve::new(GAMMA));
+            writer.controls().set_brightness(brightness);
+            writer.controls().set_dither_buffer(dither.then_some(&mut errors[..]));
+            let mut total = [0u32; 3];
+            for _ in 0..FRAMES {
+                writer.write(&dim).unwrap();
+                let pixel = writer.target.written()[3];
+                total = [total[0] + pixel.r as u32, total[1] + pixel.g as u32, total[2] + pixel.b as u32];
+            }
+            total.map(|channel| channel as f32 / FRAMES as f32)
+        };
+
+        // The exact output of the gamma curve for each channel at this brightness
+        let ideal = [100.0f32, 60.0, 200.0].map(|value| (value / 255.0 * 32.0 / 255.0).powf(GAMMA) * 255.0);
+        let dithered = average(true);
+        let truncated = average(false);
+        for channel in 0..3 {
+            assert!((dithered[channel] - ideal[channel]).abs() < 0.05, "Channel {channel} averaged {} instead of {}", dithered[channel], ideal[channel]);
+        }
+        assert!((truncated[0] - ideal[0]).abs() > 0.3, "Without dithering, the red channel should be stuck at a single level: {truncated:?}");
+    }
+
+    #[test]
+    fn test_dithering_follows_blended_curve() {
+        const FRAMES: u32 = 255;
+        // Every channel scales to an exact level at this brightness, so dithering has nothing to round
+        let colors = [Rgb::new(255u8, 100, 200); 8];
+        let brightness = Fract8::from_raw(51);
+
+        let average = |dither: bool| {
+            let mut errors = [[0; 4]; 8];
+            let mut writer = PowerManagedWriter::new(MockWriter::default(), u32::MAX);
+            writer.controls().set_gamma(GammaCurve::new(1.0).blend(&GammaCurve::new(2.2), Fract8::from_raw(128)));
+            writer.controls().set_brightness(brightness);
+            writer.controls().set_dither_buffer(dither.then_some(&mut errors[..]));
+            let mut total = [0u32; 3];
+            for _ in 0..FRAMES {
+                writer.write(&colors).unwrap();
+                let pixel = writer.target.written()[3];
+                total = [total[0] + pixel.r as u32, total[1] + pixel.g as u32, total[2] + pixel.b as u32];
+            }
+            total.map(|channel| channel as f32 / FRAMES as f32)
+        };
+
+        let dithered = average(true);
+        let undithered = average(false);
+        // The 8 bit curve is rounded from the same values that dithering averages out to, so they are always within a level of each other
+        for channel in 0..3 {
+            assert!((dithered[channel] - undithered[channel]).abs() < 1.0, "Channel {channel} averaged {} with dithering, but is {} without it", dithered[channel], undithered[channel]);
+        }
+    }
+
+    #[test]
+    fn test_composite_output_dithers_each_range() {
+        let mut errors = [[0; 4]; 6];
+        let mut pixbuf = [Rgb::new(1u8, 1, 1); 8];
+        let mut output = CompositeOutput::new([
+            (MockWriter::default(), 0..4),
+            (MockWriter::default(), 4..8)
+        ], &mut pixbuf, u32::MAX).unwrap();
+        output.controls().unwrap().set_brightness(Fract8::from_raw(128));
+        output.controls().unwrap().set_dither_buffer(Some(&mut errors));
+
+        output.commit().unwrap();
+        assert!(output.targets.iter().all(|(target, _)| target.written() == [Rgb::new(0, 0, 0); 4]));
+        output.commit().unwrap();
+        assert_eq!(output.targets[0].0.written(), &[Rgb::new(1, 1, 1); 4], "The error from the previous frame should be carried over");
+        assert_eq!(&output.targets[1].0.written()[..2], &[Rgb::new(1, 1, 1); 2], "Each range should use its own part of the buffer");
+        assert_eq!(&output.targets[1].0.written()[2..], &[Rgb::new(0, 0, 0); 2], "Pixels past the end of the buffer should not be dithered");
+    }
+
+    #[test]
+    fn test_correction_is_applied_with_brightness() {
+        let mut writer = PowerManagedWriter::new(MockWriter::default(), u32::MAX);
+        writer.controls().set_correction(ColorCorrection::TUNGSTEN_40W);
+        writer.write(&[Rgb::new(255u8, 255, 255); 4]).unwrap();
+        let warm = writer.target.written()[0];
+        assert!(warm.b < warm.r && warm.g < warm.r, "A tungsten temperature should reduce blue and green relative to red: {warm:?}");
+
+        // The correction and brightness multiply together
+        writer.controls().set_brightness(Fract8::from_raw(128));
+        writer.write(&[Rgb::new(255u8, 255, 255); 4]).unwrap();
+        assert_eq!(writer.target.written()[0], Rgb::new(128, 99, 72));
+
+        // Warmer colors draw less power, so they need less limiting to fit in the budget
+        let budget = [Rgb::new(255u8, 255, 255); 16].as_milliwatts() / 2;
+        let mut neutral = PowerManagedWriter::new(MockWriter::default(), budget);
+        let mut corrected = PowerManagedWriter::new(MockWriter::default(), budget);
+        corrected.controls().set_correction(ColorCorrection::TUNGSTEN_40W);
+        neutral.write(&[Rgb::new(255u8, 255, 255); 16]).unwrap();
+        corrected.write(&[Rgb::new(255u8, 255, 255); 16]).unwrap();
+        assert!(corrected.target.written()[0].r > neutral.target.written()[0].r, "The corrected colors should be limited less");
+        assert!(corrected.target.written().as_milliwatts() <= budget);
+    }
+}
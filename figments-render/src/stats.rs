@@ -0,0 +1,167 @@
+/// A snapshot of the rendering performance, as measured by a [FrameTimer]
+///
+/// All times are in microseconds.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    /// How long the most recent frame took to render
+    pub last_render_us: u32,
+    /// How long the most recent frame took to flush out to the hardware
+    pub last_flush_us: u32,
+    /// The average render time across the window
+    pub avg_render_us: u32,
+    /// The average flush time across the window
+    pub avg_flush_us: u32,
+    /// The average time from the start of one frame to the start of the next, including any time spent waiting between frames
+    pub avg_frame_us: u32
+}
+
+impl FrameStats {
+    /// Returns the effective number of frames per second, or zero if not enough frames have been measured yet
+    pub const fn fps(&self) -> u32 {
+        match self.avg_frame_us {
+            0 => 0,
+            us => 1_000_000 / us
+        }
+    }
+}
+
+/// Tracks a rolling window of render and flush durations, using a `now` function that returns a timestamp in microseconds
+///
+/// The timestamp source is provided by the application, so the timer works with any HAL or async runtime.
+///
+/// ```
+/// use figments_render::stats::FrameTimer;
+///
+/// let mut clock = 0;
+/// let mut timer: FrameTimer<_> = FrameTimer::new(move || { clock += 1000; clock });
+/// for _ in 0..4 {
+///     timer.render(|| { /* surfaces.render_to(...) */ });
+///     timer.flush(|| { /* writer.write(...) */ });
+/// }
+/// assert_eq!(timer.stats().last_render_us, 1000);
+/// ```
+#[derive(Debug)]
+pub struct FrameTimer<Now, const WINDOW: usize = 16> {
+    now: Now,
+    render_us: [u32; WINDOW],
+    flush_us: [u32; WINDOW],
+    frame_us: [u32; WINDOW],
+    frame_start: Option<u64>,
+    frame_count: usize
+}
+
+impl<Now: FnMut() -> u64, const WINDOW: usize> FrameTimer<Now, WINDOW> {
+    /// Creates a new timer
+    pub const fn new(now: Now) -> Self {
+        Self {
+            now,
+            render_us: [0; WINDOW],
+            flush_us: [0; WINDOW],
+            frame_us: [0; WINDOW],
+            frame_start: None,
+            frame_count: 0
+        }
+    }
+
+    /// Starts a new frame, and measures how long the given render function takes
+    pub fn render<R>(&mut self, render: impl FnOnce() -> R) -> R {
+        let start = (self.now)();
+        if let Some(previous) = self.frame_start.replace(start) {
+            // The frame period is only known once the next frame starts
+            self.frame_us[self.frame_count % WINDOW] = start.saturating_sub(previous) as u32;
+            self.frame_count += 1;
+        }
+        let result = render();
+        self.render_us[self.frame_count % WINDOW] = (self.now)().saturating_sub(start) as u32;
+        result
+    }
+
+    /// Measures how long the given flush function takes, which finishes the current frame
+    pub fn flush<R>(&mut self, flush: impl FnOnce() -> R) -> R {
+        let start = (self.now)();
+        let result = flush();
+        self.flush_us[self.frame_count % WINDOW] = (self.now)().saturating_sub(start) as u32;
+        result
+    }
+
+    /// Returns the current performance statistics
+    pub fn stats(&self) -> FrameStats {
+        let latest = self.frame_count % WINDOW;
+        // Render and flush times include the frame in progress, but the frame period does not
+        let measured = (self.frame_count + 1).min(WINDOW);
+        let periods = self.frame_count.min(WINDOW);
+        FrameStats {
+            last_render_us: self.render_us[latest],
+            last_flush_us: self.flush_us[latest],
+            avg_render_us: Self::average(&self.render_us, measured),
+            avg_flush_us: Self::average(&self.flush_us, measured),
+            avg_frame_us: Self::average(&self.frame_us, periods)
+        }
+    }
+
+    fn average(samples: &[u32; WINDOW], count: usize) -> u32 {
+        match count {
+            0 => 0,
+            _ => (samples.iter().map(|x| *x as u64).sum::<u64>() / count as u64) as u32
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Runs frames with the given render and flush durations, plus an idle delay between frames
+    fn run_frames<const WINDOW: usize>(timer: &mut FrameTimer<impl FnMut() -> u64, WINDOW>, clock: &core::cell::Cell<u64>, frames: &[(u64, u64)], idle_us: u64) {
+        for (render_us, flush_us) in frames {
+            timer.render(|| clock.set(clock.get() + render_us));
+            timer.flush(|| clock.set(clock.get() + flush_us));
+            clock.set(clock.get() + idle_us);
+        }
+    }
+
+    #[test]
+    fn test_empty_stats() {
+        let timer: FrameTimer<_> = FrameTimer::new(|| 0);
+        assert_eq!(timer.stats(), FrameStats::default());
+        assert_eq!(timer.stats().fps(), 0);
+    }
+
+    #[test]
+    fn test_averages() {
+        let clock = core::cell::Cell::new(0);
+        let mut timer: FrameTimer<_, 4> = FrameTimer::new(|| clock.get());
+
+        run_frames(&mut timer, &clock, &[(1000, 3000)], 6000);
+        let stats = timer.stats();
+        assert_eq!(stats.last_render_us, 1000);
+        assert_eq!(stats.last_flush_us, 3000);
+        assert_eq!(stats.avg_render_us, 1000);
+        // The length of a frame isn't known until the next one starts
+        assert_eq!(stats.avg_frame_us, 0);
+
+        run_frames(&mut timer, &clock, &[(3000, 1000)], 6000);
+        let stats = timer.stats();
+        assert_eq!(stats.last_render_us, 3000);
+        assert_eq!(stats.avg_render_us, 2000);
+        assert_eq!(stats.avg_flush_us, 2000);
+        assert_eq!(stats.avg_frame_us, 10_000);
+        assert_eq!(stats.fps(), 100);
+    }
+
+    #[test]
+    fn test_window_rolls_over() {
+        let clock = core::cell::Cell::new(0);
+        let mut timer: FrameTimer<_, 4> = FrameTimer::new(|| clock.get());
+
+        // Slow frames are forgotten once enough fast frames have been measured
+        run_frames(&mut timer, &clock, &[(50_000, 50_000); 8], 0);
+        assert_eq!(timer.stats().fps(), 10);
+        run_frames(&mut timer, &clock, &[(2000, 3000); 5], 15_000);
+        let stats = timer.stats();
+        assert_eq!(stats.avg_render_us, 2000);
+        assert_eq!(stats.avg_flush_us, 3000);
+        assert_eq!(stats.avg_frame_us, 20_000);
+        assert_eq!(stats.fps(), 50);
+    }
+}
@@ -2,6 +2,9 @@
 use figments::prelude::*;
 use figments::liber8tion::trig::*;
 use figments::liber8tion::noise::*;
+use figments::liber8tion::random::hash8;
+use figments::liber8tion::interpolate::Fract8;
+use figments::liber8tion::palette::Palette16;
 use core::cmp::max;
 use rgb::*;
 
@@ -62,11 +65,11 @@ impl<Space: CoordinateSpace<Data = usize>, Pixel> Shader<FrameNumber, Space, Pix
         let brightness = inoise8((noise_x.wrapping_add(coords.x as u8)).into(), (noise_y.wrapping_add(coords.y as u8)).into());
 
         // Saturation will be +/- 15 from the requested color
-        let saturation_min = self.color.saturation.saturating_sub(15);
-        let saturation_shift = 30u8 * inoise8((noise_y.wrapping_add(coords.y as u8)).into(), (noise_x.wrapping_add(coords.x as u8)).into());
-        let saturation = saturation_min.saturating_add(saturation_shift);
+        let saturation_min = Px(self.color.saturation.saturating_sub(15));
+        let saturation_shift = Px(30u8) * inoise8((noise_y.wrapping_add(coords.y as u8)).into(), (noise_x.wrapping_add(coords.x as u8)).into());
+        let saturation = saturation_min + saturation_shift;
 
-        Hsv::new(self.color.hue.wrapping_add(16u8 * uniforms.0.sin8()).wrapping_sub(8), saturation, brightness.to_raw()).into()
+        Hsv::new(self.color.hue.wrapping_add(16u8 * uniforms.0.sin8()).wrapping_sub(8), saturation.0, brightness.to_raw()).into()
     }
 }
 
@@ -120,4 +123,426 @@ impl<Pixel, Space: CoordinateSpace<Data = usize>> Shader<FrameNumber, Space, Pix
 
         Hsv::new(hue, max(128, saturation.to_raw()), brightness.to_raw()).into()
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug)]
+pub struct Twinkle {
+    pub color: Hsv,
+    /// How many pixels light up during each twinkle cycle, where 255 means every pixel
+    pub density: Fract8
+}
+
+impl Default for Twinkle {
+    fn default() -> Self {
+        Self {
+            color: Hsv::new(0, 0, 255),
+            density: Fract8::from_raw(32)
+        }
+    }
+}
+
+impl<Pixel, Space: CoordinateSpace<Data = usize>> Shader<FrameNumber, Space, Pixel> for Twinkle where Hsv: Into<Pixel> {
+    fn draw(&self, coords: &Coordinates<Space>, uniforms: &FrameNumber) -> Pixel {
+        const TWINKLE_LENGTH: usize = 32;
+
+        let x = coords.x as u32;
+        let y = coords.y as u32;
+
+        // Every pixel gets its own phase, so they don't all start and stop twinkling in lockstep
+        let local_frame = uniforms.0.wrapping_add(hash8(x, y, 0) as usize);
+        let cycle = local_frame / TWINKLE_LENGTH;
+        let cycle_pos = local_frame % TWINKLE_LENGTH;
+
+        // Each cycle, a random selection of pixels light up at full brightness and fade out by the end
+        let brightness = if hash8(x, y, cycle as u32) < self.density.to_raw() {
+            self.color.value * Fract8::from_ratio((TWINKLE_LENGTH - 1 - cycle_pos) as u8, TWINKLE_LENGTH as u8)
+        } else {
+            0
+        };
+
+        Hsv::new(self.color.hue, self.color.saturation, brightness).into()
+    }
+}
+
+/// A bright head that travels along a strip, trailed by a tail that fades out behind it
+///
+/// Once the head reaches the end of the strip, it wraps back around to the start.
+#[derive(Debug)]
+pub struct Comet {
+    /// The color of the head, which the tail fades out from
+    pub color: Hsv,
+    /// How many pixels the tail covers, including the head
+    pub tail_length: usize,
+    /// How many frames the head stays on each pixel, where 1 is the fastest
+    pub speed: usize,
+    /// How many pixels the head travels before wrapping around
+    pub length: usize
+}
+
+impl Default for Comet {
+    fn default() -> Self {
+        Self {
+            color: Hsv::new(0, 255, 255),
+            tail_length: 8,
+            speed: 2,
+            length: 64
+        }
+    }
+}
+
+impl Comet {
+    /// Returns the position of the head at the given frame
+    pub fn head(&self, frame: usize) -> usize {
+        (frame / max(1, self.speed)) % max(1, self.length)
+    }
+}
+
+impl<Pixel, Space: CoordinateSpace<Data = usize>> Shader<FrameNumber, Space, Pixel> for Comet where Hsv: Into<Pixel> {
+    fn draw(&self, coords: &Coordinates<Space>, uniforms: &FrameNumber) -> Pixel {
+        let length = max(1, self.length);
+        let tail_length = max(1, self.tail_length);
+        // How far this pixel is behind the head, wrapping around the end of the strip
+        let distance = (self.head(uniforms.0) + length - coords.x % length) % length;
+
+        let brightness = if distance < tail_length {
+            self.color.value * Fract8::from_raw(((tail_length - distance) * 255 / tail_length) as u8)
+        } else {
+            0
+        };
+
+        Hsv::new(self.color.hue, self.color.saturation, brightness).into()
+    }
+}
+
+/// A classic plasma effect, built from a few overlapping sine waves and some noise
+#[derive(Debug)]
+pub struct Plasma {
+    /// How many frames pass for each step of the animation, where 1 is the fastest
+    pub speed: usize,
+    /// How quickly the pattern changes between neighboring pixels, where higher values give smaller blobs
+    pub scale: u8
+}
+
+impl Default for Plasma {
+    fn default() -> Self {
+        Self {
+            speed: 2,
+            scale: 8
+        }
+    }
+}
+
+impl<Space: CoordinateSpace> Shader<FrameNumber, Space, Rgb<u8>> for Plasma where Space::Data: Into<usize> {
+    fn draw(&self, coords: &Coordinates<Space>, uniforms: &FrameNumber) -> Rgb<u8> {
+        let time = uniforms.0 / max(1, self.speed);
+        let x = coords.x.into().wrapping_mul(self.scale as usize);
+        let y = coords.y.into().wrapping_mul(self.scale as usize);
+
+        // One wave along each axis, and a third along the diagonal, each moving at a different rate
+        let wave_x = x.wrapping_add(time).sin8().to_raw() / 4;
+        let wave_y = y.wrapping_sub(time.wrapping_mul(2)).cos8().to_raw() / 4;
+        let wave_xy = x.wrapping_add(y).wrapping_add(time.wrapping_mul(3)).sin8().to_raw() / 4;
+        // The noise keeps the waves from lining up into an obviously repeating pattern
+        let wobble = inoise8((x as i16).wrapping_add(time as i16), (y as i16).wrapping_sub(time as i16)).to_raw() / 4;
+
+        let value = wave_x + wave_y + wave_xy + wobble;
+        Rgb::new(
+            value.sin8().to_raw(),
+            value.wrapping_add(85).sin8().to_raw(),
+            value.wrapping_add(170).sin8().to_raw()
+        )
+    }
+}
+
+/// Slowly drifting noise, colored by looking up each noise value in a [Palette16]
+#[derive(Debug)]
+pub struct PaletteNoise {
+    /// The colors that the noise is mapped onto
+    pub palette: Palette16,
+    /// How quickly the noise changes between neighboring pixels, where higher values give smaller blobs
+    pub scale: u8,
+    /// How many frames pass for each step of the animation, where 1 is the fastest
+    pub speed: usize
+}
+
+impl Default for PaletteNoise {
+    fn default() -> Self {
+        Self {
+            palette: Palette16::party(),
+            scale: 30,
+            speed: 1
+        }
+    }
+}
+
+impl<Space: CoordinateSpace<Data = usize>, Pixel> Shader<FrameNumber, Space, Pixel> for PaletteNoise where Rgb<u8>: Into<Pixel> {
+    fn draw(&self, coords: &Coordinates<Space>, uniforms: &FrameNumber) -> Pixel {
+        let time = uniforms.0 / max(1, self.speed);
+        let x = coords.x.wrapping_mul(self.scale as usize);
+        let y = coords.y.wrapping_mul(self.scale as usize).wrapping_add(time);
+        let noise = inoise8(x as i16, y as i16);
+        self.palette.color_at(noise.to_raw()).into()
+    }
+}
+
+/// A linear gradient between two colors, which are blended in HSV so the hues in between stay saturated
+///
+/// The gradient runs from `start` to `end` in the direction of `angle`, where a full turn is 256. An angle of 0 runs from left to right, and 64 runs from top to bottom. Coordinates are expected to be within 0-255 like [Virtual], and the gradient is stretched so that it reaches each color exactly at the corners of the display, whatever the angle.
+///
+/// Blending between two hues always takes the shortest way around the color wheel, so a gradient between red and magenta does not go through every other color.
+#[derive(Debug)]
+pub struct LinearGradient {
+    pub start: Hsv,
+    pub end: Hsv,
+    pub angle: u8
+}
+
+impl Default for LinearGradient {
+    fn default() -> Self {
+        Self {
+            start: Hsv::new(0, 255, 255),
+            end: Hsv::new(160, 255, 255),
+            angle: 0
+        }
+    }
+}
+
+impl LinearGradient {
+    /// Returns how far along the gradient a coordinate is, from 0 at the start to 255 at the end
+    pub fn position(&self, x: usize, y: usize) -> Fract8 {
+        // The 16 bit sine is symmetric around zero, so the gradient runs exactly straight along each axis
+        let theta = (self.angle as u16) << 8;
+        let dx = sin16(theta.wrapping_add(16384)) as i32 >> 8;
+        let dy = sin16(theta) as i32 >> 8;
+        // Dividing by the length of the projection of the whole display keeps the ends of the gradient on the corners
+        let span = max(1, dx.abs() + dy.abs());
+        let projected = (x.min(255) as i32 - 128) * dx + (y.min(255) as i32 - 128) * dy;
+        Fract8::from_raw((128 + projected / span).clamp(0, 255) as u8)
+    }
+
+    /// Returns the color of the gradient at a position
+    pub fn color_at(&self, position: Fract8) -> Hsv {
+        // Treating the difference as signed picks the shortest way around the color wheel
+        let hue_delta = self.end.hue.wrapping_sub(self.start.hue) as i8 as i32;
+        let hue_shift = hue_delta * position.to_raw() as i32 / 255;
+        Hsv::new(
+            self.start.hue.wrapping_add(hue_shift as u8),
+            self.start.saturation.lerp8by8(self.end.saturation, position),
+            self.start.value.lerp8by8(self.end.value, position)
+        )
+    }
+}
+
+impl<Space: CoordinateSpace, Pixel> Shader<FrameNumber, Space, Pixel> for LinearGradient where Space::Data: Into<usize>, Hsv: Into<Pixel> {
+    fn draw(&self, coords: &Coordinates<Space>, _uniforms: &FrameNumber) -> Pixel {
+        self.color_at(self.position(coords.x.into(), coords.y.into())).into()
+    }
+}
+
+/// Example of a custom uniform type, which carries the output of an audio analyzer instead of a frame number
+#[derive(Default, Debug, Clone, Copy)]
+pub struct AudioFrame {
+    /// The overall loudness
+    pub level: u8,
+    /// The loudness of each frequency band, from lowest to highest
+    pub bands: [u8; 8]
+}
+
+/// Renders a VU meter style bar for each band of an [AudioFrame] along a strip
+#[derive(Debug)]
+pub struct SpectrumShader {
+    /// The number of pixels used to draw each band
+    pub band_width: usize
+}
+
+impl Default for SpectrumShader {
+    fn default() -> Self {
+        Self {
+            band_width: 8
+        }
+    }
+}
+
+impl<Pixel, Space: CoordinateSpace<Data = usize>> Shader<AudioFrame, Space, Pixel> for SpectrumShader where Hsv: Into<Pixel> {
+    fn draw(&self, coords: &Coordinates<Space>, uniforms: &AudioFrame) -> Pixel {
+        let band_width = max(1, self.band_width);
+        let band_idx = (coords.x / band_width) % uniforms.bands.len();
+        let band_pos = coords.x % band_width;
+
+        // Each band lights up a bar proportional to its loudness, starting from the left side of the band
+        let lit_pixels = band_width * uniforms.bands[band_idx] as usize / 255;
+        let brightness = if band_pos < lit_pixels {
+            // Quiet moments dim the whole display, but never to black
+            128 + uniforms.level / 2
+        } else {
+            0
+        };
+
+        Hsv::new((band_idx * 32) as u8, 255, brightness).into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use figments::mappings::linear::LinearSpace;
+
+    fn render_twinkle(frame: usize) -> [Rgb<u8>; 64] {
+        let shader = Twinkle::default();
+        core::array::from_fn(|x| {
+            shader.draw(&Coordinates::<LinearSpace>::new(x, 0), &FrameNumber(frame))
+        })
+    }
+
+    #[test]
+    fn test_twinkle_is_replayable() {
+        for frame in [0, 1, 31, 32, 1000] {
+            assert_eq!(render_twinkle(frame), render_twinkle(frame), "Frame {frame} did not reproduce the same pattern");
+        }
+    }
+
+    #[test]
+    fn test_twinkle_changes_over_time() {
+        assert_ne!(render_twinkle(0), render_twinkle(1));
+        assert_ne!(render_twinkle(0), render_twinkle(100));
+    }
+
+    #[test]
+    fn test_comet_head_leads_a_fading_tail() {
+        let comet = Comet {
+            color: Hsv::new(0, 0, 255),
+            tail_length: 6,
+            speed: 3,
+            length: 32
+        };
+        let brightness = |x: usize, frame: usize| {
+            let pixel: Rgb<u8> = comet.draw(&Coordinates::<LinearSpace>::new(x, 0), &FrameNumber(frame));
+            pixel.r
+        };
+
+        // Frame 30 is the tenth step
+        assert_eq!(comet.head(30), 10);
+        assert_eq!(brightness(10, 30), 255, "The head should be at full brightness");
+        assert_eq!(brightness(11, 30), 0, "Nothing should be lit ahead of the head");
+        let tail: [u8; 6] = core::array::from_fn(|distance| brightness(10 - distance, 30));
+        assert!(tail.windows(2).all(|pair| pair[0] > pair[1] && pair[1] > 0), "The tail should fade out behind the head: {tail:?}");
+        assert_eq!(brightness(4, 30), 0, "The tail should end after {} pixels", comet.tail_length);
+
+        // Near the start of the strip, the tail wraps around to the end
+        assert_eq!(comet.head(3), 1);
+        assert_eq!(brightness(1, 3), 255);
+        assert!(brightness(31, 3) > brightness(30, 3) && brightness(30, 3) > 0, "The tail should continue from the end of the strip");
+        assert_eq!(comet.head(96), 0, "The head should wrap around after the whole strip");
+    }
+
+    fn render_plasma(frame: usize) -> [Rgb<u8>; 64] {
+        let shader = Plasma::default();
+        core::array::from_fn(|idx| {
+            shader.draw(&Coordinates::<LinearSpace>::new(idx % 8, idx / 8), &FrameNumber(frame))
+        })
+    }
+
+    #[test]
+    fn test_plasma_is_deterministic() {
+        let first = render_plasma(0);
+        assert_eq!(first, render_plasma(0), "Frame 0 did not reproduce the same pattern");
+        assert_ne!(first, render_plasma(100), "The plasma should move over time");
+        assert!(first.iter().any(|pixel| *pixel != first[0]), "The plasma should vary across the display: {first:?}");
+    }
+
+    #[test]
+    fn test_plasma_draws_in_virtual_space() {
+        let shader = Plasma::default();
+        let linear: Rgb<u8> = shader.draw(&Coordinates::<LinearSpace>::new(3, 5), &FrameNumber(7));
+        let virt: Rgb<u8> = shader.draw(&VirtualCoordinates::new(3, 5), &FrameNumber(7));
+        assert_eq!(linear, virt, "The same coordinates should draw the same color in any space");
+    }
+
+    fn render_palette_noise(shader: &PaletteNoise, frame: usize) -> [Rgb<u8>; 64] {
+        core::array::from_fn(|idx| {
+            shader.draw(&Coordinates::<LinearSpace>::new(idx % 8, idx / 8), &FrameNumber(frame))
+        })
+    }
+
+    #[test]
+    fn test_palette_noise_only_uses_palette_colors() {
+        let shader = PaletteNoise::default();
+        let first = render_palette_noise(&shader, 0);
+        assert_eq!(first, render_palette_noise(&shader, 0), "Frame 0 did not reproduce the same pattern");
+        assert_ne!(first, render_palette_noise(&shader, 100), "The noise should drift over time");
+        assert!(first.iter().any(|pixel| *pixel != first[0]), "The noise should vary across the display: {first:?}");
+
+        // A palette with a single color can only ever draw that color
+        let solid = PaletteNoise {
+            palette: Palette16::new([Rgb::new(10, 20, 30); 16]),
+            ..Default::default()
+        };
+        assert_eq!(render_palette_noise(&solid, 42), [Rgb::new(10, 20, 30); 64]);
+    }
+
+    #[test]
+    fn test_gradient_endpoints_and_midpoint() {
+        let gradient = LinearGradient {
+            start: Hsv::new(0, 255, 100),
+            end: Hsv::new(100, 155, 200),
+            angle: 0
+        };
+        let sample = |x: u8, y: u8| -> Hsv { gradient.draw(&VirtualCoordinates::new(x, y), &FrameNumber(0)) };
+
+        assert_eq!(sample(0, 0), gradient.start);
+        assert_eq!(sample(255, 0), gradient.end);
+        assert_eq!(sample(128, 0), Hsv::new(50, 205, 150), "The middle should be halfway between the two colors");
+        assert_eq!(sample(128, 0), sample(128, 255), "A horizontal gradient should not change from top to bottom");
+
+        // Turning the gradient a quarter turn runs it from top to bottom instead
+        let vertical = LinearGradient { angle: 64, ..gradient };
+        let sample = |x: u8, y: u8| -> Hsv { vertical.draw(&VirtualCoordinates::new(x, y), &FrameNumber(0)) };
+        assert_eq!(sample(0, 0), vertical.start);
+        assert_eq!(sample(0, 255), vertical.end);
+        assert_eq!(sample(0, 128), sample(255, 128));
+
+        // A diagonal gradient reaches each end at opposite corners
+        let diagonal = LinearGradient { angle: 32, ..vertical };
+        assert_eq!(diagonal.position(0, 0), Fract8::MIN);
+        assert_eq!(diagonal.position(255, 255), Fract8::MAX);
+    }
+
+    #[test]
+    fn test_gradient_hue_takes_short_path() {
+        let gradient = LinearGradient {
+            start: Hsv::new(240, 255, 255),
+            end: Hsv::new(16, 255, 255),
+            angle: 0
+        };
+        assert_eq!(gradient.color_at(Fract8::from_raw(128)).hue, 0, "The hue should wrap around through red instead of going through every other color");
+        assert_eq!(gradient.color_at(Fract8::MAX).hue, 16);
+
+        let backwards = LinearGradient { start: gradient.end, end: gradient.start, angle: 0 };
+        assert_eq!(backwards.color_at(Fract8::from_raw(128)).hue, 0);
+        assert_eq!(backwards.color_at(Fract8::MAX).hue, 240);
+    }
+
+    fn count_lit(bands: [u8; 8]) -> usize {
+        let shader = SpectrumShader::default();
+        let frame = AudioFrame { level: 255, bands };
+        (0..64).filter(|x| {
+            let pixel: Rgb<u8> = shader.draw(&Coordinates::<LinearSpace>::new(*x, 0), &frame);
+            pixel != Rgb::new(0, 0, 0)
+        }).count()
+    }
+
+    #[test]
+    fn test_spectrum_lights_more_pixels_when_louder() {
+        assert_eq!(count_lit([0; 8]), 0);
+        assert_eq!(count_lit([255; 8]), 64);
+
+        let quiet = count_lit([64; 8]);
+        let loud = count_lit([200; 8]);
+        assert!(quiet > 0 && loud > quiet, "quiet={quiet} loud={loud}");
+
+        // Only the band that got louder should light up more pixels
+        let mut bands = [64; 8];
+        bands[3] = 255;
+        assert_eq!(count_lit(bands), quiet - 2 + 8);
+    }
+}
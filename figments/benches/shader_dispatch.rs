@@ -0,0 +1,70 @@
+//! Compares rendering a pool whose shaders are stored as `Box<dyn Shader>` against one that stores them in a [shader_kind!] enum
+//!
+//! Run with `cargo bench -p figments --features std`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use figments::prelude::*;
+use figments::mappings::linear::LinearSpace;
+use figments::shader_kind;
+
+struct Ramp(u8);
+impl Shader<u8, LinearSpace, Rgb<u8>> for Ramp {
+    fn draw(&self, coords: &Coordinates<LinearSpace>, frame: &u8) -> Rgb<u8> {
+        Rgb::new((coords.x as u8).wrapping_mul(self.0), *frame, 0)
+    }
+}
+
+struct Solid(Rgb<u8>);
+impl Shader<u8, LinearSpace, Rgb<u8>> for Solid {
+    fn draw(&self, _coords: &Coordinates<LinearSpace>, _frame: &u8) -> Rgb<u8> {
+        self.0
+    }
+}
+
+shader_kind! {
+    enum Effects: Shader<u8, LinearSpace, Rgb<u8>> {
+        Ramp(Ramp),
+        Solid(Solid)
+    }
+}
+
+const PIXELS: usize = 1024;
+const FRAMES: u32 = 2000;
+
+/// Renders every frame into the same pixbuf, returning the average time taken per frame
+fn bench_frames<SH: Shader<u8, LinearSpace, Rgb<u8>>>(pool: &BufferedSurfacePool<u8, LinearSpace, Rgb<u8>, SH>) -> Duration {
+    let mut pixbuf = [Rgb::new(0u8, 0, 0); PIXELS];
+    let start = Instant::now();
+    for frame in 0..FRAMES {
+        pool.render_frame(&mut pixbuf[..], &(frame as u8));
+        black_box(&pixbuf);
+    }
+    start.elapsed() / FRAMES
+}
+
+fn main() {
+    let mut boxed: BufferedSurfacePool<u8, LinearSpace, Rgb<u8>> = BufferedSurfacePool::new();
+    let mut kinds: BufferedSurfacePool<u8, LinearSpace, Rgb<u8>, Effects> = BufferedSurfacePool::new();
+
+    boxed.new_surface(Rectangle::everything()).unwrap().set_shader(Ramp(3));
+    kinds.new_surface(Rectangle::everything()).unwrap().set_shader(Ramp(3));
+    let overlay = Rectangle::new_from_coordinates(PIXELS / 4, 0, PIXELS / 2, 0);
+    let mut boxed_overlay = boxed.new_surface(overlay).unwrap();
+    let mut kind_overlay = kinds.new_surface(overlay).unwrap();
+    boxed_overlay.set_shader(Solid(Rgb::new(0, 0, 255)));
+    kind_overlay.set_shader(Solid(Rgb::new(0, 0, 255)));
+    boxed_overlay.set_opacity(128);
+    kind_overlay.set_opacity(128);
+    boxed.commit();
+    kinds.commit();
+
+    // Warm up the caches before measuring either pool
+    bench_frames(&boxed);
+    bench_frames(&kinds);
+
+    let boxed_time = bench_frames(&boxed);
+    let kind_time = bench_frames(&kinds);
+    println!("Box<dyn Shader>: {boxed_time:?} per frame of {PIXELS} pixels");
+    println!("shader_kind!:    {kind_time:?} per frame of {PIXELS} pixels");
+}
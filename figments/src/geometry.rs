@@ -3,15 +3,18 @@
 //! 
 use core::fmt::Debug;
 use core::ops::{Add, Mul, Sub};
-use num::traits::SaturatingAdd;
+use num::traits::{SaturatingAdd, WrappingMul};
 use num::{One, pow, integer::Roots};
 use core::cmp::{min, max};
 
+use crate::liber8tion::trig::Trig8;
+use crate::liber8tion::interpolate::{scale16, Fract8};
+
 #[cfg(feature="embedded-graphics")]
 use embedded_graphics::prelude::Size;
 
 /// Basic trait for operations on 2d coordinate components
-pub trait CoordinateOp: PartialOrd + PartialEq + Sub + Clone + Mul + Copy + One + Add + Eq + Ord + Send + Sync + SaturatingAdd where
+pub trait CoordinateOp: PartialOrd + PartialEq + Sub + Clone + Mul + Copy + One + Add + Eq + Ord + Send + Sync + SaturatingAdd + WrappingMul + From<u8> where
 Self: Sub<Output=Self> + Add<Output=Self> {
     /// The smallest possible value within a coordinate space
     const MIN: Self;
@@ -22,6 +25,29 @@ Self: Sub<Output=Self> + Add<Output=Self> {
 
     /// Returns an iterator over every value from start to end, inclusive
     fn iter_range(start: Self, end: Self) -> impl Iterator<Item = Self>;
+
+    /// Wraps a value around into the inclusive range of low to high. Values are returned unchanged if the range is upside down.
+    fn wrap(value: Self, low: Self, high: Self) -> Self;
+
+    /// Converts a value within the inclusive range of low to high into a value relative to that range
+    ///
+    /// Spaces made of fractions of a display, such as [Virtual], stretch the range across every possible value so that low becomes [CoordinateOp::MIN] and high becomes [CoordinateOp::MAX]. Spaces that address physical pixels are only shifted so that low becomes zero.
+    fn relative_to(value: Self, low: Self, high: Self) -> Self;
+}
+
+/// Wraps a value into the inclusive range of low to high, without overflowing when the range covers every value
+fn wrap_usize(value: usize, low: usize, high: usize) -> usize {
+    if high < low {
+        return value;
+    }
+    let Some(span) = (high - low).checked_add(1) else {
+        return value;
+    };
+    if value >= low {
+        low + (value - low) % span
+    } else {
+        high - (low - value - 1) % span
+    }
 }
 
 /// Trait for describing coordinate spaces
@@ -60,21 +86,55 @@ impl CoordinateOp for i32 {
     fn iter_range(start: Self, end: Self) -> impl Iterator<Item = Self> {
         start..=end
     }
+
+    fn wrap(value: Self, low: Self, high: Self) -> Self {
+        if high < low {
+            return value;
+        }
+        let span = high as i64 - low as i64 + 1;
+        ((value as i64 - low as i64).rem_euclid(span) + low as i64) as i32
+    }
+
+    fn relative_to(value: Self, low: Self, _high: Self) -> Self {
+        value.saturating_sub(low)
+    }
+}
+
+/// Stretches a value within the inclusive range of low to high across 0 to max
+fn stretch(value: u32, low: u32, high: u32, max: u32) -> u32 {
+    if high <= low {
+        return 0;
+    }
+    min(value.saturating_sub(low) as u64 * max as u64 / (high - low) as u64, max as u64) as u32
 }
 
 impl CoordinateOp for u8 {
     const MIN: u8 = 0;
     const MAX: u8 = 255;
 
+    /// Distances longer than 255, such as between opposite corners, saturate at 255. Use [VirtualCoordinates::distance_u16] when the full range is needed.
     fn distance(x1: Self, y1: Self, x2: Self, y2: Self) -> Self {
-        let dx = (max(x1, x2) - min(x1, x2)) as u16;
-        let dy = (max(y1, y2) - min(y1, y2)) as u16;
-        (dx.saturating_mul(dx).saturating_add(dy.saturating_mul(dy))).sqrt() as u8
+        min(distance_u16(x1, y1, x2, y2), u8::MAX as u16) as u8
     }
     
     fn iter_range(start: Self, end: Self) -> impl Iterator<Item = Self> {
         start..=end
     }
+
+    fn wrap(value: Self, low: Self, high: Self) -> Self {
+        wrap_usize(value as usize, low as usize, high as usize) as u8
+    }
+
+    fn relative_to(value: Self, low: Self, high: Self) -> Self {
+        stretch(value as u32, low as u32, high as u32, u8::MAX as u32) as u8
+    }
+}
+
+/// Calculates the distance between two [Virtual] points without saturating, as the longest possible distance is about 361
+fn distance_u16(x1: u8, y1: u8, x2: u8, y2: u8) -> u16 {
+    let dx = x1.abs_diff(x2) as u32;
+    let dy = y1.abs_diff(y2) as u32;
+    (dx * dx + dy * dy).sqrt() as u16
 }
 
 impl CoordinateOp for u16 {
@@ -88,6 +148,14 @@ impl CoordinateOp for u16 {
     fn iter_range(start: Self, end: Self) -> impl Iterator<Item = Self> {
         start..=end
     }
+
+    fn wrap(value: Self, low: Self, high: Self) -> Self {
+        wrap_usize(value as usize, low as usize, high as usize) as u16
+    }
+
+    fn relative_to(value: Self, low: Self, high: Self) -> Self {
+        stretch(value as u32, low as u32, high as u32, u16::MAX as u32) as u16
+    }
 }
 
 impl CoordinateOp for usize {
@@ -101,6 +169,14 @@ impl CoordinateOp for usize {
     fn iter_range(start: Self, end: Self) -> impl Iterator<Item = Self> {
         start..=end
     }
+
+    fn wrap(value: Self, low: Self, high: Self) -> Self {
+        wrap_usize(value, low, high)
+    }
+
+    fn relative_to(value: Self, low: Self, _high: Self) -> Self {
+        value.saturating_sub(low)
+    }
 }
 
 impl<S: CoordinateSpace> Coordinates<S> {
@@ -112,16 +188,30 @@ impl<S: CoordinateSpace> Coordinates<S> {
         }
     }
  
-    /// Returns a new coordinate that has been rotated 90 degrees around the center of the [CoordinateSpace] a given number of times
+    /// Returns a new coordinate that has been rotated clockwise 90 degrees around the center of the [CoordinateSpace] a given number of times
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// let coords = VirtualCoordinates::new(10, 20);
+    /// assert_eq!(coords.rotated(1), VirtualCoordinates::new(235, 10));
+    /// assert_eq!(coords.rotated(2), VirtualCoordinates::new(245, 235));
+    /// assert_eq!(coords.rotated(3), VirtualCoordinates::new(20, 245));
+    /// ```
     pub fn rotated(&self, rotation: u8) -> Self {
         match rotation % 4 {
-            1 => Self { x: self.y, y: self.x },
-            2 => Self { x: S::Data::MAX - self.y, y: S::Data::MAX - self.x },
-            3 => Self { x: S::Data::MAX - self.y, y: S::Data::MAX - self.y },
+            1 => Self { x: S::Data::MAX - self.y, y: self.x },
+            2 => Self { x: S::Data::MAX - self.x, y: S::Data::MAX - self.y },
+            3 => Self { x: self.y, y: S::Data::MAX - self.x },
             _ => Self { x: self.x, y: self.y }
         }
     }
 
+    /// Returns a new coordinate with each axis multiplied by a scale factor, wrapping around the edges of the [CoordinateSpace]
+    pub fn scaled(&self, x: u8, y: u8) -> Self {
+        Self::new(self.x.wrapping_mul(&x.into()), self.y.wrapping_mul(&y.into()))
+    }
+
     /// The most top left coordinate in the associated [CoordinateSpace]
     pub const fn top_left() -> Self {
         Self::new(S::Data::MIN, S::Data::MIN)
@@ -146,6 +236,37 @@ impl<S: CoordinateSpace> Coordinates<S> {
     pub fn distance_to(&self, other: &Self) -> S::Data {
         S::Data::distance(self.x, self.y, other.x, other.y)
     }
+
+    /// Returns a new coordinate relative to the bounds, using [CoordinateOp::relative_to] on each axis
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// let bounds = Rectangle::new_from_coordinates(64, 0, 127, 255);
+    /// assert_eq!(VirtualCoordinates::new(64, 0).relative_to(&bounds), VirtualCoordinates::new(0, 0));
+    /// assert_eq!(VirtualCoordinates::new(127, 255).relative_to(&bounds), VirtualCoordinates::new(255, 255));
+    /// ```
+    pub fn relative_to(&self, bounds: &Rectangle<S>) -> Self {
+        Self::new(
+            S::Data::relative_to(self.x, bounds.left(), bounds.right()),
+            S::Data::relative_to(self.y, bounds.top(), bounds.bottom())
+        )
+    }
+
+    /// Returns a new coordinate with each axis wrapped around into the bounds, as if the opposite edges of the bounds were joined together
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// let bounds = Rectangle::new_from_coordinates(0, 0, 15, 15);
+    /// assert_eq!(VirtualCoordinates::new(17, 3).wrapped_within(&bounds), VirtualCoordinates::new(1, 3));
+    /// ```
+    pub fn wrapped_within(&self, bounds: &Rectangle<S>) -> Self {
+        Self::new(
+            S::Data::wrap(self.x, bounds.left(), bounds.right()),
+            S::Data::wrap(self.y, bounds.top(), bounds.bottom())
+        )
+    }
 }
 
 /// The standard virtual [CoordinateSpace], which ranges from (0, 0) to (255, 255).
@@ -158,7 +279,185 @@ impl CoordinateSpace for Virtual {
 /// Type alias for a coordinate within the [Virtual] space
 pub type VirtualCoordinates = Coordinates<Virtual>;
 
+/// A high resolution alternative to [Virtual], which ranges from (0, 0) to (65535, 65535)
+///
+/// Very long strips and large matrices have more pixels along an axis than [Virtual] has coordinates, so some pixels can't be told apart. This space has enough resolution to address every pixel individually.
+#[derive(PartialEq, Debug, Copy, Clone, Default)]
+pub struct Virtual16 {}
+impl CoordinateSpace for Virtual16 {
+    type Data = u16;
+}
+
+/// Type alias for a coordinate within the [Virtual16] space
+pub type Virtual16Coordinates = Coordinates<Virtual16>;
+
+/// Coordinate spaces such as [Virtual] and [Virtual16], which stretch across an entire display no matter how many pixels it has
+pub trait VirtualSpace: CoordinateSpace {
+    /// Scales a virtual coordinate into an offset within a span of physical coordinates
+    fn to_physical(value: Self::Data, span: usize) -> usize;
+
+    /// Scales an offset within an inclusive span of physical coordinates into a virtual coordinate, where the last coordinate of the span lands on [CoordinateOp::MAX]
+    fn to_virtual(offset: usize, span: usize) -> Self::Data;
+}
+
+impl VirtualSpace for Virtual {
+    fn to_physical(value: u8, span: usize) -> usize {
+        span * Fract8::from_raw(value)
+    }
+
+    fn to_virtual(offset: usize, span: usize) -> u8 {
+        match span {
+            0 => 0,
+            _ => (offset * 255 / span) as u8
+        }
+    }
+}
+
+impl VirtualSpace for Virtual16 {
+    fn to_physical(value: u16, span: usize) -> usize {
+        scale16(span, value)
+    }
+
+    fn to_virtual(offset: usize, span: usize) -> u16 {
+        match span {
+            0 => 0,
+            _ => (offset as u64 * u16::MAX as u64 / span as u64) as u16
+        }
+    }
+}
+
+impl Coordinates<Virtual> {
+    /// The center of the [Virtual] space
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// assert_eq!(VirtualCoordinates::center(), VirtualCoordinates::new(128, 128));
+    /// ```
+    pub const fn center() -> Self {
+        Self::new(128, 128)
+    }
+
+    /// Calculates the distance from this point to another point, without saturating at 255 like [Coordinates::distance_to]
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// let corner = VirtualCoordinates::new(255, 255);
+    /// assert_eq!(VirtualCoordinates::top_left().distance_u16(&corner), 360);
+    /// assert_eq!(VirtualCoordinates::top_left().distance_to(&corner), 255);
+    /// ```
+    pub fn distance_u16(&self, other: &Self) -> u16 {
+        distance_u16(self.x, self.y, other.x, other.y)
+    }
+}
+
+impl Rectangle<Virtual> {
+    /// Creates a rectangle of the given size around the center of the [Virtual] space
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// let rect = Rectangle::centered(10, 20);
+    /// assert_eq!(rect, Rectangle::new_from_coordinates(123, 118, 133, 138));
+    /// assert_eq!((rect.width(), rect.height()), (10, 20));
+    ///
+    /// // The largest possible rectangle covers everything
+    /// assert_eq!(Rectangle::centered(255, 255), Rectangle::everything());
+    /// ```
+    pub const fn centered(width: u8, height: u8) -> Self {
+        // Round the top left corner down, so a 255 wide rectangle fits without overflowing
+        let left = 128 - (width as u16).div_ceil(2) as u8;
+        let top = 128 - (height as u16).div_ceil(2) as u8;
+        Self::new_from_coordinates(left, top, left + width, top + height)
+    }
+}
+
+/// A rectangle in [Virtual] space that is rotated around its center by any angle, such as the arm of a spinning wiper
+///
+/// Unlike [Rectangle::rotated], the rotated edges are not snapped back to the axes, so a rectangle that is rotated by 45 degrees really is diagonal. Use [SampleRotated](crate::render::SampleRotated) to sample the pixels within it.
+///
+/// ```
+/// use figments::prelude::*;
+///
+/// // A thin bar along the diagonal, at an angle of 45 degrees
+/// let bar = RotatedRectangle::new(VirtualCoordinates::center(), 100, 4, 32);
+/// assert!(bar.contains(&VirtualCoordinates::new(150, 150)));
+/// assert!(!bar.contains(&VirtualCoordinates::new(150, 128)));
+/// ```
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct RotatedRectangle {
+    center: VirtualCoordinates,
+    width: u8,
+    height: u8,
+    angle: u8,
+    bounds: Rectangle<Virtual>
+}
+
+impl RotatedRectangle {
+    /// Creates a new rectangle of the given width and height, rotated clockwise around its center where an angle of 256 is a full turn
+    pub fn new(center: VirtualCoordinates, width: u8, height: u8, angle: u8) -> Self {
+        let mut rect = Self {
+            center,
+            width,
+            height,
+            angle,
+            bounds: Rectangle::single(center)
+        };
+        rect.bounds = rect.find_bounds();
+        rect
+    }
+
+    /// Returns the sine and cosine of the angle, scaled to -128 to 127
+    fn sin_cos(&self) -> (i32, i32) {
+        (self.angle.sin8().to_raw() as i32 - 128, self.angle.cos8().to_raw() as i32 - 128)
+    }
+
+    /// Returns true if the coordinate is within the rotated rectangle
+    pub fn contains(&self, coords: &VirtualCoordinates) -> bool {
+        let (sin, cos) = self.sin_cos();
+        let dx = coords.x as i32 - self.center.x as i32;
+        let dy = coords.y as i32 - self.center.y as i32;
+        // Rotate the point backwards onto the axes of the rectangle, which leaves it scaled up by 128
+        let along = dx * cos + dy * sin;
+        let across = dy * cos - dx * sin;
+        along.abs() * 2 <= self.width as i32 * 128 && across.abs() * 2 <= self.height as i32 * 128
+    }
+
+    /// Returns the smallest axis-aligned [Rectangle] that contains every corner of the rotated rectangle, clipped to the [Virtual] space
+    pub const fn bounding_box(&self) -> &Rectangle<Virtual> {
+        &self.bounds
+    }
+
+    fn find_bounds(&self) -> Rectangle<Virtual> {
+        let (sin, cos) = self.sin_cos();
+        let (width, height) = (self.width as u32, self.height as u32);
+        // Round outwards, so the edges of the rectangle are never cut off
+        let extent_x = (width * cos.unsigned_abs() + height * sin.unsigned_abs()).div_ceil(254) as i32;
+        let extent_y = (width * sin.unsigned_abs() + height * cos.unsigned_abs()).div_ceil(254) as i32;
+        let clip = |value: i32| value.clamp(0, u8::MAX as i32) as u8;
+        Rectangle::new_from_coordinates(
+            clip(self.center.x as i32 - extent_x),
+            clip(self.center.y as i32 - extent_y),
+            clip(self.center.x as i32 + extent_x),
+            clip(self.center.y as i32 + extent_y)
+        )
+    }
+}
+
 /// A 2d rectangle specified with two [Coordinates]
+///
+/// Both corners are inclusive, so a rectangle covers every coordinate from `top_left` up to and including `bottom_right`. A rectangle from [Rectangle::single] covers exactly one coordinate, and [Rectangle::everything] covers every coordinate in the space, including [CoordinateOp::MAX].
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::mappings::linear::LinearSpace;
+///
+/// let mut pixbuf = [0u8; 16];
+/// let rect: Rectangle<LinearSpace> = Rectangle::new_from_coordinates(2, 0, 5, 0);
+/// assert_eq!(rect.width(), 3);
+/// assert_eq!(pixbuf.sample(&rect).count(), 4);
+/// ```
 #[derive(PartialEq, Eq, Copy, Clone, PartialOrd)]
 pub struct Rectangle<Space: CoordinateSpace> {
     /// Top left [Coordinates] of the rectangle
@@ -187,6 +486,19 @@ impl<Space: CoordinateSpace> Rectangle<Space> {
         Self::new(Coordinates::new(left, top), Coordinates::new(right, bottom))
     }
 
+    /// Creates a rectangle that covers only a single coordinate
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// let rect = Rectangle::single(VirtualCoordinates::new(4, 2));
+    /// assert_eq!(rect.top_left, VirtualCoordinates::new(4, 2));
+    /// assert_eq!(rect.bottom_right, VirtualCoordinates::new(4, 2));
+    /// ```
+    pub const fn single(coords: Coordinates<Space>) -> Self {
+        Self::new(coords, coords)
+    }
+
     /// Returns a new rectangle that is rotated a number of 90 degree turns around the center of the [CoordinateSpace]
     pub fn rotated(&self, rotation: u8) -> Self {
         let a = self.top_left.rotated(rotation);
@@ -206,12 +518,16 @@ impl<Space: CoordinateSpace> Rectangle<Space> {
         }
     }
 
-    /// Calculates the width of the rectangle
+    /// Calculates the distance between the left and right edges of the rectangle
+    ///
+    /// As both edges are inclusive, the rectangle covers one more column than its width.
     pub fn width(&self) -> Space::Data {
         self.bottom_right.x - self.top_left.x
     }
 
-    /// Calculates the height of the rectangle
+    /// Calculates the distance between the top and bottom edges of the rectangle
+    ///
+    /// As both edges are inclusive, the rectangle covers one more row than its height.
     pub fn height(&self) -> Space::Data {
         self.bottom_right.y - self.top_left.y
     }
@@ -236,6 +552,56 @@ impl<Space: CoordinateSpace> Rectangle<Space> {
         self.bottom_right.y
     }
 
+    /// Returns this rectangle clamped to fit within the given bounds, eg the valid size of a mapping, or None if it lies completely outside of them and there is nothing left to select
+    ///
+    /// This is the same as [Rectangle::intersection], for when one of the rectangles is a limit on the other.
+    pub fn clamp_to(&self, bounds: &Rectangle<Space>) -> Option<Rectangle<Space>> {
+        self.intersection(bounds)
+    }
+
+    /// Returns true if the coordinate falls within the rectangle, including on any of its edges
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// let rect: Rectangle<Virtual> = Rectangle::new_from_coordinates(10, 10, 20, 20);
+    /// assert!(rect.contains(&VirtualCoordinates::new(20, 10)));
+    /// assert!(!rect.contains(&VirtualCoordinates::new(21, 10)));
+    /// ```
+    pub fn contains(&self, point: &Coordinates<Space>) -> bool {
+        point.x >= self.left() && point.x <= self.right() && point.y >= self.top() && point.y <= self.bottom()
+    }
+
+    /// Returns true if there is at least one coordinate that is covered by both rectangles
+    ///
+    /// As the edges are inclusive, two rectangles that share an edge overlap along it, while rectangles that are merely next to each other do not. Upside-down rectangles don't cover any coordinates, so they never intersect anything.
+    pub fn intersects(&self, other: &Rectangle<Space>) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// Returns the rectangle that is covered by both rectangles, or None if they don't overlap
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// let a: Rectangle<Virtual> = Rectangle::new_from_coordinates(0, 0, 127, 255);
+    /// let b = Rectangle::new_from_coordinates(100, 50, 255, 60);
+    /// assert_eq!(a.intersection(&b), Some(Rectangle::new_from_coordinates(100, 50, 127, 60)));
+    ///
+    /// // Rectangles that sit side by side don't share any coordinates
+    /// let c = Rectangle::new_from_coordinates(128, 0, 255, 255);
+    /// assert_eq!(a.intersection(&c), None);
+    /// ```
+    pub fn intersection(&self, other: &Rectangle<Space>) -> Option<Rectangle<Space>> {
+        let overlap = Rectangle::new_from_coordinates(
+            max(self.left(), other.left()),
+            max(self.top(), other.top()),
+            min(self.right(), other.right()),
+            min(self.bottom(), other.bottom())
+        );
+        (overlap.left() <= overlap.right() && overlap.top() <= overlap.bottom()).then_some(overlap)
+    }
+
     /// Produces a row-first iterator of every coordinate contained within this rectangle
     // The strange bounds are due to not relying on std::iter::Step, which is unstable
     pub fn iter_coords(&self) -> impl Iterator<Item = Coordinates<Space>>  + use<'_, Space> {
@@ -264,7 +630,174 @@ impl<Space: CoordinateSpace> From<Rectangle<Space>> for embedded_graphics::primi
     fn from(val: Rectangle<Space>) -> Self {
         embedded_graphics::primitives::Rectangle::new(
             val.top_left.into(),
-            Size::new(val.width().into(), val.height().into())
+            // embedded-graphics sizes count the pixels covered, which includes both inclusive edges
+            Size::new(Into::<u32>::into(val.width()) + 1, Into::<u32>::into(val.height()) + 1)
         )
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mappings::linear::LinearSpace;
+    use crate::mappings::stride::StrideSpace;
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    struct TestSpace {}
+    impl CoordinateSpace for TestSpace {
+        type Data = i32;
+    }
+
+    #[test]
+    fn test_clamp_to() {
+        let bounds: Rectangle<TestSpace> = Rectangle::new_from_coordinates(0, 0, 15, 15);
+
+        // Oversized rectangles are shrunk down to the bounds
+        assert_eq!(Rectangle::new_from_coordinates(-10, -5, 100, 20).clamp_to(&bounds), Some(bounds));
+        assert_eq!(Rectangle::everything().clamp_to(&bounds), Some(bounds));
+
+        // Partially overlapping rectangles are cut down to the overlapping area
+        assert_eq!(Rectangle::new_from_coordinates(8, -4, 30, 4).clamp_to(&bounds), Some(Rectangle::new_from_coordinates(8, 0, 15, 4)));
+
+        // Rectangles that already fit are unchanged
+        let inside = Rectangle::new_from_coordinates(2, 3, 12, 13);
+        assert_eq!(inside.clamp_to(&bounds), Some(inside));
+
+        // Rectangles completely outside don't select anything, instead of being squashed onto the nearest edge
+        assert_eq!(Rectangle::new_from_coordinates(20, 2, 30, 4).clamp_to(&bounds), None);
+        assert_eq!(Rectangle::new_from_coordinates(-8, -8, -1, 4).clamp_to(&bounds), None);
+    }
+
+    #[test]
+    fn test_intersection_edges() {
+        let a: Rectangle<Virtual> = Rectangle::new_from_coordinates(10, 10, 20, 20);
+
+        // Rectangles that share an edge overlap along that edge, since both edges are inclusive
+        let sharing = Rectangle::new_from_coordinates(20, 0, 30, 15);
+        assert_eq!(a.intersection(&sharing), Some(Rectangle::new_from_coordinates(20, 10, 20, 15)));
+        assert!(a.intersects(&sharing) && sharing.intersects(&a));
+
+        // Rectangles that are merely next to each other do not
+        let touching = Rectangle::new_from_coordinates(21, 10, 30, 20);
+        assert_eq!(a.intersection(&touching), None);
+        assert!(!a.intersects(&touching) && !touching.intersects(&a));
+        assert!(!a.intersects(&Rectangle::new_from_coordinates(10, 21, 20, 30)));
+
+        // Zero width and zero height rectangles still cover a line of coordinates
+        let column = Rectangle::new_from_coordinates(15, 0, 15, 255);
+        assert_eq!(a.intersection(&column), Some(Rectangle::new_from_coordinates(15, 10, 15, 20)));
+        let point = Rectangle::single(VirtualCoordinates::new(10, 20));
+        assert_eq!(a.intersection(&point), Some(point));
+        assert!(a.contains(&point.top_left));
+        assert!(!column.intersects(&Rectangle::single(VirtualCoordinates::new(16, 5))));
+
+        // Upside-down rectangles don't cover anything
+        let upside_down = Rectangle::new_from_coordinates(20, 20, 10, 10);
+        assert_eq!(a.intersection(&upside_down), None);
+        assert!(!upside_down.contains(&VirtualCoordinates::new(15, 15)));
+
+        assert_eq!(a.intersection(&Rectangle::everything()), Some(a));
+        assert_eq!(a.intersection(&a), Some(a));
+    }
+
+    #[test]
+    fn test_intersection_in_stride_space() {
+        let a: Rectangle<StrideSpace> = Rectangle::new_from_coordinates(0, 0, 3, 7);
+        let b = Rectangle::new_from_coordinates(2, 5, 9, 20);
+        let overlap = a.intersection(&b).unwrap();
+        assert_eq!((overlap.left(), overlap.top(), overlap.right(), overlap.bottom()), (2, 5, 3, 7));
+        assert_eq!(overlap.iter_coords().count(), 6);
+        assert!(overlap.iter_coords().all(|coords| a.contains(&coords) && b.contains(&coords)), "Every coordinate of the intersection should be in both rectangles");
+
+        assert!(a.intersection(&Rectangle::new_from_coordinates(4, 0, 9, 7)).is_none());
+        assert!(a.intersects(&Rectangle::single(Coordinates::new(3, 7))));
+        assert!(!a.contains(&Coordinates::new(4, 7)));
+    }
+
+    #[test]
+    fn test_virtual_distance_does_not_overflow() {
+        let origin = VirtualCoordinates::top_left();
+        let corner = VirtualCoordinates::new(255, 255);
+        // 255 * sqrt(2) is 360.6
+        assert_eq!(origin.distance_u16(&corner), 360);
+        assert_eq!(corner.distance_u16(&origin), 360);
+        assert_eq!(origin.distance_to(&corner), 255, "The u8 distance should saturate");
+
+        assert_eq!(origin.distance_u16(&VirtualCoordinates::new(255, 0)), 255);
+        assert_eq!(origin.distance_u16(&VirtualCoordinates::new(3, 4)), 5);
+        assert_eq!(origin.distance_to(&VirtualCoordinates::new(3, 4)), 5);
+        assert_eq!(corner.distance_u16(&corner), 0);
+    }
+
+    fn assert_rotations_round_trip<S: CoordinateSpace + PartialEq + Debug>(coords: Coordinates<S>) where S::Data: Debug {
+        for rotation in 0..4 {
+            let rotated = coords.rotated(rotation);
+            let mut turned = rotated;
+            for _ in 0..4 {
+                turned = turned.rotated(1);
+            }
+            assert_eq!(turned, rotated, "Four quarter turns should end where they started");
+            assert_eq!(rotated.rotated(4 - rotation), coords, "Rotating the rest of the way around should undo a rotation of {rotation}");
+            assert_eq!(coords.rotated(rotation + 4), rotated);
+        }
+        assert_eq!(coords.rotated(1).rotated(1), coords.rotated(2));
+        assert_eq!(coords.rotated(1).rotated(2), coords.rotated(3));
+    }
+
+    #[test]
+    fn test_rotations_round_trip() {
+        for (x, y) in [(0, 0), (3, 200), (255, 7), (128, 128), (255, 255)] {
+            assert_rotations_round_trip(VirtualCoordinates::new(x, y));
+        }
+        for (x, y) in [(0, 1000), (65535, 12), (300, 40000)] {
+            assert_rotations_round_trip(Virtual16Coordinates::new(x, y));
+        }
+        for (x, y) in [(0, 0), (17, 4), (usize::MAX, 99)] {
+            assert_rotations_round_trip(Coordinates::<StrideSpace>::new(x, y));
+        }
+
+        // A quarter turn moves the top left corner to the top right, instead of collapsing onto the diagonal
+        assert_eq!(VirtualCoordinates::top_left().rotated(1), VirtualCoordinates::top_right());
+        assert_eq!(VirtualCoordinates::top_left().rotated(3), VirtualCoordinates::bottom_left());
+        assert_eq!(VirtualCoordinates::new(10, 20).rotated(3), VirtualCoordinates::new(20, 245));
+    }
+
+    #[test]
+    fn test_rotated_rect_stays_ordered() {
+        let rect: Rectangle<Virtual> = Rectangle::new_from_coordinates(0, 0, 63, 31);
+        assert_eq!(rect.rotated(1), Rectangle::new_from_coordinates(224, 0, 255, 63));
+        assert_eq!(rect.rotated(2), Rectangle::new_from_coordinates(192, 224, 255, 255));
+        assert_eq!(rect.rotated(3), Rectangle::new_from_coordinates(0, 192, 31, 255));
+    }
+
+    #[test]
+    fn test_everything_and_single_sizes() {
+        let everything: Rectangle<Virtual> = Rectangle::everything();
+        assert_eq!((everything.width(), everything.height()), (255, 255));
+        assert_eq!(everything.iter_coords().count(), 256 * 256);
+
+        let single = Rectangle::single(VirtualCoordinates::new(3, 4));
+        assert_eq!((single.width(), single.height()), (0, 0));
+        assert!(single.iter_coords().eq([VirtualCoordinates::new(3, 4)]));
+    }
+
+    #[test]
+    fn test_wrapped_within() {
+        let bounds: Rectangle<Virtual> = Rectangle::new_from_coordinates(0, 0, 15, 15);
+        assert_eq!(VirtualCoordinates::new(16, 0).wrapped_within(&bounds), VirtualCoordinates::new(0, 0), "Stepping past the right edge should wrap to the left edge");
+        assert_eq!(VirtualCoordinates::new(255, 33).wrapped_within(&bounds), VirtualCoordinates::new(15, 1));
+        assert_eq!(VirtualCoordinates::new(15, 15).wrapped_within(&bounds), VirtualCoordinates::new(15, 15));
+
+        let offset: Rectangle<Virtual> = Rectangle::new_from_coordinates(16, 16, 31, 31);
+        assert_eq!(VirtualCoordinates::new(32, 15).wrapped_within(&offset), VirtualCoordinates::new(16, 31), "Coordinates before the bounds should wrap to the far edge");
+        assert_eq!(VirtualCoordinates::new(200, 1).wrapped_within(&Rectangle::everything()), VirtualCoordinates::new(200, 1));
+
+        let bounds: Rectangle<LinearSpace> = Rectangle::new_from_coordinates(0, 0, 15, 0);
+        assert_eq!(Coordinates::<LinearSpace>::new(16, 0).wrapped_within(&bounds).x, 0);
+        assert_eq!(Coordinates::<LinearSpace>::new(100, 0).wrapped_within(&bounds).x, 4);
+        assert_eq!(Coordinates::<LinearSpace>::new(usize::MAX, 0).wrapped_within(&Rectangle::everything()).x, usize::MAX);
+
+        let signed: Rectangle<TestSpace> = Rectangle::new_from_coordinates(-8, -8, 7, 7);
+        assert_eq!(Coordinates::<TestSpace>::new(8, -9).wrapped_within(&signed), Coordinates::new(-8, 7));
+    }
+}
@@ -1,14 +1,18 @@
 #![no_std]
 #![doc = include_str!("../README.md")]
 //#![warn(missing_docs)]
+mod logging;
 pub mod geometry;
 pub mod mappings;
 pub mod render;
 pub mod liber8tion;
 pub mod pixels;
+pub mod pixbuf;
 pub mod prelude;
 
 #[cfg(feature="alloc")]
 pub mod surface;
 #[cfg(feature="alloc")]
-extern crate alloc;
\ No newline at end of file
+extern crate alloc;
+#[cfg(feature="std")]
+extern crate std;
\ No newline at end of file
@@ -0,0 +1,119 @@
+#![doc = "Box blurs for strips and matrices, like FastLED's blur1d and blur2d"]
+use core::ops::Mul;
+
+use crate::liber8tion::interpolate::{Fract8, Fract8Ops};
+
+/// Blurs `len` pixels that are `stride` pixels apart, starting at `start`
+fn blur_line<T>(pixels: &mut [T], start: usize, stride: usize, len: usize, amount: Fract8) where T: Fract8Ops + Mul<Fract8, Output = T> + Copy + Default {
+    if len < 2 {
+        return;
+    }
+    let keep = Fract8::MAX - amount;
+    let seep = Fract8::from_raw(amount.to_raw() >> 1);
+    let mut carryover = T::default();
+    for i in 0..len {
+        let idx = start + i * stride;
+        let cur = pixels[idx];
+        let part = cur * seep;
+        let mut next = (cur * keep).saturating_add(carryover);
+        if i == 0 {
+            // There is no neighbor to the left, so that share stays put instead of leaking off the end
+            next = next.saturating_add(part);
+        } else {
+            let prev = idx - stride;
+            pixels[prev] = pixels[prev].saturating_add(part);
+        }
+        if i == len - 1 {
+            // Likewise for the share that would have gone to the right
+            next = next.saturating_add(part);
+        }
+        pixels[idx] = next;
+        carryover = part;
+    }
+}
+
+/// Blurs a strip of pixels, where each pixel shares `amount / 2` of itself with each of its neighbors
+///
+/// The blur never adds any brightness, and the pixels at either end keep the share that would have gone past the end of the strip.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::liber8tion::{blur::blur1d, interpolate::Fract8};
+///
+/// let mut pixels = [Rgb::new(0u8, 0, 0); 5];
+/// pixels[2] = Rgb::new(200, 0, 0);
+/// blur1d(&mut pixels, Fract8::from_raw(128));
+/// assert_eq!(pixels.map(|pixel| pixel.r), [0, 50, 99, 50, 0]);
+/// ```
+pub fn blur1d<T>(pixels: &mut [T], amount: Fract8) where T: Fract8Ops + Mul<Fract8, Output = T> + Copy + Default {
+    let len = pixels.len();
+    blur_line(pixels, 0, 1, len, amount);
+}
+
+/// Blurs a `width` x `height` matrix of pixels that is stored row by row, like a [MatrixMapping](crate::mappings::MatrixMapping) with the default origin
+///
+/// Each row is blurred with [blur1d], followed by each column.
+pub fn blur2d<T>(pixels: &mut [T], width: usize, height: usize, amount: Fract8) where T: Fract8Ops + Mul<Fract8, Output = T> + Copy + Default {
+    debug_assert!(pixels.len() >= width * height, "A {width}x{height} matrix needs at least {} pixels", width * height);
+    for y in 0..height {
+        blur_line(pixels, y * width, 1, width, amount);
+    }
+    for x in 0..width {
+        blur_line(pixels, x, width, height, amount);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rgb::Rgb;
+
+    fn total(pixels: &[Rgb<u8>]) -> u32 {
+        pixels.iter().map(|pixel| pixel.r as u32).sum()
+    }
+
+    #[test]
+    fn test_blur1d_spreads_symmetrically() {
+        let mut pixels = [Rgb::new(0u8, 0, 0); 9];
+        pixels[4] = Rgb::new(255, 0, 0);
+        for _ in 0..3 {
+            blur1d(&mut pixels, Fract8::from_raw(100));
+            for offset in 1..=4 {
+                assert_eq!(pixels[4 - offset], pixels[4 + offset], "The blur should spread evenly in both directions: {pixels:?}");
+            }
+            assert!(total(&pixels) <= 255, "The blur should not add any brightness: {pixels:?}");
+        }
+        assert!(pixels[3].r > pixels[2].r && pixels[2].r > pixels[1].r, "The blur should fade out away from the lit pixel: {pixels:?}");
+
+        // Nothing changes without any blur
+        let mut unblurred = [Rgb::new(0u8, 0, 0), Rgb::new(255, 0, 0), Rgb::new(0, 0, 0)];
+        blur1d(&mut unblurred, Fract8::MIN);
+        assert_eq!(unblurred, [Rgb::new(0, 0, 0), Rgb::new(255, 0, 0), Rgb::new(0, 0, 0)]);
+    }
+
+    #[test]
+    fn test_blur_keeps_color_at_edges() {
+        let mut pixels = [Rgb::new(0u8, 0, 0); 4];
+        pixels[0] = Rgb::new(200, 0, 0);
+        blur1d(&mut pixels, Fract8::from_raw(128));
+        assert_eq!(pixels.map(|pixel| pixel.r), [149, 50, 0, 0], "Only the share for the right neighbor should leave the first pixel, give or take rounding");
+
+        let mut matrix = [Rgb::new(0u8, 0, 0); 16];
+        matrix[15] = Rgb::new(200, 0, 0);
+        blur2d(&mut matrix, 4, 4, Fract8::from_raw(128));
+        assert!((195..=200).contains(&total(&matrix)), "The corner of a matrix should not leak color off either edge, give or take rounding: {matrix:?}");
+        assert!(matrix[14].r.abs_diff(matrix[11].r) <= 1, "The blur should spread evenly along rows and columns: {matrix:?}");
+    }
+
+    #[test]
+    fn test_blur2d_spreads_symmetrically() {
+        let mut matrix = [Rgb::new(0u8, 0, 0); 25];
+        matrix[12] = Rgb::new(255, 0, 0);
+        blur2d(&mut matrix, 5, 5, Fract8::from_raw(128));
+        assert!(total(&matrix) <= 255, "The blur should not add any brightness: {matrix:?}");
+        // The four direct neighbors, and then the four diagonal neighbors, should all match
+        assert!([7, 11, 13, 17].iter().all(|idx| matrix[*idx] == matrix[7] && matrix[7].r > 0), "{matrix:?}");
+        assert!([6, 8, 16, 18].iter().all(|idx| matrix[*idx] == matrix[6] && matrix[6].r > 0), "{matrix:?}");
+        assert!(matrix[12].r > matrix[7].r && matrix[7].r > matrix[6].r);
+    }
+}
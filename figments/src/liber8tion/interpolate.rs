@@ -1,9 +1,10 @@
-use core::ops::{Add, BitOr, Div, Mul, Sub};
+use core::ops::{Add, Div, Mul, Sub};
 
 use num::traits::{WrappingAdd, WrappingMul};
 use rgb::*;
 
 use crate::liber8tion::trig::Trig8;
+use crate::pixels::{Gray, Rgbw};
 
 /// An alias for u8 to indicate that the value is a fraction from 0-255 where 0 is 0% and 255 is 100%
 #[derive(Clone, Copy, Default, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -37,6 +38,18 @@ impl Fract8 {
     }
 }
 
+impl From<u8> for Fract8 {
+    fn from(value: u8) -> Self {
+        Fract8(value)
+    }
+}
+
+impl From<Fract8> for u8 {
+    fn from(value: Fract8) -> Self {
+        value.0
+    }
+}
+
 impl WrappingAdd for Fract8 {
     fn wrapping_add(&self, v: &Self) -> Self {
         Fract8(self.0.wrapping_add(v.0))
@@ -123,10 +136,27 @@ impl Mul<Fract8> for usize {
 
     #[inline(always)]
     fn mul(self, rhs: Fract8) -> Self::Output {
-        ((self as u8) * rhs) as usize
+        // Widen before multiplying, so values larger than a u8 are not truncated
+        (self as u64 * rhs.0 as u64 / 255) as usize
     }
 }
 
+/// Scales a value by a 16 bit fraction, where 65535 is 100%
+///
+/// This is the 16 bit equivalent of multiplying by a [Fract8], for when 256 steps are not fine enough.
+///
+/// ```
+/// use figments::liber8tion::interpolate::scale16;
+///
+/// assert_eq!(scale16(1000, u16::MAX), 1000);
+/// assert_eq!(scale16(1000, 32768), 500);
+/// assert_eq!(scale16(1000, 0), 0);
+/// ```
+#[inline(always)]
+pub const fn scale16(value: usize, scale: u16) -> usize {
+    (value as u64 * scale as u64 / u16::MAX as u64) as usize
+}
+
 macro_rules! fract8_color_impl {
     ($color_type:tt $($component:ident),+) => {
 
@@ -164,6 +194,16 @@ macro_rules! fract8_color_impl {
                 }
             }
         }
+
+        impl<T> Fract8Sub for $color_type<T> where T: Fract8Sub {
+
+            #[inline(always)]
+            fn saturating_sub(self, other: Self) -> Self {
+                Self {
+                    $($component: Fract8Sub::saturating_sub(self.$component, other.$component)),*
+                }
+            }
+        }
     };
 }
 
@@ -173,6 +213,8 @@ fract8_color_impl!(Bgr b,g,r);
 fract8_color_impl!(Rgba r,g,b,a);
 fract8_color_impl!(Bgra r,g,b,a);
 fract8_color_impl!(GrayA a,v);
+fract8_color_impl!(Rgbw r,g,b,w);
+fract8_color_impl!(Gray v);
 
 pub trait Fract8Ops {
     fn blend8(self, other: Self, scale: Fract8) -> Self;
@@ -180,6 +222,12 @@ pub trait Fract8Ops {
     fn lerp8by8(self, other: Self, scale: Fract8) -> Self;
 }
 
+/// Subtraction that stops at zero instead of wrapping around, for the types that support it alongside [Fract8Ops]
+pub trait Fract8Sub {
+    /// Subtracts `other` from each channel, stopping at black
+    fn saturating_sub(self, other: Self) -> Self;
+}
+
 impl Fract8Ops for bool {
     
     fn blend8(self, other: Self, scale: Fract8) -> Self {
@@ -203,6 +251,19 @@ impl Fract8Ops for bool {
     }
 }
 
+impl Fract8Sub for bool {
+    fn saturating_sub(self, other: Self) -> Self {
+        self && !other
+    }
+}
+
+/// Divides by 255 and rounds to the nearest integer, without an actual division
+#[inline(always)]
+const fn div255_round(x: u16) -> u8 {
+    let x = x as u32 + 128;
+    ((x + (x >> 8)) >> 8) as u8
+}
+
 impl Fract8Ops for u8 {
 
     #[inline(always)]
@@ -210,7 +271,9 @@ impl Fract8Ops for u8 {
         match scale {
             Fract8::MIN => self,
             Fract8::MAX => other,
-            _ => (((self as u16).wrapping_shl(8).bitor(other as u16)).wrapping_add(other as u16 * scale.0 as u16).wrapping_sub(self as u16 * scale.0 as u16)).wrapping_shr(8) as u8
+            // Scale the difference between the two values, rounding to the nearest value instead of truncating so the blend is never off by one
+            _ if other > self => self + div255_round((other - self) as u16 * scale.0 as u16),
+            _ => self - div255_round((self - other) as u16 * scale.0 as u16)
         }
     }
 
@@ -218,7 +281,7 @@ impl Fract8Ops for u8 {
     fn saturating_add(self, other: Self) -> Self {
         self.saturating_add(other)
     }
-    
+
     #[inline(always)]
     fn lerp8by8(self, other: Self, scale: Fract8) -> Self {
         if other > self {
@@ -244,7 +307,7 @@ impl Fract8Ops for usize {
     fn saturating_add(self, other: Self) -> Self {
         (self as usize).saturating_add(other)
     }
-    
+
     fn lerp8by8(self, other: Self, scale: Fract8) -> Self {
         if other > self {
             let delta = other - self;
@@ -258,11 +321,25 @@ impl Fract8Ops for usize {
     }
 }
 
+impl Fract8Sub for u8 {
+    #[inline(always)]
+    fn saturating_sub(self, other: Self) -> Self {
+        self.saturating_sub(other)
+    }
+}
+
+impl Fract8Sub for usize {
+    #[inline]
+    fn saturating_sub(self, other: Self) -> Self {
+        self.saturating_sub(other)
+    }
+}
+
 #[cfg(feature="embedded-graphics")]
 mod embedded_impl {
     use embedded_graphics::pixelcolor::BinaryColor;
 
-    use super::Fract8Ops;
+    use super::{Fract8Ops, Fract8Sub};
     impl Fract8Ops for BinaryColor {
     
         fn blend8(self, other: Self, scale: super::Fract8) -> Self {
@@ -272,11 +349,17 @@ mod embedded_impl {
         fn saturating_add(self, other: Self) -> Self {
             self
         }
-    
+
         fn lerp8by8(self, other: Self, scale: super::Fract8) -> Self {
             self
         }
     }
+
+    impl Fract8Sub for BinaryColor {
+        fn saturating_sub(self, _other: Self) -> Self {
+            self
+        }
+    }
 }
 
 #[inline]
@@ -304,6 +387,26 @@ pub fn grad8(hash: u8, x: i8, y: i8) -> i8 {
     avg7(u, v)
 }
 
+/// The three dimensional version of [grad8], which picks one of twelve gradient directions from the hash
+pub fn grad8_3d(hash: u8, x: i8, y: i8, z: i8) -> i8 {
+    let hash = hash & 0xF;
+    let mut u = if hash & 8 != 0 { y } else { x };
+    let mut v = match hash {
+        0..4 => y,
+        12 | 14 => x,
+        _ => z
+    };
+
+    if hash & 1 != 0 {
+        u = u.wrapping_neg();
+    }
+    if hash & 2 != 0 {
+        v = v.wrapping_neg();
+    }
+
+    avg7(u, v)
+}
+
 pub fn lerp7by8(a: i8, b: i8, frac: Fract8) -> i8 {
     if b > a {
         let delta: u8 = b.wrapping_sub(a) as u8;
@@ -320,6 +423,29 @@ pub fn lerp8by8<T: Fract8Ops>(a: T, b: T, frac: Fract8) -> T {
     a.lerp8by8(b, frac)
 }
 
+/// Scales every pixel in a buffer by `scale`, like FastLED's `nscale8`
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::liber8tion::interpolate::{nscale8, Fract8};
+///
+/// let mut pixels = [Rgb::new(255u8, 128, 0); 3];
+/// nscale8(&mut pixels, Fract8::from_raw(128));
+/// assert_eq!(pixels, [Rgb::new(128, 64, 0); 3]);
+/// ```
+pub fn nscale8<P: Mul<Fract8, Output = P> + Copy>(pixels: &mut [P], scale: Fract8) {
+    for pixel in pixels.iter_mut() {
+        *pixel = *pixel * scale;
+    }
+}
+
+/// Fades every pixel in a buffer towards black by `amount`, like FastLED's `fadeToBlackBy`
+///
+/// Fading by [Fract8::MAX] turns every pixel off, while fading by [Fract8::MIN] leaves the buffer as it is.
+pub fn fade_to_black_by<P: Mul<Fract8, Output = P> + Copy>(pixels: &mut [P], amount: Fract8) {
+    nscale8(pixels, Fract8::MAX - amount);
+}
+
 pub fn map8(x: Fract8, range_start: Fract8, range_end: Fract8) -> Fract8 {
     let range_width = range_end.0 - range_start.0;
     let mut out = x.0 * Fract8(range_width);
@@ -340,4 +466,60 @@ pub fn ease_in_out_quad(i: Fract8) -> Fract8 {
     } else {
         Fract8(255 - jj2)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_blend8_matches_reference() {
+        for a in 0..=255u8 {
+            for b in 0..=255u8 {
+                for s in 0..=255u8 {
+                    let delta = (b as i32 - a as i32) * s as i32;
+                    // Integer division truncates towards zero, so round away from zero by half of the divisor. There are never any ties, since 255 is odd.
+                    let expected = a as i32 + (delta + delta.signum() * 127) / 255;
+                    assert_eq!(a.blend8(b, Fract8(s)) as i32, expected, "blend8({a}, {b}, {s})");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fade_to_black_by_scales_every_channel() {
+        let colors = [Rgb::new(255u8, 128, 0), Rgb::new(10, 200, 1), Rgb::new(0, 0, 0), Rgb::new(255, 255, 255)];
+        let mut pixels = colors;
+        fade_to_black_by(&mut pixels, Fract8::from_raw(64));
+        for (faded, original) in pixels.iter().zip(colors.iter()) {
+            for (channel, before) in [(faded.r, original.r), (faded.g, original.g), (faded.b, original.b)] {
+                let expected = (before as u32 * 191 / 255) as u8;
+                assert_eq!(channel, expected, "{before} should have been faded to 191/255 of its brightness, not {channel}");
+            }
+        }
+
+        // A few more fades will pull the dimmest channels all the way down to zero, without wrapping around
+        for _ in 0..4 {
+            fade_to_black_by(&mut pixels, Fract8::from_raw(64));
+        }
+        assert_eq!(pixels[1].b, 0);
+        assert!(pixels[3].r > 0 && pixels[3].r < 255);
+
+        fade_to_black_by(&mut pixels, Fract8::MAX);
+        assert_eq!(pixels, [Rgb::new(0, 0, 0); 4], "Fading by the maximum amount should turn everything off");
+
+        let mut unfaded = colors;
+        fade_to_black_by(&mut unfaded, Fract8::MIN);
+        assert_eq!(unfaded, colors, "Fading by nothing should not change anything");
+    }
+
+    #[test]
+    fn test_nscale8_matches_fade() {
+        let mut scaled = [Rgba::new(200u8, 100, 50, 255); 2];
+        let mut faded = scaled;
+        nscale8(&mut scaled, Fract8::from_raw(100));
+        fade_to_black_by(&mut faded, Fract8::from_raw(155));
+        assert_eq!(scaled, faded);
+        assert_eq!(scaled[0], Rgba::new(78, 39, 19, 100));
+    }
+}
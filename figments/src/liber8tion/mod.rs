@@ -1,8 +1,11 @@
 #![doc = "A partial rust implementation of FastLED's lib8tion for fast 8 bit math on microcontrollers"]
 pub mod interpolate;
+pub mod blur;
 pub mod noise;
+pub mod palette;
 pub mod trig;
 pub mod rhythm;
+pub mod random;
 mod sin_table;
 
 use rgb::{Rgb, Rgba};
@@ -24,68 +27,116 @@ impl Hsv {
             value
         }
     }
-}
-
-fn sqrt16(x: u16) -> u16 {
-    if x <= 1 {
-        return x;
-    }
 
-    let mut low = 1; // lower bound
+    /// Converts to RGB with the textbook HSV formula, where each sixth of the hues blends evenly between a primary and a secondary color
+    ///
+    /// The [Into] conversion follows FastLED instead, which spreads the hues unevenly to look better on LEDs. This one is for when colors need to match what other software calls the same hue, where red, yellow, green, cyan, blue and magenta are at hues 0, 43, 85, 128, 171 and 213.
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    /// use figments::liber8tion::Hsv;
+    ///
+    /// assert_eq!(Hsv::new(85, 255, 255).to_rgb_spectrum(), Rgb::new(0, 255, 0));
+    /// assert_eq!(Hsv::new(128, 255, 128).to_rgb_spectrum(), Rgb::new(0, 128, 128));
+    /// ```
+    pub fn to_rgb_spectrum(self) -> Rgb<u8> {
+        let sector = SPECTRUM_SECTORS.iter().rposition(|start| self.hue >= *start).unwrap_or_default();
+        let (start, width) = spectrum_sector(sector);
+        let rise = (self.hue as u16 - start) * 255 / width;
 
-    let mut hi = if x > 7904 {
-        255
-    } else {
-        (x >> 5) + 8 // initial estimate for upper bound
-    };
+        let value = self.value as u16;
+        let floor = value * (255 - self.saturation as u16) / 255;
+        let amplitude = value - floor;
+        let up = (floor + amplitude * rise / 255) as u8;
+        let down = (floor + amplitude * (255 - rise) / 255) as u8;
+        let (top, floor) = (self.value, floor as u8);
 
-    loop {
-        let mid = (low + hi) >> 1;
-        if (mid as u16 * mid as u16) > x {
-            hi = mid - 1;
-        } else {
-            if mid == 255 {
-                return 255;
-            }
-            low = mid + 1;
+        match sector {
+            0 => Rgb::new(top, up, floor),
+            1 => Rgb::new(down, top, floor),
+            2 => Rgb::new(floor, top, up),
+            3 => Rgb::new(floor, down, top),
+            4 => Rgb::new(up, floor, top),
+            _ => Rgb::new(top, floor, down)
         }
-        if hi >= low {
-            break
+    }
+
+    /// Converts from RGB with the textbook formula, which is the inverse of [Hsv::to_rgb_spectrum]
+    ///
+    /// Colors that are converted back with [Hsv::to_rgb_spectrum] come out the same, give or take a little rounding. The [From] conversion is the inverse of the FastLED [Into] conversion instead.
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    /// use figments::liber8tion::Hsv;
+    ///
+    /// assert_eq!(Hsv::from_rgb_spectrum(Rgb::new(0, 0, 255)), Hsv::new(171, 255, 255));
+    /// assert_eq!(Hsv::from_rgb_spectrum(Rgb::new(100, 100, 100)), Hsv::new(0, 0, 100));
+    /// ```
+    pub fn from_rgb_spectrum(rgb: Rgb<u8>) -> Self {
+        let max = rgb.r.max(rgb.g).max(rgb.b);
+        let min = rgb.r.min(rgb.g).min(rgb.b);
+        let chroma = (max - min) as u16;
+        if chroma == 0 {
+            // Grays don't have a hue, so pick red for no special reason
+            return Hsv::new(0, 0, max);
         }
+        let saturation = ((chroma * 255 + max as u16 / 2) / max as u16) as u8;
+
+        // How far the middle channel is between the lowest and highest channels
+        let up = |channel: u8| (channel - min) as u16 * 255 / chroma;
+        let down = |channel: u8| 255 - up(channel);
+        let (sector, rise) = if rgb.r == max && rgb.b == min {
+            (0, up(rgb.g))
+        } else if rgb.g == max && rgb.b == min {
+            (1, down(rgb.r))
+        } else if rgb.g == max && rgb.r == min {
+            (2, up(rgb.b))
+        } else if rgb.b == max && rgb.r == min {
+            (3, down(rgb.g))
+        } else if rgb.b == max && rgb.g == min {
+            (4, up(rgb.r))
+        } else {
+            (5, down(rgb.b))
+        };
+        let (start, width) = spectrum_sector(sector);
+        let hue = start + (rise * width + 127) / 255;
+
+        // The very end of the last sector rounds up to 256, which wraps back around to red where it belongs
+        Hsv::new(hue as u8, saturation, max)
     }
+}
 
-    return low - 1;
+/// Types that can be dimmed in HSV space by only lowering their value, which keeps the hue and saturation intact
+///
+/// Dimming an RGB color scales each channel separately, which rounds the smaller channels down faster than the larger ones and shifts the hue. Dimming the value avoids that.
+pub trait DimValue {
+    /// Dims the value by the given amount, where 0 leaves it unchanged and 255 turns it completely off
+    fn dim_value(&mut self, amount: Fract8);
 }
 
-#[inline]
-fn qadd8(i: u8, j: u8) -> u8 {
-    let t= i as u16 + j as u16;
-    if t > 255 {
-        255
-    } else {
-        t as u8
+impl DimValue for Hsv {
+    #[inline]
+    fn dim_value(&mut self, amount: Fract8) {
+        self.value = self.value * (Fract8::MAX - amount);
     }
 }
 
-#[inline]
-fn qsub8(i: u8, j: u8) -> u8 {
-    let t = i as i16 - j as i16;
-    if t < 0 {
-        0
-    } else {
-        t as u8
+impl DimValue for [Hsv] {
+    fn dim_value(&mut self, amount: Fract8) {
+        self.iter_mut().for_each(|pixel| pixel.dim_value(amount));
     }
 }
 
-// Pre-defined hue values for CHSV objects
-const HUE_RED: u8 = 0;       ///< Red (0°)
-const HUE_ORANGE: u8 = 32;   ///< Orange (45°)
-const HUE_YELLOW: u8 = 64;   ///< Yellow (90°)
-const HUE_GREEN: u8 = 96;    ///< Green (135°)
-const HUE_AQUA: u8 = 128;    ///< Aqua (180°)
-const HUE_BLUE: u8 = 160;    ///< Blue (225°)
-const HUE_PURPLE: u8 = 192;  ///< Purple (270°)
-const HUE_PINK: u8 = 224;     ///< Pink (315°)
+/// The closest hues to each sixth of the color wheel, which are red, yellow, green, cyan, blue and magenta
+const SPECTRUM_SECTORS: [u8; 6] = [0, 43, 85, 128, 171, 213];
+
+/// Returns the first hue of a sector of [SPECTRUM_SECTORS], and how many hues it spans
+#[inline]
+fn spectrum_sector(sector: usize) -> (u16, u16) {
+    let start = SPECTRUM_SECTORS[sector] as u16;
+    let end = SPECTRUM_SECTORS.get(sector + 1).map_or(256, |end| *end as u16);
+    (start, end - start)
+}
 
 impl From<Rgba<u8>> for Hsv {
     fn from(value: Rgba<u8>) -> Self {
@@ -93,181 +144,48 @@ impl From<Rgba<u8>> for Hsv {
     }
 }
 
+/// Converts to HSV by undoing the default [Into] conversion, so colors that are converted back with [Into] come out the same, give or take a little rounding
+///
+/// FastLED's ramps top out slightly below full brightness and dim the colors between the primaries, so the value is worked out from both of the lit channels instead of only the brightest one. Use [Hsv::from_rgb_spectrum] to get the textbook hue of a color instead.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::liber8tion::Hsv;
+///
+/// assert_eq!(Hsv::from(Rgb::new(0u8, 0, 251)), Hsv::new(170, 255, 255));
+/// assert_eq!(Hsv::from(Rgb::new(100u8, 100, 100)), Hsv::new(0, 0, 100));
+///
+/// let orange: Rgb<u8> = Hsv::new(21, 255, 255).into();
+/// assert_eq!(Hsv::from(orange).hue, 21);
+/// ```
 impl From<Rgb<u8>> for Hsv {
-    fn from(rgb: Rgb<u8>) -> Self { //FIXME: it is broken :(
-        let mut r = rgb.r;
-        let mut g = rgb.g;
-        let mut b = rgb.b;
-        let mut h: u8;
-        let mut s: u8;
-        let mut v: u8;
-        
-        // find desaturation
-        let mut desat = 255;
-        if r < desat {
-            desat = r;
-        }
-        if g < desat {
-            desat = g;
-        }
-        if b < desat {
-            desat = b;
-        }
-        
-        // remove saturation from all channels
-        r -= desat;
-        g -= desat;
-        b -= desat;
-        
-        //Serial.print("desat="); Serial.print(desat); Serial.println("");
-        
-        //uint8_t orig_desat = sqrt16( desat * 256);
-        //Serial.print("orig_desat="); Serial.print(orig_desat); Serial.println("");
-        
-        // saturation is opposite of desaturation
-        s = 255 - desat;
-        //Serial.print("s.1="); Serial.print(s); Serial.println("");
-        
-        if s != 255 {
-            // undo 'dimming' of saturation
-            s = 255 - sqrt16( (255-s as u16) * 256) as u8;
+    fn from(rgb: Rgb<u8>) -> Self {
+        let max = rgb.r.max(rgb.g).max(rgb.b);
+        let min = rgb.r.min(rgb.g).min(rgb.b);
+        if max == min {
+            return Hsv::new(0, 0, max);
         }
-        // without lib8tion: float ... ew ... sqrt... double ew, or rather, ew ^ 0.5
-        // if( s != 255 ) s = (255 - (256.0 * sqrt( (float)(255-s) / 256.0)));
-        //Serial.print("s.2="); Serial.print(s); Serial.println("");
-        
-        
-        // at least one channel is now zero
-        // if all three channels are zero, we had a
-        // shade of gray.
-        if (r.wrapping_add(g).wrapping_add(b)) == 0 {
-            // we pick hue zero for no special reason
-            return Hsv::new( 0, 0, 255 - s);
-        }
-        
-        // scale all channels up to compensate for desaturation
-        if s < 255 {
-            if s == 0 {
-                s = 1;
-            }
-            let scaleup = 65535 / (s as u32);
-            r = ((r as u32 * scaleup) / 256) as u8;
-            g = ((g as u32 * scaleup) / 256) as u8;
-            b = ((b as u32 * scaleup) / 256) as u8;
-        }
-        //Serial.print("r.2="); Serial.print(r); Serial.println("");
-        //Serial.print("g.2="); Serial.print(g); Serial.println("");
-        //Serial.print("b.2="); Serial.print(b); Serial.println("");
-        
-        let mut total: u16 = r as u16 + g as u16 + b as u16;
-        
-        //Serial.print("total="); Serial.print(total); Serial.println("");
-        
-        // scale all channels up to compensate for low values
-        if total < 255 {
-            if total == 0 {
-                total = 1;
-            }
-            let scaleup = 65535 / (total as u32);
-            r = ((r as u32 * scaleup) / 256) as u8;
-            g = ((g as u32 * scaleup) / 256) as u8;
-            b = ((b as u32 * scaleup) / 256) as u8;
-        }
-        //Serial.print("r.3="); Serial.print(r); Serial.println("");
-        //Serial.print("g.3="); Serial.print(g); Serial.println("");
-        //Serial.print("b.3="); Serial.print(b); Serial.println("");
-        
-        if total > 255 {
-            v = 255;
+
+        // Each third of the hues keeps one channel at the floor, while the other two ramp between each other
+        let (section, down, up) = if rgb.b == min {
+            (0, rgb.r, rgb.g)
+        } else if rgb.r == min {
+            (1, rgb.g, rgb.b)
         } else {
-            v = qadd8(desat,total as u8);
-            // undo 'dimming' of brightness
-            if v != 255 {
-                v = sqrt16( v as u16 * 256) as u8;
-            }
-            // without lib8tion: float ... ew ... sqrt... double ew, or rather, ew ^ 0.5
-            // if( v != 255) v = (256.0 * sqrt( (float)(v) / 256.0));
-            
-        }
-        
-        // since this wasn't a pure shade of gray,
-        // the interesting question is what hue is it
-        
-        
-        
-        // start with which channel is highest
-        // (ties don't matter)
-        let mut highest = r;
-        if g > highest {
-            highest = g;
-        }
-        if b > highest {
-            highest = b;
-        }
-        
-        if highest == r {
-            // Red is highest.
-            // Hue could be Purple/Pink-Red,Red-Orange,Orange-Yellow
-            if g == 0 {
-                // if green is zero, we're in Purple/Pink-Red
-                h = (HUE_PURPLE.wrapping_add(HUE_PINK)) / 2;
-                h += qsub8(r, 128) * Fract8::from_ratio(48,128);
-            } else if (r - g) > g {
-                // if R-G > G then we're in Red-Orange
-                h = HUE_RED;
-                h += g * Fract8::from_ratio(32,85);
-            } else {
-                // R-G < G, we're in Orange-Yellow
-                h = HUE_ORANGE;
-                h += qsub8((g.wrapping_sub(85)).wrapping_add(171u8.wrapping_sub(r)), 4) * Fract8::from_ratio(32,85); //221
-            }
-            
-        } else if highest == g {
-            // Green is highest
-            // Hue could be Yellow-Green, Green-Aqua
-            if b == 0 {
-                // if Blue is zero, we're in Yellow-Green
-                //   G = 171..255
-                //   R = 171..  0
-                h = HUE_YELLOW;
-                let radj = qsub8(171,r) * Fract8::from_raw(47); //171..0 -> 0..171 -> 0..31
-                let gadj = qsub8(g,171) * Fract8::from_raw(96); //171..255 -> 0..84 -> 0..31;
-                let rgadj = radj + gadj;
-                let hueadv = rgadj / 2;
-                h += hueadv;
-                //h += scale8( qadd8( 4, qadd8((g - 128), (128 - r))),
-                //             FIXFRAC8(32,255)); //
-            } else {
-                // if Blue is nonzero we're in Green-Aqua
-                if (g-b) > b {
-                    h = HUE_GREEN;
-                    h += b * Fract8::from_ratio(32,85);
-                } else {
-                    h = HUE_AQUA;
-                    h += qsub8(b, 85) * Fract8::from_ratio(8,42);
-                }
-            }
-            
-        } else /* highest == b */ {
-            // Blue is highest
-            // Hue could be Aqua/Blue-Blue, Blue-Purple, Purple-Pink
-            if r == 0 {
-                // if red is zero, we're in Aqua/Blue-Blue
-                h = HUE_AQUA + ((HUE_BLUE - HUE_AQUA) / 4);
-                h += qsub8(b, 128) * Fract8::from_ratio(24,128);
-            } else if (b-r) > r {
-                // B-R > R, we're in Blue-Purple
-                h = HUE_BLUE;
-                h += r * Fract8::from_ratio(32,85);
-            } else {
-                // B-R < R, we're in Purple-Pink
-                h = HUE_PURPLE;
-                h += qsub8(r, 85) * Fract8::from_ratio(32,85);
-            }
-        }
-        
-        h += 1;
-        return Hsv::new( h, s, v);
+            (2, rgb.b, rgb.r)
+        };
+        let (up, down) = ((up - min) as u16, (down - min) as u16);
+        let span = up + down;
+        let offset = (up * 63 + span / 2) / span;
+
+        // The two ramps add up to 63/64ths of the amplitude
+        let amplitude = (span * 64).div_ceil(63);
+        let value = (min as u16 + amplitude).min(255);
+        let saturation = 255 - (min as u16 * 256).div_ceil(value).min(255);
+
+        // The hue was squeezed into 0..192 before picking the section, so spread it back out
+        let hue = ((section * 64 + offset) * 255).div_ceil(191);
+        Hsv::new(hue.min(255) as u8, saturation as u8, value as u8)
     }
 }
 
@@ -278,6 +196,9 @@ impl Into<Rgba<u8>> for Hsv {
     }
 }
 
+/// Converts to RGB like FastLED's raw rainbow, where the hues are split into thirds that each ramp from one primary color to the next
+///
+/// The [From] conversion is the inverse of this one, and [Hsv::to_rgb_spectrum] is the textbook conversion.
 impl Into<Rgb<u8>> for Hsv {
     //TODO: Borrowed from FastLED
     fn into(self) -> Rgb<u8> {
@@ -309,7 +230,157 @@ impl Into<Rgb<u8>> for Hsv {
         match section {
             1 => Rgb::new(brightness_floor, rampdown_adj_with_floor, rampup_adj_with_floor),
             0 => Rgb::new(rampdown_adj_with_floor, rampup_adj_with_floor, brightness_floor),
+            // Scaling the hue by 191 keeps it below 192, so this can only be section 2
             _ => Rgb::new(rampup_adj_with_floor, brightness_floor, rampdown_adj_with_floor)
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dim_value_keeps_hue_and_saturation() {
+        let original: [Hsv; 4] = core::array::from_fn(|idx| Hsv::new(idx as u8 * 60, 200 - idx as u8 * 10, 255));
+        let mut pixbuf = original;
+        pixbuf.dim_value(Fract8::from_raw(128));
+
+        for (dimmed, original) in pixbuf.iter().zip(original.iter()) {
+            assert_eq!(dimmed.hue, original.hue);
+            assert_eq!(dimmed.saturation, original.saturation);
+            assert!(dimmed.value < original.value && dimmed.value > 0, "{dimmed:?} was not dimmed by half from {original:?}");
+        }
+
+        pixbuf.dim_value(Fract8::MIN);
+        assert_eq!(pixbuf[0].value, 127);
+
+        pixbuf.dim_value(Fract8::MAX);
+        assert!(pixbuf.iter().all(|pixel| pixel.value == 0));
+    }
+
+    #[test]
+    fn test_hsv_sweep_is_continuous() {
+        // Any mistake in the section boundaries shows up as a sudden jump between neighboring hues
+        const MAX_STEP: i16 = 8;
+        let colors: [Rgb<u8>; 256] = core::array::from_fn(|hue| Hsv::new(hue as u8, 255, 255).into());
+        for hue in 0..256 {
+            let current = colors[hue];
+            let next = colors[(hue + 1) % 256];
+            for (a, b) in [(current.r, next.r), (current.g, next.g), (current.b, next.b)] {
+                assert!((a as i16 - b as i16).abs() <= MAX_STEP, "Hue {hue} jumps from {current:?} to {next:?}");
+            }
+        }
+
+        // Like FastLED's raw spectrum, the ramps top out slightly below full brightness
+        assert_eq!(colors[0], Rgb::new(251, 0, 0), "Red should be the start of the sweep");
+        assert_eq!(colors[85].b, 0, "Blue should not appear until the second section");
+    }
+
+    #[test]
+    fn test_spectrum_hits_pure_colors() {
+        let expected = [
+            (0, Rgb::new(255, 0, 0)),
+            (43, Rgb::new(255, 255, 0)),
+            (85, Rgb::new(0, 255, 0)),
+            (128, Rgb::new(0, 255, 255)),
+            (171, Rgb::new(0, 0, 255)),
+            (213, Rgb::new(255, 0, 255))
+        ];
+        for (hue, color) in expected {
+            assert_eq!(Hsv::new(hue, 255, 255).to_rgb_spectrum(), color, "Hue {hue} should be a pure color");
+            assert_eq!(Hsv::new(hue, 0, 200).to_rgb_spectrum(), Rgb::new(200, 200, 200), "Hue {hue} should be gray without any saturation");
+            assert_eq!(Hsv::new(hue, 255, 0).to_rgb_spectrum(), Rgb::new(0, 0, 0), "Hue {hue} should be black without any value");
+        }
+
+        // Halfway between red and yellow is orange, with a little white mixed in by the saturation
+        assert_eq!(Hsv::new(21, 255, 255).to_rgb_spectrum(), Rgb::new(255, 124, 0));
+        assert_eq!(Hsv::new(21, 128, 255).to_rgb_spectrum(), Rgb::new(255, 189, 127));
+
+        // The sweep is smooth all the way around, including from magenta back to red
+        for hue in 0..=255u8 {
+            let current = Hsv::new(hue, 255, 255).to_rgb_spectrum();
+            let next = Hsv::new(hue.wrapping_add(1), 255, 255).to_rgb_spectrum();
+            for (a, b) in [(current.r, next.r), (current.g, next.g), (current.b, next.b)] {
+                assert!(a.abs_diff(b) <= 7, "Hue {hue} jumps from {current:?} to {next:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_rgb_to_hsv_lands_in_the_right_sextant() {
+        // Every hue that is between two of the pure colors, and which channels are rising or falling there
+        let sextant = |hue: u8| SPECTRUM_SECTORS.iter().rposition(|start| hue >= *start).unwrap();
+        for low in (0..=200u8).step_by(50) {
+            for mid in (low..=255).step_by(17) {
+                for high in (mid.max(low + 1)..=255).step_by(23) {
+                    let colors = [
+                        (Rgb::new(high, mid, low), 0),
+                        (Rgb::new(mid, high, low), 1),
+                        (Rgb::new(low, high, mid), 2),
+                        (Rgb::new(low, mid, high), 3),
+                        (Rgb::new(mid, low, high), 4),
+                        (Rgb::new(high, low, mid), 5)
+                    ];
+                    for (color, expected) in colors {
+                        let hsv = Hsv::from_rgb_spectrum(color);
+                        assert_eq!(hsv.value, high, "{color:?} should keep its brightest channel as the value");
+                        // The boundaries between sextants can round into either side
+                        let sextants = [expected, (expected + 1) % 6, (expected + 5) % 6];
+                        assert!(sextants.contains(&sextant(hsv.hue)), "{color:?} became {hsv:?}, which is not in sextant {expected}");
+                        if mid > low && mid < high && (high - low) > 32 {
+                            assert_eq!(sextant(hsv.hue), expected, "{color:?} became {hsv:?}, which is not in sextant {expected}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rgb_to_hsv_round_trips() {
+        let pure = [
+            Rgb::new(255, 0, 0), Rgb::new(255, 255, 0), Rgb::new(0, 255, 0),
+            Rgb::new(0, 255, 255), Rgb::new(0, 0, 255), Rgb::new(255, 0, 255),
+            Rgb::new(255, 255, 255), Rgb::new(0, 0, 0), Rgb::new(0, 0, 128), Rgb::new(77, 77, 77)
+        ];
+        for color in pure {
+            assert_eq!(Hsv::from_rgb_spectrum(color).to_rgb_spectrum(), color, "{color:?} should survive a round trip through {:?}", Hsv::from_rgb_spectrum(color));
+            assert_eq!(Hsv::from_rgb_spectrum(Hsv::from_rgb_spectrum(color).to_rgb_spectrum()), Hsv::from_rgb_spectrum(color));
+        }
+
+        // Other colors come back close
+        for r in (0..=255u8).step_by(15) {
+            for g in (0..=255u8).step_by(15) {
+                for b in (0..=255u8).step_by(15) {
+                    let color = Rgb::new(r, g, b);
+                    let round_trip = Hsv::from_rgb_spectrum(color).to_rgb_spectrum();
+                    for (a, b) in [(color.r, round_trip.r), (color.g, round_trip.g), (color.b, round_trip.b)] {
+                        assert!(a.abs_diff(b) <= 4, "{color:?} came back as {round_trip:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_into_rgb_round_trips() {
+        for hue in 0..=255u8 {
+            for saturation in (0..=255u8).step_by(15) {
+                for value in (0..=255u8).step_by(15) {
+                    let hsv = Hsv::new(hue, saturation, value);
+                    let color: Rgb<u8> = hsv.into();
+                    let round_trip: Rgb<u8> = Hsv::from(color).into();
+                    for (a, b) in [(color.r, round_trip.r), (color.g, round_trip.g), (color.b, round_trip.b)] {
+                        assert!(a.abs_diff(b) <= 1, "{hsv:?} became {color:?}, but came back as {round_trip:?}");
+                    }
+                }
+            }
+
+            // Fully saturated colors keep their hue, except where neighboring hues convert to the same color, which includes wrapping around from magenta to red
+            let back = Hsv::from(Into::<Rgb<u8>>::into(Hsv::new(hue, 255, 255)));
+            assert!(back.hue.wrapping_sub(hue).min(hue.wrapping_sub(back.hue)) <= 2, "Hue {hue} came back as {back:?}");
+            assert_eq!(back.saturation, 255);
+        }
+    }
+}
@@ -58,3 +58,150 @@ pub fn inoise8(x: i16, y: i16) -> Fract8 {
     n = n.wrapping_add(64);
     Fract8::from_raw((n as u8).saturating_add(n as u8))
 }
+
+#[inline]
+fn inoise8_3d_raw(x_src: u16, y_src: u16, z_src: u16) -> i8 {
+    let x = x_src.wrapping_shr(8) as u8;
+    let y = y_src.wrapping_shr(8) as u8;
+    let z = z_src.wrapping_shr(8) as u8;
+
+    let a  = get_cube(x).wrapping_add(y);
+    let aa = get_cube(a).wrapping_add(z);
+    let ab = get_cube(a.wrapping_add(1)).wrapping_add(z);
+    let b = get_cube(x.wrapping_add(1)).wrapping_add(y);
+    let ba = get_cube(b).wrapping_add(z);
+    let bb = get_cube(b.wrapping_add(1)).wrapping_add(z);
+
+    let u = ease_in_out_quad(Fract8::from_raw(x_src as u8));
+    let v = ease_in_out_quad(Fract8::from_raw(y_src as u8));
+    let w = ease_in_out_quad(Fract8::from_raw(z_src as u8));
+
+    let xx = ((x_src as u8).wrapping_shr(1) & 0x7f) as i8;
+    let yy = ((y_src as u8).wrapping_shr(1) & 0x7f) as i8;
+    let zz = ((z_src as u8).wrapping_shr(1) & 0x7f) as i8;
+    let n = 0x80u8 as i8;
+
+    // Interpolate across the near face of the cube, then the far face, and then between the two faces
+    let x1 = lerp7by8(grad8_3d(get_cube(aa), xx, yy, zz), grad8_3d(get_cube(ba), xx.wrapping_sub(n), yy, zz), u);
+    let x2 = lerp7by8(grad8_3d(get_cube(ab), xx, yy.wrapping_sub(n), zz), grad8_3d(get_cube(bb), xx.wrapping_sub(n), yy.wrapping_sub(n), zz), u);
+    let y1 = lerp7by8(x1, x2, v);
+
+    let x1 = lerp7by8(grad8_3d(get_cube(aa.wrapping_add(1)), xx, yy, zz.wrapping_sub(n)), grad8_3d(get_cube(ba.wrapping_add(1)), xx.wrapping_sub(n), yy, zz.wrapping_sub(n)), u);
+    let x2 = lerp7by8(grad8_3d(get_cube(ab.wrapping_add(1)), xx, yy.wrapping_sub(n), zz.wrapping_sub(n)), grad8_3d(get_cube(bb.wrapping_add(1)), xx.wrapping_sub(n), yy.wrapping_sub(n), zz.wrapping_sub(n)), u);
+    let y2 = lerp7by8(x1, x2, v);
+
+    lerp7by8(y1, y2, w)
+}
+
+/// Three dimensional noise, which is the same as FastLED's 3D `inoise8`
+///
+/// Animating a 2D field by passing time as the third axis moves much more smoothly than scrolling the inputs of [inoise8], since the whole field morphs instead of sliding across the display. A shader would pass `(coords.x, coords.y, frame)`.
+///
+/// ```
+/// use figments::liber8tion::noise::inoise8_3d;
+///
+/// assert_eq!(inoise8_3d(1234, 5678, 42), inoise8_3d(1234, 5678, 42));
+/// ```
+#[inline]
+pub fn inoise8_3d(x: i16, y: i16, z: i16) -> Fract8 {
+    let mut n = inoise8_3d_raw(x as u16, y as u16, z as u16);
+    n = n.wrapping_add(64);
+    Fract8::from_raw((n as u8).saturating_add(n as u8))
+}
+
+/// Fractal Brownian motion, which sums several octaves of [inoise8] at doubling frequencies and halving amplitudes
+///
+/// A single octave is identical to [inoise8], while each additional octave adds finer detail on top, which is useful for cloud or marble textures. The `frame` scrolls each octave through the noise field, so the texture can be animated. At most 8 octaves are used.
+///
+/// ```
+/// use figments::liber8tion::noise::{fbm8, inoise8};
+///
+/// assert_eq!(fbm8(1234, 5678, 1, 0), inoise8(1234, 5678));
+/// ```
+pub fn fbm8(x: i16, y: i16, octaves: u8, frame: u16) -> Fract8 {
+    let mut total = 0u32;
+    let mut weight = 0u32;
+    for octave in 0..octaves.clamp(1, 8) {
+        let amplitude = 128u32 >> octave;
+        // Each octave is also shifted by a different amount, so the octaves don't all line up at the origin
+        let shift = (octave as i16).wrapping_mul(0x1000);
+        let octave_x = x.wrapping_shl(octave as u32).wrapping_add(shift).wrapping_add(frame as i16);
+        let octave_y = y.wrapping_shl(octave as u32).wrapping_sub(shift).wrapping_sub(frame as i16);
+        total += inoise8(octave_x, octave_y).to_raw() as u32 * amplitude;
+        weight += amplitude;
+    }
+    // Dividing by the total weight keeps the sum in the same range as a single octave
+    Fract8::from_raw((total / weight) as u8)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns how much of the variance of the noise is found between close neighbors, in thousandths, which grows as finer detail is added
+    fn detail(octaves: u8) -> i64 {
+        const STEP: i16 = 16;
+        let (mut count, mut sum, mut sum_sq, mut diff_sq) = (0, 0, 0, 0);
+        for y in (0..8192).step_by(97) {
+            for x in (0..8192).step_by(STEP as usize) {
+                let value = fbm8(x, y, octaves, 0).to_raw() as i64;
+                let neighbor = fbm8(x + STEP, y, octaves, 0).to_raw() as i64;
+                count += 1;
+                sum += value;
+                sum_sq += value * value;
+                diff_sq += (value - neighbor).pow(2);
+            }
+        }
+        let variance = sum_sq / count - (sum / count).pow(2);
+        diff_sq * 1000 / count / variance
+    }
+
+    #[test]
+    fn test_fbm_octaves_add_detail() {
+        let detail: [i64; 4] = core::array::from_fn(|idx| detail(idx as u8 + 1));
+        assert!(detail.windows(2).all(|pair| pair[0] < pair[1]), "Each octave should add finer detail: {detail:?}");
+    }
+
+    #[test]
+    fn test_fbm_stays_in_range() {
+        for octaves in [1, 2, 4, 8] {
+            let (mut min, mut max) = (u8::MAX, u8::MIN);
+            for y in (i16::MIN..i16::MAX).step_by(997) {
+                for x in (i16::MIN..i16::MAX).step_by(991) {
+                    let value = fbm8(x, y, octaves, 1000).to_raw();
+                    min = min.min(value);
+                    max = max.max(value);
+                    assert_eq!(fbm8(x, y, 255, 1000), fbm8(x, y, 8, 1000), "Octaves past 8 should be ignored");
+                }
+            }
+            // Averaging the octaves together should never flatten the texture out
+            assert!(max - min > 64, "{octaves} octaves only ranged from {min} to {max}");
+        }
+    }
+
+    #[test]
+    fn test_inoise8_3d_is_continuous() {
+        let (mut min, mut max) = (u8::MAX, u8::MIN);
+        for z in (0..4096).step_by(61) {
+            for y in (0..4096).step_by(127) {
+                for x in (0..4096).step_by(131) {
+                    let value = inoise8_3d(x, y, z).to_raw();
+                    min = min.min(value);
+                    max = max.max(value);
+                    for (dx, dy, dz) in [(1, 0, 0), (0, 1, 0), (0, 0, 1)] {
+                        let neighbor = inoise8_3d(x + dx, y + dy, z + dz).to_raw();
+                        assert!(value.abs_diff(neighbor) <= 8, "Stepping by ({dx}, {dy}, {dz}) from ({x}, {y}, {z}) jumped from {value} to {neighbor}");
+                    }
+                }
+            }
+        }
+        assert!(max - min > 128, "The noise only ranged from {min} to {max}");
+    }
+
+    #[test]
+    fn test_inoise8_3d_is_deterministic() {
+        let sample = |z: i16| core::array::from_fn::<_, 16, _>(|idx| inoise8_3d(idx as i16 * 300, idx as i16 * -700, z));
+        assert_eq!(sample(1000), sample(1000));
+        assert_ne!(sample(1000), sample(1200), "Moving along the third axis should change the field");
+    }
+}
@@ -0,0 +1,182 @@
+#![doc = "Gradient palettes of 16 colors, like FastLED's CRGBPalette16"]
+use rgb::Rgb;
+
+use crate::liber8tion::interpolate::{Fract8, Fract8Ops};
+
+const fn hex(color: u32) -> Rgb<u8> {
+    Rgb::new((color >> 16) as u8, (color >> 8) as u8, color as u8)
+}
+
+const fn from_hex(colors: [u32; 16]) -> Palette16 {
+    let mut entries = [Rgb::new(0, 0, 0); 16];
+    let mut idx = 0;
+    while idx < 16 {
+        entries[idx] = hex(colors[idx]);
+        idx += 1;
+    }
+    Palette16 { entries }
+}
+
+/// A palette of 16 colors that can be looked up with any 8 bit index, blending smoothly between neighboring entries
+///
+/// Shaders can map any 8 bit value onto a color, such as the output of [inoise8](crate::liber8tion::noise::inoise8):
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::liber8tion::palette::Palette16;
+///
+/// let palette = Palette16::heat();
+/// assert_eq!(palette.color_at(0), Rgb::new(0, 0, 0));
+/// assert_eq!(palette.color_at(80), Rgb::new(255, 0, 0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette16 {
+    /// The colors of the palette, each of which is 16 indexes apart
+    pub entries: [Rgb<u8>; 16]
+}
+
+impl Default for Palette16 {
+    fn default() -> Self {
+        Self::rainbow()
+    }
+}
+
+impl Palette16 {
+    /// Creates a palette from 16 colors
+    pub const fn new(entries: [Rgb<u8>; 16]) -> Self {
+        Self { entries }
+    }
+
+    /// Builds the 16 entries of a palette from a gradient, which is a list of (position, color) stops sorted by their position
+    ///
+    /// Each entry is spaced evenly across the gradient, with the first entry at position 0 and the last at 255. Positions before the first stop or after the last stop take the color of the nearest stop.
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    /// use figments::liber8tion::palette::Palette16;
+    ///
+    /// let sunset = Palette16::from_gradient(&[(0, Rgb::new(255, 0, 0)), (255, Rgb::new(0, 0, 255))]);
+    /// assert_eq!(sunset.entries[0], Rgb::new(255, 0, 0));
+    /// assert_eq!(sunset.entries[15], Rgb::new(0, 0, 255));
+    /// ```
+    pub fn from_gradient(stops: &[(u8, Rgb<u8>)]) -> Self {
+        let entries = core::array::from_fn(|idx| {
+            let position = idx as u8 * 17;
+            let Some(after) = stops.iter().position(|(stop, _)| *stop >= position) else {
+                return stops.last().map(|(_, color)| *color).unwrap_or_default();
+            };
+            let (end, end_color) = stops[after];
+            match after.checked_sub(1).map(|before| stops[before]) {
+                Some((start, start_color)) if end > position => {
+                    start_color.blend8(end_color, Fract8::from_ratio(position - start, end - start))
+                },
+                _ => end_color
+            }
+        });
+        Self { entries }
+    }
+
+    /// Looks up the color at an index, where every 16 indexes is the next entry in the palette
+    ///
+    /// Indexes in between entries are blended linearly. Like FastLED, the last entry blends back towards the first one, so the palette can be cycled through smoothly.
+    pub fn color_at(&self, index: u8) -> Rgb<u8> {
+        let entry = (index >> 4) as usize;
+        let fraction = index & 0x0F;
+        let color = self.entries[entry];
+        match fraction {
+            0 => color,
+            _ => color.blend8(self.entries[(entry + 1) % 16], Fract8::from_raw(fraction << 4))
+        }
+    }
+
+    /// Every hue of the rainbow, which is the same as FastLED's RainbowColors_p
+    pub const fn rainbow() -> Self {
+        from_hex([
+            0xFF0000, 0xD52A00, 0xAB5500, 0xAB7F00, 0xABAB00, 0x56D500, 0x00FF00, 0x00D52A,
+            0x00AB55, 0x0056AA, 0x0000FF, 0x2A00D5, 0x5500AB, 0x7F0081, 0xAB0055, 0xD5002B
+        ])
+    }
+
+    /// Black through red, yellow and white, which is the same as FastLED's HeatColors_p
+    pub const fn heat() -> Self {
+        from_hex([
+            0x000000, 0x330000, 0x660000, 0x990000, 0xCC0000, 0xFF0000, 0xFF3300, 0xFF6600,
+            0xFF9900, 0xFFCC00, 0xFFFF00, 0xFFFF33, 0xFFFF66, 0xFFFF99, 0xFFFFCC, 0xFFFFFF
+        ])
+    }
+
+    /// Deep blues and sea greens, which is the same as FastLED's OceanColors_p
+    pub const fn ocean() -> Self {
+        from_hex([
+            0x191970, 0x00008B, 0x191970, 0x000080, 0x00008B, 0x0000CD, 0x2E8B57, 0x008080,
+            0x5F9EA0, 0x0000FF, 0x008B8B, 0x6495ED, 0x7FFFD4, 0x2E8B57, 0x00FFFF, 0x87CEFA
+        ])
+    }
+
+    /// Greens, which is the same as FastLED's ForestColors_p
+    pub const fn forest() -> Self {
+        from_hex([
+            0x006400, 0x006400, 0x556B2F, 0x006400, 0x008000, 0x228B22, 0x6B8E23, 0x008000,
+            0x2E8B57, 0x66CDAA, 0x32CD32, 0x9ACD32, 0x90EE90, 0x7CFC00, 0x66CDAA, 0x228B22
+        ])
+    }
+
+    /// Purples, reds and yellows without any greens, which is the same as FastLED's PartyColors_p
+    pub const fn party() -> Self {
+        from_hex([
+            0x5500AB, 0x84007C, 0xB5004B, 0xE5001B, 0xE81700, 0xB84700, 0xAB7700, 0xABAB00,
+            0xAB5500, 0xDD2200, 0xF2000E, 0xC2003E, 0x8F0071, 0x5F00A1, 0x2F00D0, 0x0007F9
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_color_at_entries_and_midpoints() {
+        let palette = Palette16::heat();
+        for (idx, entry) in palette.entries.iter().enumerate() {
+            assert_eq!(palette.color_at(idx as u8 * 16), *entry, "Index {} should be exactly entry {idx}", idx * 16);
+        }
+
+        // Halfway between black and dark red
+        assert_eq!(palette.color_at(8), Rgb::new(26, 0, 0));
+        assert_eq!(palette.color_at(8), palette.entries[0].blend8(palette.entries[1], Fract8::from_raw(128)));
+
+        // Each step between two entries gets closer to the next one
+        for index in 80..95 {
+            assert!(palette.color_at(index + 1).g >= palette.color_at(index).g, "The red to orange gradient should not go backwards at {index}");
+        }
+
+        // The end of the palette wraps back around towards the first entry
+        assert_eq!(palette.color_at(255), palette.entries[15].blend8(palette.entries[0], Fract8::from_raw(240)));
+    }
+
+    #[test]
+    fn test_from_gradient_stops() {
+        let red = Rgb::new(255u8, 0, 0);
+        let blue = Rgb::new(0u8, 0, 255);
+        let palette = Palette16::from_gradient(&[(0, red), (255, blue)]);
+        assert_eq!(palette.entries[0], red);
+        assert_eq!(palette.entries[15], blue);
+        // Entries 7 and 8 straddle the middle of the gradient
+        assert_eq!(palette.entries[7], red.blend8(blue, Fract8::from_raw(119)));
+        assert_eq!(palette.entries[8], red.blend8(blue, Fract8::from_raw(136)));
+
+        // Colors outside of the stops are held at the nearest stop
+        let held = Palette16::from_gradient(&[(68, red), (187, blue)]);
+        assert_eq!(&held.entries[..5], &[red; 5]);
+        assert_eq!(&held.entries[11..], &[blue; 5]);
+        assert_eq!(held.entries[7], red.blend8(blue, Fract8::from_ratio(51, 119)));
+
+        // Stops that are exactly on an entry are used as they are
+        let stepped = Palette16::from_gradient(&[(0, red), (17, red), (17, blue), (255, blue)]);
+        assert_eq!(stepped.entries[1], red);
+        assert_eq!(stepped.entries[2], blue);
+
+        assert_eq!(Palette16::from_gradient(&[]).entries, [Rgb::new(0, 0, 0); 16]);
+        assert_eq!(Palette16::from_gradient(&[(100, blue)]).entries, [blue; 16]);
+    }
+}
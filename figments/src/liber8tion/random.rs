@@ -0,0 +1,122 @@
+/// Mixes a set of coordinates and a seed into a well distributed 32 bit value
+#[inline]
+const fn mix32(x: u32, y: u32, seed: u32) -> u32 {
+    let mut h = seed ^ x.wrapping_mul(0x9E37_79B1) ^ y.wrapping_mul(0x85EB_CA77);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2C1B_3C6D);
+    h ^= h >> 12;
+    h = h.wrapping_mul(0x297A_2D39);
+    h ^= h >> 15;
+    h
+}
+
+/// Hashes a pair of coordinates and a seed (eg, a frame number) into a random-looking but deterministic 8 bit value
+#[inline]
+pub const fn hash8(x: u32, y: u32, seed: u32) -> u8 {
+    (mix32(x, y, seed) >> 24) as u8
+}
+
+
+/// Hashes a single seed value into a random-looking but deterministic 8 bit value, useful for spatially-varying randomness that stays fixed within a frame
+#[inline]
+pub const fn random8_from(seed: u32) -> u8 {
+    (mix32(seed, 0, 0) >> 24) as u8
+}
+
+const RAND16_MULTIPLIER: u16 = 2053;
+const RAND16_ADD: u16 = 13849;
+
+/// A FastLED-style 16 bit pseudo random number generator with explicit state, since no_std has no thread RNG
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rand16 {
+    seed: u16
+}
+
+impl Default for Rand16 {
+    fn default() -> Self {
+        Self::new(1337)
+    }
+}
+
+impl Rand16 {
+    /// Creates a new generator from a seed. The same seed always produces the same sequence of numbers.
+    pub const fn new(seed: u16) -> Self {
+        Self {
+            seed
+        }
+    }
+
+    /// Returns the current seed of the generator
+    pub const fn seed(&self) -> u16 {
+        self.seed
+    }
+
+    /// Returns the next pseudo random 16 bit number
+    #[inline]
+    pub fn random16(&mut self) -> u16 {
+        self.seed = self.seed.wrapping_mul(RAND16_MULTIPLIER).wrapping_add(RAND16_ADD);
+        self.seed
+    }
+
+    /// Returns the next pseudo random 8 bit number
+    #[inline]
+    pub fn random8(&mut self) -> u8 {
+        let next = self.random16();
+        // The low byte of a LCG is not very random, so we mix in the high byte
+        ((next & 0xff) as u8).wrapping_add((next >> 8) as u8)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_roughly_uniform(mut next: impl FnMut() -> u8) {
+        const SAMPLES: usize = 16 * 1024;
+        let mut buckets = [0usize; 16];
+        for _ in 0..SAMPLES {
+            buckets[(next() >> 4) as usize] += 1;
+        }
+        let expected = SAMPLES / buckets.len();
+        for (idx, count) in buckets.iter().enumerate() {
+            assert!(count.abs_diff(expected) < expected / 4, "Bucket {idx} has {count} samples, but expected around {expected}: {buckets:?}");
+        }
+    }
+
+    #[test]
+    fn test_random8_is_uniform() {
+        let mut rng = Rand16::new(42);
+        assert_roughly_uniform(|| rng.random8());
+    }
+
+    #[test]
+    fn test_random16_is_uniform() {
+        let mut rng = Rand16::new(42);
+        assert_roughly_uniform(|| (rng.random16() >> 8) as u8);
+    }
+
+    #[test]
+    fn test_random8_from_is_uniform() {
+        let mut seed = 0;
+        assert_roughly_uniform(|| {
+            seed += 1;
+            random8_from(seed)
+        });
+    }
+
+    #[test]
+    fn test_seeding_is_reproducible() {
+        let mut a = Rand16::new(1234);
+        let mut b = Rand16::new(1234);
+        let mut c = Rand16::new(4321);
+        let a_seq: [u8; 32] = core::array::from_fn(|_| a.random8());
+        let b_seq: [u8; 32] = core::array::from_fn(|_| b.random8());
+        let c_seq: [u8; 32] = core::array::from_fn(|_| c.random8());
+        assert_eq!(a_seq, b_seq);
+        assert_ne!(a_seq, c_seq);
+
+        for seed in 0..256 {
+            assert_eq!(random8_from(seed), random8_from(seed));
+        }
+    }
+}
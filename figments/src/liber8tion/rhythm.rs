@@ -1,13 +1,15 @@
 use num::traits::WrappingAdd;
 
-use crate::liber8tion::{interpolate::Fract8, trig::Trig8};
+use crate::liber8tion::{interpolate::Fract8, trig::{sin16, Trig8}};
 
 
-fn beat88(now: u32, bpm: u16, timebase: u32) -> u16 {
+/// The phase of a beat in Q8.8 fixed point beats per minute, which lets the tempo have a fractional part. The phase rises from 0 to 65535 once per beat.
+pub fn beat88(now: u32, bpm: u16, timebase: u32) -> u16 {
     (((now - timebase).wrapping_mul(bpm as u32).wrapping_mul(280)).wrapping_shr(16)) as u16
 }
 
-fn beat16(now: u32, bpm: u16, timebase: u32) -> u16 {
+/// The phase of a beat, which rises from 0 to 65535 once per beat. Tempos of 256 or more are treated as Q8.8 fixed point, like [beat88].
+pub fn beat16(now: u32, bpm: u16, timebase: u32) -> u16 {
     let adj_bpm = if bpm < 256 {
         bpm.wrapping_shl(8)
     } else {
@@ -16,7 +18,8 @@ fn beat16(now: u32, bpm: u16, timebase: u32) -> u16 {
     beat88(now, adj_bpm, timebase)
 }
 
-fn beat8(now: u32, bpm: u16, timebase: u32) -> Fract8 {
+/// The phase of a beat, which rises from 0 to 255 once per beat
+pub fn beat8(now: u32, bpm: u16, timebase: u32) -> Fract8 {
     Fract8::from_raw(beat16(now, bpm, timebase).wrapping_shr(8) as u8)
 }
 
@@ -27,4 +30,71 @@ pub fn beatsin8(now: u32, bpm: u16, lowest: Fract8, highest: Fract8, timebase: u
     let scaledbeat = beatsin * width;
     
     lowest + scaledbeat
-} 
\ No newline at end of file
+}
+
+/// The same as [beatsin8], except that it follows a cosine wave so it starts at `highest` instead of halfway between `lowest` and `highest`
+pub fn beatsin8_cos(now: u32, bpm: u16, lowest: Fract8, highest: Fract8, timebase: u32, phase: Fract8) -> Fract8 {
+    let beat = beat8(now, bpm, timebase);
+    let beatsin = beat.wrapping_add(&phase).cos8();
+    let width = highest - lowest;
+    let scaledbeat = beatsin * width;
+
+    lowest + scaledbeat
+}
+
+/// A sine wave that oscillates between `lowest` and `highest` at the given tempo, with the full 16 bits of precision for smooth and slow sweeps across long strips
+///
+/// ```
+/// use figments::liber8tion::rhythm::beatsin16;
+///
+/// // A quarter of the way through a beat at 60 BPM is the peak of the wave
+/// assert_eq!(beatsin16(250, 60, 1000, 2000, 0, 0), 1999);
+/// ```
+pub fn beatsin16(now: u32, bpm: u16, lowest: u16, highest: u16, timebase: u32, phase: u16) -> u16 {
+    let beat = beat16(now, bpm, timebase);
+    let beatsin = (sin16(beat.wrapping_add(phase)) as i32 + 32768) as u32;
+    let width = highest.saturating_sub(lowest) as u32;
+    let scaledbeat = (beatsin * (width + 1)) >> 16;
+
+    lowest + scaledbeat as u16
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_beatsin_stays_in_range() {
+        let (mut min, mut max) = (u16::MAX, u16::MIN);
+        for now in (0..5000).step_by(3) {
+            let value = beatsin16(now, 47, 1000, 3000, 0, 0);
+            min = min.min(value);
+            max = max.max(value);
+            let value8 = beatsin8(now, 47, Fract8::from_raw(20), Fract8::from_raw(200), 0, Fract8::MIN).to_raw();
+            let value8_cos = beatsin8_cos(now, 47, Fract8::from_raw(20), Fract8::from_raw(200), 0, Fract8::MIN).to_raw();
+            assert!((20..=200).contains(&value8) && (20..=200).contains(&value8_cos), "beatsin8 left its range with {value8} and {value8_cos} at {now}");
+        }
+        assert!(min >= 1000 && max <= 3000, "beatsin16 left its range, from {min} to {max}");
+        assert!(min < 1010 && max > 2990, "beatsin16 should cover its whole range, but only went from {min} to {max}");
+        assert!(beatsin16(0, 60, 0, u16::MAX, 0, 16384) > 65000, "The full range should reach its peak without overflowing");
+    }
+
+    #[test]
+    fn test_beatsin_phase_is_half_a_cycle() {
+        for now in (0..2000).step_by(37) {
+            let low = Fract8::from_raw(0);
+            let high = Fract8::from_raw(254);
+            // Half a cycle later, the wave is mirrored around its midpoint
+            let value = beatsin8(now, 60, low, high, 0, Fract8::MIN).to_raw() as i32;
+            let shifted = beatsin8(now, 60, low, high, 0, Fract8::from_raw(128)).to_raw() as i32;
+            assert!((value + shifted - 254).abs() <= 4, "A phase of 128 should be half a cycle from 0 at {now}, but was {value} and {shifted}");
+
+            let value16 = beatsin16(now, 60, 0, 60000, 0, 0) as i32;
+            let shifted16 = beatsin16(now, 60, 0, 60000, 0, 32768) as i32;
+            assert!((value16 + shifted16 - 60000).abs() <= 2, "A phase of 32768 should be half a cycle from 0 at {now}, but was {value16} and {shifted16}");
+        }
+
+        // A cosine is a quarter of a cycle ahead of a sine
+        assert_eq!(beatsin8_cos(100, 60, Fract8::MIN, Fract8::MAX, 0, Fract8::MIN), beatsin8(100, 60, Fract8::MIN, Fract8::MAX, 0, Fract8::from_raw(64)));
+    }
+}
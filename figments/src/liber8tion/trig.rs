@@ -45,4 +45,128 @@ impl Trig8 for f32 {
     fn cos8(self) -> Fract8 {
         (self as u8).cos8()
     }
-}
\ No newline at end of file
+}
+
+/// Scales one 8 bit value by another, exactly like FastLED's `scale8`
+const fn scale8(i: u8, scale: u8) -> u8 {
+    ((i as u16 * (1 + scale as u16)) >> 8) as u8
+}
+
+/// A triangle wave, which rises from 0 to a peak of 254 at 127 and 128, then falls back to 0 at 255
+///
+/// This is a much cheaper replacement for [Trig8::sin8] when the shape of the wave doesn't matter much.
+///
+/// ```
+/// use figments::liber8tion::trig::triwave8;
+///
+/// assert_eq!(triwave8(0), 0);
+/// assert_eq!(triwave8(64), 128);
+/// assert_eq!(triwave8(128), 254);
+/// assert_eq!(triwave8(255), 0);
+/// ```
+pub const fn triwave8(i: u8) -> u8 {
+    let i = if i & 0x80 != 0 { 255 - i } else { i };
+    i << 1
+}
+
+/// A triangle wave with quadratic easing applied, which is shaped much like [Trig8::sin8] but spends a little more time near the peaks
+pub const fn quadwave8(i: u8) -> u8 {
+    let i = triwave8(i);
+    let j = if i & 0x80 != 0 { 255 - i } else { i };
+    let jj = scale8(j, j) << 1;
+    if i & 0x80 != 0 { 255 - jj } else { jj }
+}
+
+/// A triangle wave with cubic easing applied, which spends even more time near the peaks than [quadwave8]
+pub const fn cubicwave8(i: u8) -> u8 {
+    let i = triwave8(i);
+    let ii = scale8(i, i) as u16;
+    let iii = scale8(ii as u8, i) as u16;
+    let r1 = 3 * ii - 2 * iii;
+    if r1 & 0x100 != 0 { 255 } else { r1 as u8 }
+}
+
+/// A 16 bit sine wave, which is the same approximation as FastLED's `sin16`
+///
+/// A full cycle takes all 65536 input values, and the output ranges from -32767 to 32767.
+///
+/// ```
+/// use figments::liber8tion::trig::sin16;
+///
+/// assert_eq!(sin16(0), 0);
+/// assert_eq!(sin16(16384), 32645);
+/// assert_eq!(sin16(49152), -32645);
+/// ```
+pub const fn sin16(theta: u16) -> i16 {
+    const BASE: [u16; 8] = [0, 6393, 12539, 18204, 23170, 27245, 30273, 32137];
+    const SLOPE: [u8; 8] = [49, 48, 44, 38, 31, 23, 14, 4];
+
+    // Each quarter of the wave is approximated by 8 straight line segments
+    let mut offset = (theta & 0x3FFF) >> 3;
+    if theta & 0x4000 != 0 {
+        offset = 2047 - offset;
+    }
+    let section = (offset / 256) as usize;
+    let y = (SLOPE[section] as u16 * ((offset as u8) / 2) as u16 + BASE[section]) as i16;
+    if theta & 0x8000 != 0 { -y } else { y }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_waves_match_fastled() {
+        // Reference values from FastLED's triwave8, quadwave8 and cubicwave8
+        let expected: [(u8, u8, u8, u8); 13] = [
+            (0, 0, 0, 0),
+            (1, 2, 0, 0),
+            (32, 64, 32, 40),
+            (64, 128, 129, 128),
+            (96, 192, 225, 216),
+            (127, 254, 255, 255),
+            (128, 254, 255, 255),
+            (129, 252, 255, 255),
+            (160, 190, 223, 213),
+            (192, 126, 124, 126),
+            (224, 62, 30, 39),
+            (254, 2, 0, 0),
+            (255, 0, 0, 0)
+        ];
+        for (input, tri, quad, cubic) in expected {
+            assert_eq!(triwave8(input), tri, "triwave8({input})");
+            assert_eq!(quadwave8(input), quad, "quadwave8({input})");
+            assert_eq!(cubicwave8(input), cubic, "cubicwave8({input})");
+        }
+    }
+
+    #[test]
+    fn test_waves_are_symmetric() {
+        for i in 0..=127u8 {
+            assert_eq!(triwave8(i), triwave8(255 - i), "triwave8 should mirror around the peak at {i}");
+            assert_eq!(quadwave8(i), quadwave8(255 - i), "quadwave8 should mirror around the peak at {i}");
+            assert_eq!(cubicwave8(i), cubicwave8(255 - i), "cubicwave8 should mirror around the peak at {i}");
+        }
+    }
+
+    #[test]
+    fn test_sin16_matches_fastled() {
+        // Values from FastLED's sin16_C, which is linear within each eighth of a quarter wave
+        let expected: [(u16, i16); 8] = [
+            (0, 0),
+            (1000, 3038),
+            (8192, 23170),
+            (16383, 32645),
+            (16384, 32645),
+            (32768, 0),
+            (40960, -23170),
+            (65535, 0)
+        ];
+        for (theta, sin) in expected {
+            assert_eq!(sin16(theta), sin, "sin16({theta})");
+        }
+        for theta in (0..32768u16).step_by(7) {
+            assert_eq!(sin16(theta), -sin16(theta + 32768), "The second half of the wave should mirror the first at {theta}");
+        }
+    }
+}
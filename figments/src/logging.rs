@@ -0,0 +1,13 @@
+//! Optional logging, which can be sent to either the `log` facade or `defmt`, and compiles away to nothing by default
+
+/// Emits a trace level message through whichever logging backend is enabled
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature="log-04")]
+        log::trace!($($arg)*);
+        #[cfg(feature="defmt")]
+        defmt::trace!($($arg)*);
+    };
+}
+
+pub(crate) use trace;
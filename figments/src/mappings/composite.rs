@@ -0,0 +1,145 @@
+use core::array;
+use core::ops::IndexMut;
+
+use crate::geometry::*;
+use crate::render::Sample;
+use crate::mappings::stride::{StrideMapping, StrideView};
+
+/// Several [StrideMapping]s that are placed side by side within a single [Virtual] space, such as a display built from multiple panels that are each wired independently
+///
+/// Each panel is stretched to fill its own rectangle of the virtual space, and has its own pixel buffer in a [CompositeSampler].
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::mappings::{CompositeMapping, CompositeSampler, StrideMapping};
+///
+/// let left: StrideMapping = StrideMapping::from_json(&[(0, 0, 8, false), (1, 0, 8, true)]);
+/// let right: StrideMapping = StrideMapping::from_json(&[(0, 0, 8, false), (1, 0, 8, true)]);
+/// let map = CompositeMapping::new([
+///     (&left, Rectangle::new_from_coordinates(0, 0, 127, 255)),
+///     (&right, Rectangle::new_from_coordinates(128, 0, 255, 255))
+/// ]);
+///
+/// let mut left_pixbuf = [0u8; 16];
+/// let mut right_pixbuf = [0u8; 16];
+/// let mut sampler = CompositeSampler::new([&mut left_pixbuf, &mut right_pixbuf], &map);
+/// for (_, pixel) in sampler.sample(&Rectangle::new_from_coordinates(200, 0, 255, 255)) {
+///     *pixel = 1;
+/// }
+/// assert_eq!(left_pixbuf, [0; 16]);
+/// assert_eq!(right_pixbuf, [1; 16]);
+/// ```
+#[derive(Debug)]
+pub struct CompositeMapping<'a, const N: usize> {
+    panels: [(&'a StrideMapping, Rectangle<Virtual>); N]
+}
+
+impl<'a, const N: usize> CompositeMapping<'a, N> {
+    /// Creates a new composite from each panel's mapping, along with the rectangle of the virtual space it covers
+    pub const fn new(panels: [(&'a StrideMapping, Rectangle<Virtual>); N]) -> Self {
+        Self {
+            panels
+        }
+    }
+
+    /// Returns the part of a rectangle that falls within a panel, in the panel's own virtual coordinates
+    fn local_rect(rect: &Rectangle<Virtual>, placement: &Rectangle<Virtual>) -> Option<Rectangle<Virtual>> {
+        rect.intersection(placement).map(|clipped| {
+            Rectangle::new(clipped.top_left.relative_to(placement), clipped.bottom_right.relative_to(placement))
+        })
+    }
+
+    /// Converts a coordinate in a panel's own virtual coordinates back into the shared virtual space
+    fn global_coords(coords: VirtualCoordinates, placement: &Rectangle<Virtual>) -> VirtualCoordinates {
+        VirtualCoordinates::new(
+            placement.left() + (coords.x as u16 * placement.width() as u16 / 255) as u8,
+            placement.top() + (coords.y as u16 * placement.height() as u16 / 255) as u8
+        )
+    }
+}
+
+/// A [Sample] implementation that routes each part of a [Virtual] rectangle to the pixel buffer of the panel underneath it in a [CompositeMapping]
+#[derive(Debug)]
+pub struct CompositeSampler<'a, PB, const N: usize> {
+    pixbufs: [&'a mut PB; N],
+    map: &'a CompositeMapping<'a, N>
+}
+
+impl<'a, PB, const N: usize> CompositeSampler<'a, PB, N> {
+    /// Creates a new sampler over one pixel buffer for each panel, in the same order as the panels of the mapping
+    pub fn new(pixbufs: [&'a mut PB; N], map: &'a CompositeMapping<'a, N>) -> Self {
+        Self {
+            pixbufs,
+            map
+        }
+    }
+}
+
+impl<'a, P: 'a, PB: IndexMut<usize, Output = P> + 'a, const N: usize> Sample<'a, Virtual> for CompositeSampler<'a, PB, N> {
+    type Output = P;
+
+    fn sample(&mut self, rect: &Rectangle<Virtual>) -> impl Iterator<Item = (Coordinates<Virtual>, &'a mut Self::Output)> {
+        let rect = *rect;
+        let panels = self.map.panels;
+        // Trick the borrow checker, until we can rewrite the sample trait to use a lifetime generic parameter
+        let pixbufs: [*mut PB; N] = array::from_fn(|idx| &mut *self.pixbufs[idx] as *mut PB);
+        panels.into_iter().zip(pixbufs).filter_map(move |((map, placement), pixbuf)| {
+            CompositeMapping::<N>::local_rect(&rect, &placement).map(|local| {
+                let bufref = unsafe {
+                    pixbuf.as_mut().unwrap()
+                };
+                StrideView::new(bufref, map, &local).map(move |(coords, pixel)| {
+                    (CompositeMapping::<N>::global_coords(coords, &placement), pixel)
+                })
+            })
+        }).flatten()
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.map.panels.iter().map(|(map, _)| map.pixel_count).sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn panel() -> StrideMapping {
+        let strides: [(usize, usize, usize, bool); 16] = array::from_fn(|x| (x, 0, 16, x % 2 == 1));
+        StrideMapping::from_json(&strides)
+    }
+
+    #[test]
+    fn test_halves_route_to_each_panel() {
+        let (panel_a, panel_b) = (panel(), panel());
+        let map = CompositeMapping::new([
+            (&panel_a, Rectangle::new_from_coordinates(0, 0, 127, 255)),
+            (&panel_b, Rectangle::new_from_coordinates(128, 0, 255, 255))
+        ]);
+        let mut pixbuf_a = [0u8; 256];
+        let mut pixbuf_b = [0u8; 256];
+
+        let mut sampler = CompositeSampler::new([&mut pixbuf_a, &mut pixbuf_b], &map);
+        for (coords, pixel) in sampler.sample(&Rectangle::new_from_coordinates(0, 0, 127, 255)) {
+            assert!(coords.x <= 127, "{coords:?} is outside of the left half");
+            *pixel += 1;
+        }
+        assert!(pixbuf_a.iter().all(|pixel| *pixel == 1), "The left half should hit every pixel of panel A once: {pixbuf_a:?}");
+        assert!(pixbuf_b.iter().all(|pixel| *pixel == 0), "The left half should not touch panel B: {pixbuf_b:?}");
+
+        let mut sampler = CompositeSampler::new([&mut pixbuf_a, &mut pixbuf_b], &map);
+        for (coords, pixel) in sampler.sample(&Rectangle::new_from_coordinates(128, 0, 255, 255)) {
+            assert!(coords.x >= 128, "{coords:?} is outside of the right half");
+            *pixel += 2;
+        }
+        assert!(pixbuf_a.iter().all(|pixel| *pixel == 1), "The right half should not touch panel A: {pixbuf_a:?}");
+        assert!(pixbuf_b.iter().all(|pixel| *pixel == 2), "The right half should hit every pixel of panel B once: {pixbuf_b:?}");
+
+        // A rectangle that straddles the seam reaches into both panels
+        let mut sampler = CompositeSampler::new([&mut pixbuf_a, &mut pixbuf_b], &map);
+        let seam = Rectangle::new_from_coordinates(120, 0, 135, 0);
+        assert!(sampler.sample(&seam).any(|(coords, _)| coords.x < 128), "The seam should reach into panel A");
+        assert!(sampler.sample(&seam).any(|(coords, _)| coords.x >= 128), "The seam should reach into panel B");
+        assert_eq!(sampler.sample(&Rectangle::everything()).count(), 512);
+    }
+}
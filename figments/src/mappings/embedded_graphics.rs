@@ -3,12 +3,7 @@ use embedded_graphics::{pixelcolor::BinaryColor, prelude::{Dimensions, DrawTarge
 
 use crate::{liber8tion::interpolate::Fract8, prelude::*};
 
-#[derive(Default, Debug, Clone, Copy)]
-pub struct Matrix2DSpace {}
-
-impl CoordinateSpace for Matrix2DSpace {
-    type Data = i32;
-}
+pub use crate::mappings::matrix::Matrix2DSpace;
 
 pub struct EmbeddedGraphicsSampler<'a, T: ?Sized>(pub &'a mut T, pub embedded_graphics::primitives::Rectangle);
 
@@ -27,7 +22,7 @@ impl<'a, T> DrawTarget for EmbeddedGraphicsSampler<'a, T> where T: Sample<'a, Ma
     where
         I: IntoIterator<Item = Pixel<Self::Color>> {
         for pix in pixels {
-            let rect = Rectangle::new(Coordinates::new(pix.0.x, pix.0.y), Coordinates::new(pix.0.x, pix.0.y));
+            let rect = Rectangle::single(pix.0.into());
             for (coords, fpix) in self.0.sample(&rect) {
                 fpix.add(pix.1, Fract8::MAX);
             }
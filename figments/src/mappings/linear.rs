@@ -1,8 +1,12 @@
 use core::cmp::min;
 use core::fmt::Debug;
+use core::iter::{Enumerate, Map};
+use core::slice::IterMut;
 
 use crate::geometry::*;
-use crate::render::Sample;
+use crate::render::{Sample, SampleIndexed};
+#[cfg(feature="std")]
+use crate::{liber8tion::interpolate::Fract8, pixels::AdditivePixelSink, render::Shader};
 
 /// Linear coordinate space where Y is meaningless and X points to a unique pixel
 #[derive(Debug, Clone, Copy, Default)]
@@ -11,6 +15,11 @@ impl CoordinateSpace for LinearSpace {
     type Data = usize;
 }
 
+/// Returns the number of pixels covered by a rectangle, which is zero when it is upside down
+const fn span_len(rect: &Rectangle<LinearSpace>) -> usize {
+    rect.right().saturating_add(1).saturating_sub(rect.left())
+}
+
 impl<'a, Pixel: 'a> Sample<'a, LinearSpace> for [Pixel] {
     type Output = Pixel;
 
@@ -18,8 +27,8 @@ impl<'a, Pixel: 'a> Sample<'a, LinearSpace> for [Pixel] {
         let size = self.len();
         // Clip the pixbuf at the left side of the rectangle
         let (_, rest) = self.split_at_mut(min(rect.left(), size));
-        // Clip again on the other end of the rectangle
-        let (subset, _) = rest.split_at_mut(min(rect.width(), rest.len()));
+        // Clip again on the other end of the rectangle, which is inclusive
+        let (subset, _) = rest.split_at_mut(min(span_len(rect), rest.len()));
         // Trick the borrow checker, until we can rewrite the sample trait to use a lifetime generic parameter
         let bufref = unsafe {
             (subset as *mut [Pixel]).as_mut().unwrap()
@@ -29,28 +38,165 @@ impl<'a, Pixel: 'a> Sample<'a, LinearSpace> for [Pixel] {
             (Coordinates::new(idx + rect.left(), 0), pix)
         })
     }
+
+    fn bounds(&self) -> Rectangle<LinearSpace> {
+        Rectangle::new_from_coordinates(0, 0, self.len().saturating_sub(1), 0)
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.len()
+    }
 }
 
 impl<'a, Pixel: 'a, const N: usize> Sample<'a, LinearSpace> for [Pixel; N] {
     type Output = Pixel;
 
     fn sample(&mut self, rect: &Rectangle<LinearSpace>) -> impl Iterator<Item = (Coordinates<LinearSpace>, &'a mut Self::Output)> {
-        let size = self.len();
-        // Clip the pixbuf at the left side of the rectangle
-        let (_, rest) = self.split_at_mut(min(rect.left(), size));
-        // Clip again on the other end of the rectangle
-        let (subset, _) = rest.split_at_mut(min(rect.width(), rest.len()));
-        // Trick the borrow checker, until we can rewrite the sample trait to use a lifetime generic parameter
-        let bufref = unsafe {
-            (subset as *mut [Pixel]).as_mut().unwrap()
-        };
-        // Enumerate each pixel into an absolute (x, 0) coordinate along with the sampled pixel
-        bufref.iter_mut().enumerate().map(|(idx, pix)| {
-            (Coordinates::new(idx + rect.left(), 0), pix)
+        // Fixed size arrays are sampled exactly the same way as a runtime sized slice
+        <[Pixel] as Sample<'a, LinearSpace>>::sample(self.as_mut_slice(), rect)
+    }
+
+    fn bounds(&self) -> Rectangle<LinearSpace> {
+        <[Pixel] as Sample<'a, LinearSpace>>::bounds(self.as_slice())
+    }
+
+    fn pixel_count(&self) -> usize {
+        N
+    }
+}
+
+// The X coordinate of every pixel in a strip is already its index
+impl<'a, Pixel: 'a> SampleIndexed<'a, LinearSpace> for [Pixel] {
+    fn sample_indexed(&mut self, rect: &Rectangle<LinearSpace>) -> impl Iterator<Item = (Coordinates<LinearSpace>, usize, &'a mut Self::Output)> {
+        self.sample(rect).map(|(coords, pix)| (coords, coords.x, pix))
+    }
+}
+
+impl<'a, Pixel: 'a, const N: usize> SampleIndexed<'a, LinearSpace> for [Pixel; N] {
+    fn sample_indexed(&mut self, rect: &Rectangle<LinearSpace>) -> impl Iterator<Item = (Coordinates<LinearSpace>, usize, &'a mut Self::Output)> {
+        <[Pixel] as SampleIndexed<'a, LinearSpace>>::sample_indexed(self.as_mut_slice(), rect)
+    }
+}
+
+/// A [Sample] implementation over a 1d strip of pixels in [LinearSpace], where X is the index of the pixel
+///
+/// Slices and arrays can also be sampled directly, but this wrapper is useful when a sampler needs to be passed around separately from the pixel buffer.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::mappings::LinearSampler;
+/// use rgb::Rgb;
+///
+/// let mut pixbuf = [Rgb::new(0u8, 0, 0); 16];
+/// let mut sampler = LinearSampler::new(&mut pixbuf);
+/// for (coords, pixel) in sampler.sample(&Rectangle::everything()) {
+///     *pixel = Rgb::new(coords.x as u8, 0, 0);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct LinearSampler<'a, Pixel> {
+    pixbuf: &'a mut [Pixel]
+}
+
+impl<'a, Pixel> LinearSampler<'a, Pixel> {
+    /// Creates a new sampler over a pixel buffer
+    pub fn new(pixbuf: &'a mut [Pixel]) -> Self {
+        Self {
+            pixbuf
+        }
+    }
+
+    /// Borrows the sampler to iterate over every pixel within a [Rectangle]
+    ///
+    /// Unlike [Sample::sample], the pixels are only borrowed for as long as the sampler is.
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    /// use figments::mappings::LinearSampler;
+    /// use rgb::Rgb;
+    ///
+    /// let mut pixbuf = [Rgb::new(0u8, 0, 0); 16];
+    /// let mut sampler = LinearSampler::new(&mut pixbuf);
+    /// for (coords, pixel) in sampler.region(&Rectangle::new_from_coordinates(4, 0, 8, 0)) {
+    ///     pixel.r = 255;
+    /// }
+    ///
+    /// // The sampler itself can also be iterated over to visit every pixel
+    /// for (coords, pixel) in &mut sampler {
+    ///     pixel.g = coords.x as u8;
+    /// }
+    /// assert_eq!(pixbuf[4], Rgb::new(255, 4, 0));
+    /// assert_eq!(pixbuf[8], Rgb::new(255, 8, 0));
+    /// assert_eq!(pixbuf[9], Rgb::new(0, 9, 0));
+    /// ```
+    pub fn region(&mut self, rect: &Rectangle<LinearSpace>) -> impl Iterator<Item = (Coordinates<LinearSpace>, &mut Pixel)> + use<'_, 'a, Pixel> {
+        let size = self.pixbuf.len();
+        let start = min(rect.left(), size);
+        let end = min(start.saturating_add(span_len(rect)), size);
+        let left = rect.left();
+        self.pixbuf[start..end].iter_mut().enumerate().map(move |(idx, pix)| {
+            (Coordinates::new(idx + left, 0), pix)
+        })
+    }
+}
+
+type LinearIter<'s, Pixel> = Map<Enumerate<IterMut<'s, Pixel>>, fn((usize, &'s mut Pixel)) -> (Coordinates<LinearSpace>, &'s mut Pixel)>;
+
+impl<'s, 'a, Pixel> IntoIterator for &'s mut LinearSampler<'a, Pixel> {
+    type Item = (Coordinates<LinearSpace>, &'s mut Pixel);
+    type IntoIter = LinearIter<'s, Pixel>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pixbuf.iter_mut().enumerate().map(|(idx, pix)| {
+            (Coordinates::new(idx, 0), pix)
         })
     }
 }
 
+impl<'a, Pixel: 'a> Sample<'a, LinearSpace> for LinearSampler<'a, Pixel> {
+    type Output = Pixel;
+
+    fn sample(&mut self, rect: &Rectangle<LinearSpace>) -> impl Iterator<Item = (Coordinates<LinearSpace>, &'a mut Self::Output)> {
+        self.pixbuf.sample(rect)
+    }
+
+    fn bounds(&self) -> Rectangle<LinearSpace> {
+        self.pixbuf.bounds()
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.pixbuf.len()
+    }
+}
+
+impl<'a, Pixel: 'a> SampleIndexed<'a, LinearSpace> for LinearSampler<'a, Pixel> {
+    fn sample_indexed(&mut self, rect: &Rectangle<LinearSpace>) -> impl Iterator<Item = (Coordinates<LinearSpace>, usize, &'a mut Self::Output)> {
+        self.pixbuf.sample_indexed(rect)
+    }
+}
+
+/// Fills a strip of pixels with a shader, splitting the strip across every available CPU core
+///
+/// The output is identical to [Painter::fill](crate::render::Painter::fill), but is much faster on a host with many cores, such as when previewing a large display.
+#[cfg(feature="std")]
+pub fn render_parallel<U, Input, Pixel>(pixbuf: &mut [Pixel], shader: &(impl Shader<U, LinearSpace, Input> + Sync), uniforms: &U)
+    where
+        U: Sync,
+        Pixel: AdditivePixelSink<Input> + Send {
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let chunk_size = pixbuf.len().div_ceil(threads).max(1);
+    std::thread::scope(|scope| {
+        for (chunk_idx, chunk) in pixbuf.chunks_mut(chunk_size).enumerate() {
+            scope.spawn(move || {
+                let offset = chunk_idx * chunk_size;
+                for (idx, pixel) in chunk.iter_mut().enumerate() {
+                    pixel.add(shader.draw(&Coordinates::new(offset + idx, 0), uniforms), Fract8::MAX);
+                }
+            });
+        }
+    });
+}
+
 #[cfg(test)]
 mod test {
     use core::array;
@@ -73,7 +219,7 @@ mod test {
         let mut pixbuf: [_; PIXEL_COUNT] = array::from_fn(|n| { Rgb::new(n as u8, 0, 0) });
 
         let buf_start = rect.left();
-        let buf_end = min(pixbuf.len(), buf_start + rect.width());
+        let buf_end = min(pixbuf.len(), rect.right().saturating_add(1));
 
         // Then, set the blue value to the selection coordinates
         with_sample::<PIXEL_COUNT>(&mut pixbuf, rect, |coords: Coordinates<LinearSpace>, pix| {
@@ -102,13 +248,23 @@ mod test {
         test_gradient::<1024>(&everything, 1024);
     }
 
+    #[test]
+    fn test_single_samples() {
+        for x in 0..16 {
+            test_gradient::<16>(&Rectangle::single(Coordinates::new(x, 0)), 1);
+        }
+        test_gradient::<16>(&Rectangle::single(Coordinates::new(16, 0)), 0);
+        // Upside down rectangles select nothing
+        test_gradient::<16>(&Rectangle::new_from_coordinates(8, 0, 4, 0), 0);
+    }
+
     #[test]
     fn test_top_left_samples() {
         for width in 0..128 {
             let rect = Rectangle::new(Coordinates::new(0, 0), Coordinates::new(width, width));
             test_gradient::<0>(&rect, 0);
-            test_gradient::<255>(&rect, width);
-            test_gradient::<1024>(&rect, width);
+            test_gradient::<255>(&rect, width + 1);
+            test_gradient::<1024>(&rect, width + 1);
         }
     }
 
@@ -117,8 +273,76 @@ mod test {
         for width in 0..128 {
             let rect = Rectangle::new(Coordinates::new(width, width), Coordinates::new(width * 2, width * 2));
             test_gradient::<0>(&rect, 0);
-            test_gradient::<255>(&rect, width);
-            test_gradient::<1024>(&rect, width);
+            test_gradient::<255>(&rect, width + 1);
+            test_gradient::<1024>(&rect, width + 1);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_runtime_slice_samples() {
+        let mut storage: [Rgb<u8>; 64] = array::from_fn(|n| { Rgb::new(n as u8, 0, 0) });
+        // Use a length only known at runtime, to ensure we are sampling an unsized slice and not the array
+        let len = core::hint::black_box(37);
+        let pixbuf: &mut [Rgb<u8>] = &mut storage[..len];
+
+        let mut num_sampled = 0;
+        for (coords, pix) in pixbuf.sample(&Rectangle::everything()) {
+            assert_eq!(pix, &Rgb::new(coords.x as u8, 0, 0));
+            pix.b = 255;
+            num_sampled += 1;
+        }
+        assert_eq!(num_sampled, len);
+
+        let rect = Rectangle::new(Coordinates::new(10, 0), Coordinates::new(20, 0));
+        let mut num_sampled = 0;
+        for (coords, pix) in pixbuf.sample(&rect) {
+            assert!(coords.x >= 10 && coords.x <= 20, "{coords:?} is outside of {rect:?}");
+            pix.g = 255;
+            num_sampled += 1;
+        }
+        assert_eq!(num_sampled, 11);
+
+        // Nothing past the end of the slice should have been touched
+        assert!(storage[len..].iter().all(|pix| pix.b == 0));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn test_parallel_render_matches_serial() {
+        use crate::mappings::linear::render_parallel;
+
+        fn check<const N: usize>() {
+            let shader = |coords: &Coordinates<LinearSpace>, frame: &usize| {
+                let x = coords.x.wrapping_mul(2654435761).wrapping_add(*frame);
+                Rgb::new(x as u8, (x >> 8) as u8, (x >> 16) as u8)
+            };
+
+            let mut serial = [Rgb::new(0u8, 0, 0); N];
+            let mut parallel = serial;
+            serial.fill(&shader, &42);
+            render_parallel(&mut parallel, &shader, &42);
+            assert_eq!(serial, parallel, "The parallel render of {N} pixels differs from the serial render");
+        }
+
+        check::<0>();
+        check::<1>();
+        check::<7>();
+        check::<1000>();
+        check::<4096>();
+    }
+
+    #[test]
+    fn test_bounds_match_pixbuf_size() {
+        let mut pixbuf = [Rgb::new(0u8, 0, 0); 24];
+        assert_eq!(Sample::bounds(&pixbuf).right(), 23);
+        assert_eq!(Sample::pixel_count(&pixbuf), 24);
+
+        let slice: &mut [Rgb<u8>] = &mut pixbuf[..10];
+        assert_eq!((slice.bounds().left(), slice.bounds().right()), (0, 9));
+        assert_eq!(slice.pixel_count(), 10);
+
+        // Sampling the reported bounds should visit every pixel
+        let bounds = slice.bounds();
+        assert_eq!(slice.sample(&bounds).count(), 10);
+    }
+}
@@ -0,0 +1,546 @@
+use core::cmp::max;
+use core::ops::{IndexMut, Range};
+
+use crate::geometry::*;
+use crate::render::{DisplayInfo, Sample};
+
+/// A 2d [CoordinateSpace] for displays that are a true grid of pixels, where X is the column and Y is the row
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Matrix2DSpace {}
+
+impl CoordinateSpace for Matrix2DSpace {
+    type Data = i32;
+}
+
+/// Coordinates within a [Matrix2DSpace]
+pub type MatrixCoords = Coordinates<Matrix2DSpace>;
+
+/// A 2d [CoordinateSpace] for a [MatrixMapping], where X is the column and Y is the row counted from the top left of the display, regardless of its [MatrixOrigin]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatrixSpace {}
+
+impl CoordinateSpace for MatrixSpace {
+    type Data = usize;
+}
+
+/// Clips an inclusive start and end coordinate to the indexes that exist within a row or column of the given length
+fn clip_span(start: i32, end: i32, len: usize) -> Range<usize> {
+    let start = (start as i64).clamp(0, len as i64) as usize;
+    let end = (end as i64 + 1).clamp(0, len as i64) as usize;
+    start..max(start, end)
+}
+
+/// Like [clip_span], for coordinates that can't be negative
+fn clip_unsigned_span(start: usize, end: usize, len: usize) -> Range<usize> {
+    let start = start.min(len);
+    let end = end.saturating_add(1).min(len);
+    start..max(start, end)
+}
+
+/// Converts an inclusive start and end virtual coordinate into the indexes of a row or column of the given length
+fn virtual_span(start: u8, end: u8, len: usize) -> Range<usize> {
+    if len == 0 {
+        return 0..0;
+    }
+    let (start, end) = if len <= 256 {
+        // Each column and row covers an equal share of the 256 virtual coordinates
+        (start as usize * len / 256, end as usize * len / 256 + 1)
+    } else {
+        // Each virtual coordinate covers an equal share of the columns and rows
+        ((start as usize * len).div_ceil(256), ((end as usize + 1) * len).div_ceil(256).min(len))
+    };
+    start..max(start, end)
+}
+
+/// Returns the virtual coordinate of a given column or row, so the conversion round trips
+const fn to_virtual(idx: usize, len: usize) -> u8 {
+    if len <= 256 {
+        // The first virtual coordinate that maps to the column or row
+        ((idx * 256).div_ceil(len)) as u8
+    } else {
+        (idx * 256 / len) as u8
+    }
+}
+
+/// Iterates over the given columns and rows of a matrix
+fn sample_cells<Pixel, const W: usize, const H: usize>(pixbuf: &mut [[Pixel; W]; H], cols: Range<usize>, rows: Range<usize>) -> impl Iterator<Item = (MatrixCoords, &mut Pixel)> {
+    // The outer array is each row, and the inner array is each column within that row
+    pixbuf[rows.clone()].iter_mut().zip(rows).flat_map(move |(row, y)| {
+        row[cols.clone()].iter_mut().zip(cols.clone()).map(move |(pix, x)| {
+            (Coordinates::new(x as i32, y as i32), pix)
+        })
+    })
+}
+
+impl<'a, Pixel: 'a, const W: usize, const H: usize> Sample<'a, Matrix2DSpace> for [[Pixel; W]; H] {
+    type Output = Pixel;
+
+    fn sample(&mut self, rect: &Rectangle<Matrix2DSpace>) -> impl Iterator<Item = (Coordinates<Matrix2DSpace>, &'a mut Self::Output)> {
+        // Trick the borrow checker, until we can rewrite the sample trait to use a lifetime generic parameter
+        let bufref = unsafe {
+            (self as *mut [[Pixel; W]; H]).as_mut().unwrap()
+        };
+        sample_cells(bufref, clip_span(rect.left(), rect.right(), W), clip_span(rect.top(), rect.bottom(), H))
+    }
+
+    fn bounds(&self) -> Rectangle<Matrix2DSpace> {
+        Rectangle::new_from_coordinates(0, 0, W.saturating_sub(1) as i32, H.saturating_sub(1) as i32)
+    }
+
+    fn pixel_count(&self) -> usize {
+        W * H
+    }
+}
+
+/// A [Sample] implementation that maps 2d [Virtual] coordinates onto a `[[Pixel; W]; H]` matrix of rows
+///
+/// The matrix can also be sampled directly in [Matrix2DSpace], where each coordinate is the exact column and row.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::mappings::MatrixSampler;
+/// use rgb::Rgb;
+///
+/// let mut pixbuf = [[Rgb::new(0u8, 0, 0); 16]; 8];
+/// let mut sampler = MatrixSampler::new(&mut pixbuf);
+/// let everything: Rectangle<Virtual> = Rectangle::everything();
+/// for (coords, pixel) in sampler.sample(&everything) {
+///     *pixel = Rgb::new(coords.x, coords.y, 0);
+/// }
+/// assert_eq!(pixbuf[7][15], Rgb::new(240, 224, 0));
+/// ```
+#[derive(Debug)]
+pub struct MatrixSampler<'a, Pixel, const W: usize, const H: usize> {
+    pixbuf: &'a mut [[Pixel; W]; H]
+}
+
+impl<'a, Pixel, const W: usize, const H: usize> MatrixSampler<'a, Pixel, W, H> {
+    /// Creates a new sampler over a matrix of pixels
+    pub fn new(pixbuf: &'a mut [[Pixel; W]; H]) -> Self {
+        Self {
+            pixbuf
+        }
+    }
+
+}
+
+impl<'a, Pixel: 'a, const W: usize, const H: usize> Sample<'a, Virtual> for MatrixSampler<'a, Pixel, W, H> {
+    type Output = Pixel;
+
+    fn sample(&mut self, rect: &Rectangle<Virtual>) -> impl Iterator<Item = (Coordinates<Virtual>, &'a mut Self::Output)> {
+        let cols = virtual_span(rect.left(), rect.right(), W);
+        let rows = virtual_span(rect.top(), rect.bottom(), H);
+        let bounds = *rect;
+        // Trick the borrow checker, until we can rewrite the sample trait to use a lifetime generic parameter
+        let bufref = unsafe {
+            (self.pixbuf as *mut [[Pixel; W]; H]).as_mut().unwrap()
+        };
+        sample_cells(bufref, cols, rows).map(move |(coords, pix)| {
+            let virtual_coords = VirtualCoordinates::new(
+                max(to_virtual(coords.x as usize, W), bounds.left()),
+                max(to_virtual(coords.y as usize, H), bounds.top())
+            );
+            (virtual_coords, pix)
+        })
+    }
+
+    fn pixel_count(&self) -> usize {
+        W * H
+    }
+}
+
+impl<'a, Pixel: 'a, const W: usize, const H: usize> Sample<'a, Matrix2DSpace> for MatrixSampler<'a, Pixel, W, H> {
+    type Output = Pixel;
+
+    fn sample(&mut self, rect: &Rectangle<Matrix2DSpace>) -> impl Iterator<Item = (Coordinates<Matrix2DSpace>, &'a mut Self::Output)> {
+        Sample::<Matrix2DSpace>::sample(self.pixbuf, rect)
+    }
+
+    fn bounds(&self) -> Rectangle<Matrix2DSpace> {
+        Sample::<Matrix2DSpace>::bounds(&*self.pixbuf)
+    }
+
+    fn pixel_count(&self) -> usize {
+        W * H
+    }
+}
+
+/// The corner of a matrix where the first physical pixel is wired
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixOrigin {
+    /// Rows run left to right, starting from the top
+    #[default]
+    TopLeft,
+    /// Rows run right to left, starting from the top
+    TopRight,
+    /// Rows run left to right, starting from the bottom
+    BottomLeft,
+    /// Rows run right to left, starting from the bottom
+    BottomRight
+}
+
+/// A mapping between 2d [Virtual] coordinates and a `width` x `height` grid of pixels that is stored row by row in a flat pixel buffer
+///
+/// Every row runs in the same direction. Displays that are wired in a zig-zag can use [StrideMapping::new_serpentine](crate::mappings::StrideMapping::new_serpentine) instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatrixMapping {
+    /// The number of columns in the grid
+    pub width: usize,
+
+    /// The number of rows in the grid
+    pub height: usize,
+
+    /// The corner of the display where the first pixel of the buffer is
+    pub origin: MatrixOrigin
+}
+
+impl MatrixMapping {
+    /// Creates a new mapping for a grid where the first pixel is at the top left
+    ///
+    /// A grid with no columns or no rows is an empty mapping, which never samples any pixels.
+    pub const fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            origin: MatrixOrigin::TopLeft
+        }
+    }
+
+    /// Moves the first pixel of the buffer to a different corner of the grid, to match the physical wiring of a display
+    pub const fn with_origin(self, origin: MatrixOrigin) -> Self {
+        Self {
+            origin,
+            ..self
+        }
+    }
+
+    /// Returns the physical size of the display, for shaders that use a [RenderContext](crate::render::RenderContext)
+    pub fn display_info(&self) -> DisplayInfo {
+        DisplayInfo::new(self.width, self.height)
+    }
+
+    /// Returns the index within the pixel buffer of the given column and row, where (0, 0) is always the top left of the display
+    ///
+    /// The column and row must be within the grid. Anything else returns an index that belongs to a different pixel or is past the end of the buffer, or overflows for the origins that count from the right or bottom.
+    ///
+    /// ```
+    /// use figments::mappings::matrix::{MatrixMapping, MatrixOrigin};
+    ///
+    /// let map = MatrixMapping::new(4, 3);
+    /// assert_eq!(map.index(1, 2), 9);
+    /// assert_eq!(map.with_origin(MatrixOrigin::BottomRight).index(1, 2), 2);
+    /// ```
+    pub const fn index(&self, x: usize, y: usize) -> usize {
+        let x = match self.origin {
+            MatrixOrigin::TopRight | MatrixOrigin::BottomRight => self.width - 1 - x,
+            _ => x
+        };
+        let y = match self.origin {
+            MatrixOrigin::BottomLeft | MatrixOrigin::BottomRight => self.height - 1 - y,
+            _ => y
+        };
+        y * self.width + x
+    }
+}
+
+/// A [Sample] implementation that uses a [MatrixMapping] to map 2d [Virtual] coordinates onto a flat pixel buffer
+///
+/// The grid can also be sampled directly in [MatrixSpace], where each coordinate is the exact column and row.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::mappings::matrix::{MatrixMapping, MatrixMappingSampler, MatrixOrigin, MatrixSpace};
+///
+/// // A 4x4 grid where the first pixel is at the bottom left
+/// let map = MatrixMapping::new(4, 4).with_origin(MatrixOrigin::BottomLeft);
+/// let mut pixbuf = [0u8; 16];
+/// let mut sampler = MatrixMappingSampler::new(&mut pixbuf, &map);
+/// let top_row: Rectangle<Virtual> = Rectangle::new_from_coordinates(0, 0, 255, 63);
+/// for (_, pixel) in sampler.sample(&top_row) {
+///     *pixel = 1;
+/// }
+/// // The top row of the display is the end of the buffer
+/// assert_eq!(pixbuf, [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1]);
+///
+/// // The same pixel, selected by its column and row
+/// let mut sampler = MatrixMappingSampler::new(&mut pixbuf, &map);
+/// let top_left: Rectangle<MatrixSpace> = Rectangle::single(Coordinates::new(0, 0));
+/// for (_, pixel) in sampler.sample(&top_left) {
+///     *pixel = 2;
+/// }
+/// assert_eq!(pixbuf[12], 2);
+/// ```
+#[derive(Debug)]
+pub struct MatrixMappingSampler<'a, PB> {
+    pixbuf: &'a mut PB,
+    map: &'a MatrixMapping
+}
+
+impl<'a, PB> MatrixMappingSampler<'a, PB> {
+    /// Creates a new sampler over a pixel buffer using the given [MatrixMapping]
+    pub fn new(pixbuf: &'a mut PB, map: &'a MatrixMapping) -> Self {
+        Self {
+            pixbuf,
+            map
+        }
+    }
+}
+
+impl<'a, P: 'a, PB: IndexMut<usize, Output = P>> MatrixMappingSampler<'a, PB> {
+    /// Iterates over the given columns and rows of the grid, which must be within it
+    fn sample_cells(&mut self, cols: Range<usize>, rows: Range<usize>) -> impl Iterator<Item = (usize, usize, &'a mut P)> + use<'a, P, PB> {
+        let map = *self.map;
+        let pixbuf = self.pixbuf as *mut PB;
+        rows.flat_map(move |y| cols.clone().map(move |x| (x, y))).map(move |(x, y)| {
+            // Trick the borrow checker, until we can rewrite the sample trait to use a lifetime generic parameter. Every index is only visited once.
+            let pix = unsafe {
+                let pixbuf = &mut *pixbuf;
+                &mut *(&mut pixbuf[map.index(x, y)] as *mut P)
+            };
+            (x, y, pix)
+        })
+    }
+}
+
+impl<'a, P: 'a, PB: IndexMut<usize, Output = P>> Sample<'a, Virtual> for MatrixMappingSampler<'a, PB> {
+    type Output = P;
+
+    fn sample(&mut self, rect: &Rectangle<Virtual>) -> impl Iterator<Item = (Coordinates<Virtual>, &'a mut Self::Output)> {
+        let map = *self.map;
+        let cols = virtual_span(rect.left(), rect.right(), map.width);
+        let rows = virtual_span(rect.top(), rect.bottom(), map.height);
+        let bounds = *rect;
+        self.sample_cells(cols, rows).map(move |(x, y, pix)| {
+            let virtual_coords = VirtualCoordinates::new(
+                max(to_virtual(x, map.width), bounds.left()),
+                max(to_virtual(y, map.height), bounds.top())
+            );
+            (virtual_coords, pix)
+        })
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.map.width * self.map.height
+    }
+}
+
+impl<'a, P: 'a, PB: IndexMut<usize, Output = P>> Sample<'a, MatrixSpace> for MatrixMappingSampler<'a, PB> {
+    type Output = P;
+
+    fn sample(&mut self, rect: &Rectangle<MatrixSpace>) -> impl Iterator<Item = (Coordinates<MatrixSpace>, &'a mut Self::Output)> {
+        let cols = clip_unsigned_span(rect.left(), rect.right(), self.map.width);
+        let rows = clip_unsigned_span(rect.top(), rect.bottom(), self.map.height);
+        self.sample_cells(cols, rows).map(|(x, y, pix)| (Coordinates::new(x, y), pix))
+    }
+
+    fn bounds(&self) -> Rectangle<MatrixSpace> {
+        Rectangle::new_from_coordinates(0, 0, self.map.width.saturating_sub(1), self.map.height.saturating_sub(1))
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.map.width * self.map.height
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rgb::Rgb;
+    use crate::mappings::matrix::*;
+
+    const W: usize = 12;
+    const H: usize = 7;
+
+    fn blank() -> [[Rgb<u8>; W]; H] {
+        [[Rgb::new(0, 0, 0); W]; H]
+    }
+
+    #[test]
+    fn test_sub_rectangle_samples() {
+        let mut pixbuf = blank();
+        let rect: Rectangle<Matrix2DSpace> = Rectangle::new_from_coordinates(2, 1, 5, 3);
+        let mut num_sampled = 0;
+        for (coords, pix) in pixbuf.sample(&rect) {
+            assert!(coords.x >= 2 && coords.x <= 5 && coords.y >= 1 && coords.y <= 3, "{coords:?} is outside of {rect:?}");
+            *pix = Rgb::new(coords.x as u8, coords.y as u8, 255);
+            num_sampled += 1;
+        }
+        assert_eq!(num_sampled, 4 * 3);
+
+        for (y, row) in pixbuf.iter().enumerate() {
+            for (x, pix) in row.iter().enumerate() {
+                if (2..=5).contains(&x) && (1..=3).contains(&y) {
+                    assert_eq!(pix, &Rgb::new(x as u8, y as u8, 255), "Cell ({x}, {y}) has the wrong coordinates");
+                } else {
+                    assert_eq!(pix, &Rgb::new(0, 0, 0), "Cell ({x}, {y}) was unexpectedly written while sampling {rect:?}");
+                }
+            }
+        }
+    }
+
+    fn count_sampled(pixbuf: &mut [[Rgb<u8>; W]; H], rect: Rectangle<Matrix2DSpace>) -> usize {
+        pixbuf.sample(&rect).count()
+    }
+
+    #[test]
+    fn test_out_of_bounds_samples_are_clipped() {
+        let mut pixbuf = blank();
+        assert_eq!(count_sampled(&mut pixbuf, Rectangle::everything()), W * H);
+        assert_eq!(count_sampled(&mut pixbuf, Rectangle::new_from_coordinates(-5, -5, 0, 0)), 1);
+        assert_eq!(count_sampled(&mut pixbuf, Rectangle::new_from_coordinates(W as i32 - 1, 0, i32::MAX, 0)), 1);
+        assert_eq!(count_sampled(&mut pixbuf, Rectangle::new_from_coordinates(W as i32, 0, W as i32 + 10, 0)), 0);
+        // Upside down rectangles select nothing
+        assert_eq!(count_sampled(&mut pixbuf, Rectangle::new_from_coordinates(5, 5, 2, 2)), 0);
+    }
+
+    #[test]
+    fn test_virtual_samples() {
+        let mut pixbuf = blank();
+        let mut sampler = MatrixSampler::new(&mut pixbuf);
+        let mut num_sampled = 0;
+        for (_, pix) in Sample::<Virtual>::sample(&mut sampler, &Rectangle::everything()) {
+            pix.r += 1;
+            num_sampled += 1;
+        }
+        assert_eq!(num_sampled, W * H);
+        assert!(pixbuf.iter().flatten().all(|pix| pix.r == 1), "Every cell should be sampled exactly once: {pixbuf:?}");
+
+        // The right half of the virtual space should only select the right half of the columns
+        let mut sampler = MatrixSampler::new(&mut pixbuf);
+        let rect = Rectangle::new_from_coordinates(128, 0, 255, 255);
+        for (coords, pix) in Sample::<Virtual>::sample(&mut sampler, &rect) {
+            assert!(coords.x >= 128, "{coords:?} is outside of {rect:?}");
+            pix.g = 255;
+        }
+        for row in pixbuf.iter() {
+            assert!(row[..W / 2].iter().all(|pix| pix.g == 0));
+            assert!(row[W / 2..].iter().all(|pix| pix.g == 255));
+        }
+    }
+
+    #[test]
+    fn test_virtual_samples_wide_matrix() {
+        // More columns than there are virtual coordinates
+        let mut pixbuf = [[0u8; 300]; 2];
+        let mut sampler = MatrixSampler::new(&mut pixbuf);
+        for (_, pix) in Sample::<Virtual>::sample(&mut sampler, &Rectangle::everything()) {
+            *pix += 1;
+        }
+        assert!(pixbuf.iter().flatten().all(|pix| *pix == 1), "Every cell should be sampled exactly once: {pixbuf:?}");
+
+        // Adjacent rectangles should still split the columns between them
+        let mut sampler = MatrixSampler::new(&mut pixbuf);
+        for (coords, pix) in Sample::<Virtual>::sample(&mut sampler, &Rectangle::new_from_coordinates(0, 0, 127, 255)) {
+            assert!(coords.x <= 127, "{coords:?} is outside of the left half");
+            *pix += 1;
+        }
+        let mut sampler = MatrixSampler::new(&mut pixbuf);
+        for (coords, pix) in Sample::<Virtual>::sample(&mut sampler, &Rectangle::new_from_coordinates(128, 0, 255, 255)) {
+            assert!(coords.x >= 128, "{coords:?} is outside of the right half");
+            *pix += 1;
+        }
+        assert!(pixbuf.iter().flatten().all(|pix| *pix == 2), "Every cell should be sampled exactly once by both halves: {pixbuf:?}");
+    }
+
+    #[test]
+    fn test_virtual_samples_empty_matrix() {
+        let mut no_rows = [[0u8; 300]; 0];
+        assert_eq!(Sample::<Virtual>::sample(&mut MatrixSampler::new(&mut no_rows), &Rectangle::everything()).count(), 0);
+        let mut no_cols = [[0u8; 0]; 4];
+        assert_eq!(Sample::<Virtual>::sample(&mut MatrixSampler::new(&mut no_cols), &Rectangle::everything()).count(), 0);
+    }
+
+    fn sampled_indexes(map: &MatrixMapping, rect: Rectangle<Virtual>) -> [bool; W * H] {
+        let mut pixbuf = [false; W * H];
+        let mut sampler = MatrixMappingSampler::new(&mut pixbuf, map);
+        for (_, pix) in sampler.sample(&rect) {
+            assert!(!*pix, "Every pixel should only be sampled once");
+            *pix = true;
+        }
+        pixbuf
+    }
+
+    #[test]
+    fn test_mapping_sub_rectangle_indexes() {
+        // The left half of the columns, and the top three rows
+        let rect = Rectangle::new_from_coordinates(0, 0, 127, 109);
+        let expected = |x: usize, y: usize| x < W / 2 && y < 3;
+
+        let map = MatrixMapping::new(W, H);
+        let sampled = sampled_indexes(&map, rect);
+        for (idx, was_sampled) in sampled.iter().enumerate() {
+            assert_eq!(*was_sampled, expected(idx % W, idx / W), "Pixel {idx} was sampled incorrectly");
+        }
+        assert_eq!(sampled_indexes(&map, Rectangle::everything()), [true; W * H]);
+
+        // Each origin flips the physical layout, but selects the same area of the display
+        for (origin, flip_x, flip_y) in [(MatrixOrigin::TopRight, true, false), (MatrixOrigin::BottomLeft, false, true), (MatrixOrigin::BottomRight, true, true)] {
+            let sampled = sampled_indexes(&map.with_origin(origin), rect);
+            for (idx, was_sampled) in sampled.iter().enumerate() {
+                let (x, y) = (idx % W, idx / W);
+                let x = if flip_x { W - 1 - x } else { x };
+                let y = if flip_y { H - 1 - y } else { y };
+                assert_eq!(*was_sampled, expected(x, y), "Pixel {idx} was sampled incorrectly with {origin:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_mapping_matrix_space_indexes() {
+        let rect: Rectangle<MatrixSpace> = Rectangle::new_from_coordinates(2, 1, 5, 3);
+        for origin in [MatrixOrigin::TopLeft, MatrixOrigin::TopRight, MatrixOrigin::BottomLeft, MatrixOrigin::BottomRight] {
+            let map = MatrixMapping::new(W, H).with_origin(origin);
+            let mut pixbuf = [None; W * H];
+            for (coords, pix) in MatrixMappingSampler::new(&mut pixbuf, &map).sample(&rect) {
+                assert!(pix.is_none(), "Every pixel should only be sampled once");
+                *pix = Some((coords.x, coords.y));
+            }
+            for x in 0..W {
+                for y in 0..H {
+                    let expected = ((2..=5).contains(&x) && (1..=3).contains(&y)).then_some((x, y));
+                    assert_eq!(pixbuf[map.index(x, y)], expected, "Cell ({x}, {y}) was sampled incorrectly with {origin:?}");
+                }
+            }
+        }
+
+        // Rectangles past the edge of the grid are clipped to it
+        let map = MatrixMapping::new(W, H);
+        let mut pixbuf = [0u8; W * H];
+        let mut sampler = MatrixMappingSampler::new(&mut pixbuf, &map);
+        assert_eq!(sampler.sample(&Rectangle::<MatrixSpace>::everything()).count(), W * H);
+        assert_eq!(sampler.sample(&Rectangle::<MatrixSpace>::new_from_coordinates(W - 1, 0, usize::MAX, 0)).count(), 1);
+        assert_eq!(sampler.sample(&Rectangle::<MatrixSpace>::new_from_coordinates(W, 0, W + 10, 0)).count(), 0);
+        assert_eq!(Sample::<MatrixSpace>::bounds(&sampler), Rectangle::new_from_coordinates(0, 0, W - 1, H - 1));
+        assert_eq!(Sample::<MatrixSpace>::sample(&mut MatrixMappingSampler::new(&mut pixbuf, &MatrixMapping::new(0, H)), &Rectangle::everything()).count(), 0);
+    }
+
+    #[test]
+    fn test_mapping_wide_and_empty_grids() {
+        // More columns than there are virtual coordinates
+        let map = MatrixMapping::new(300, 2).with_origin(MatrixOrigin::BottomRight);
+        let mut pixbuf = [0u8; 600];
+        for (_, pix) in Sample::<Virtual>::sample(&mut MatrixMappingSampler::new(&mut pixbuf, &map), &Rectangle::everything()) {
+            *pix += 1;
+        }
+        assert!(pixbuf.iter().all(|pix| *pix == 1), "Every pixel should be sampled exactly once: {pixbuf:?}");
+
+        let mut pixbuf = [0u8; 4];
+        for map in [MatrixMapping::new(0, 4), MatrixMapping::new(4, 0), MatrixMapping::new(0, 0)] {
+            assert_eq!(Sample::<Virtual>::sample(&mut MatrixMappingSampler::new(&mut pixbuf, &map), &Rectangle::everything()).count(), 0, "{map:?} should be empty");
+        }
+    }
+
+    #[test]
+    fn test_mapping_matches_matrix_sampler() {
+        let map = MatrixMapping::new(W, H);
+        let rect = Rectangle::new_from_coordinates(40, 90, 200, 180);
+        let mut flat = [VirtualCoordinates::top_left(); W * H];
+        for (coords, pix) in Sample::<Virtual>::sample(&mut MatrixMappingSampler::new(&mut flat, &map), &rect) {
+            *pix = coords;
+        }
+        let mut grid = [[VirtualCoordinates::top_left(); W]; H];
+        for (coords, pix) in Sample::<Virtual>::sample(&mut MatrixSampler::new(&mut grid), &rect) {
+            *pix = coords;
+        }
+        assert_eq!(flat, *grid.as_flattened(), "A flat buffer should be sampled exactly like the same matrix of rows");
+    }
+}
@@ -0,0 +1,171 @@
+use core::ops::Div;
+
+use crate::geometry::*;
+use crate::render::Sample;
+
+/// How a [MirrorSampler] folds the coordinates of a display back onto one part of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MirrorMode {
+    /// The right half is a mirror image of the left half
+    #[default]
+    Horizontal,
+    /// The bottom half is a mirror image of the top half
+    Vertical,
+    /// Every quadrant is a mirror image of the top left quadrant, like a kaleidoscope
+    Both,
+    /// Every quadrant is a copy of the top left quadrant, repeated without being flipped
+    Quad
+}
+
+/// A [Sample] wrapper that folds the coordinates of another sampler, so that effects drawn over the whole display become symmetric
+///
+/// The pixels are still selected by the wrapped sampler, but their coordinates are replaced with the matching coordinates in the top left part of the display. Every shader that is rendered over the whole display will then only draw that part, which is repeated according to the [MirrorMode].
+///
+/// Coordinates are folded around the middle of [Sample::bounds]. Samplers that round physical pixels to [Virtual] coordinates can report coordinates that are not exactly symmetric, so a mirrored pixel may land one virtual coordinate away from its twin.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::mappings::{MatrixSampler, MirrorMode, MirrorSampler};
+///
+/// let mut pixbuf = [[0u8; 4]; 1];
+/// let mut matrix = MatrixSampler::new(&mut pixbuf);
+/// let mut sampler = MirrorSampler::new(&mut matrix, MirrorMode::Horizontal);
+/// for (coords, pixel) in Sample::<Virtual>::sample(&mut sampler, &Rectangle::everything()) {
+///     *pixel = coords.x / 64;
+/// }
+/// assert_eq!(pixbuf, [[0, 1, 1, 0]]);
+/// ```
+#[derive(Debug)]
+pub struct MirrorSampler<'a, S: ?Sized> {
+    inner: &'a mut S,
+    mode: MirrorMode
+}
+
+impl<'a, S: ?Sized> MirrorSampler<'a, S> {
+    /// Creates a new wrapper around a sampler
+    pub const fn new(inner: &'a mut S, mode: MirrorMode) -> Self {
+        Self {
+            inner,
+            mode
+        }
+    }
+
+    /// Returns the current [MirrorMode]
+    pub const fn mode(&self) -> MirrorMode {
+        self.mode
+    }
+
+    /// Changes how the coordinates are folded
+    pub fn set_mode(&mut self, mode: MirrorMode) {
+        self.mode = mode;
+    }
+}
+
+/// Folds a value in the second half of the inclusive range of low to high onto the first half, either by mirroring it around the middle or by shifting it over by half of the range
+fn fold<T: CoordinateOp + Div<Output = T>>(value: T, low: T, high: T, repeat: bool) -> T {
+    if value < low || value > high {
+        return value;
+    }
+    let middle = low + (high - low) / T::from(2);
+    if value <= middle {
+        value
+    } else if repeat {
+        value - (middle + T::one() - low)
+    } else {
+        low + (high - value)
+    }
+}
+
+impl<'a, Space: CoordinateSpace, S: Sample<'a, Space> + ?Sized> Sample<'a, Space> for MirrorSampler<'a, S> where Space::Data: Div<Output = Space::Data> {
+    type Output = S::Output;
+
+    fn sample(&mut self, rect: &Rectangle<Space>) -> impl Iterator<Item = (Coordinates<Space>, &'a mut Self::Output)> {
+        let bounds = self.inner.bounds();
+        let (fold_x, fold_y) = match self.mode {
+            MirrorMode::Horizontal => (true, false),
+            MirrorMode::Vertical => (false, true),
+            MirrorMode::Both | MirrorMode::Quad => (true, true)
+        };
+        let repeat = self.mode == MirrorMode::Quad;
+        self.inner.sample(rect).map(move |(mut coords, pixel)| {
+            if fold_x {
+                coords.x = fold(coords.x, bounds.left(), bounds.right(), repeat);
+            }
+            if fold_y {
+                coords.y = fold(coords.y, bounds.top(), bounds.bottom(), repeat);
+            }
+            (coords, pixel)
+        })
+    }
+
+    fn bounds(&self) -> Rectangle<Space> {
+        self.inner.bounds()
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.inner.pixel_count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::ops::Range;
+    use crate::mappings::matrix::{Matrix2DSpace, MatrixSampler};
+
+    /// Lights every pixel whose folded coordinate lands within the given band of virtual columns
+    fn light_column<const W: usize, const H: usize>(pixbuf: &mut [[u8; W]; H], mode: MirrorMode, column: Range<u8>) {
+        let mut matrix = MatrixSampler::new(pixbuf);
+        let mut sampler = MirrorSampler::new(&mut matrix, mode);
+        for (coords, pixel) in Sample::<Virtual>::sample(&mut sampler, &Rectangle::everything()) {
+            if column.contains(&coords.x) {
+                *pixel = 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_column_is_mirrored_horizontally() {
+        let mut pixbuf = [[0u8; 8]; 2];
+        light_column(&mut pixbuf, MirrorMode::Horizontal, 32..64);
+        assert_eq!(pixbuf, [[0, 1, 0, 0, 0, 0, 1, 0]; 2], "The column should appear on both sides");
+
+        let mut pixbuf = [[0u8; 8]; 2];
+        light_column(&mut pixbuf, MirrorMode::Vertical, 32..64);
+        assert_eq!(pixbuf, [[0, 1, 0, 0, 0, 0, 0, 0]; 2], "Vertical mirroring should leave columns alone");
+
+        // Physical coordinates are mirrored exactly, and the middle column of an odd width display is its own mirror image
+        let mut pixbuf = [[0u8; 7]; 1];
+        let mut matrix = MatrixSampler::new(&mut pixbuf);
+        let mut sampler = MirrorSampler::new(&mut matrix, MirrorMode::Horizontal);
+        for (coords, pixel) in Sample::<Matrix2DSpace>::sample(&mut sampler, &Rectangle::everything()) {
+            *pixel = coords.x as u8;
+        }
+        assert_eq!(pixbuf, [[0, 1, 2, 3, 2, 1, 0]]);
+    }
+
+    /// Numbers every pixel by its folded position within the top left quadrant of an 8x8 matrix
+    fn number_quadrant(mode: MirrorMode) -> [[u8; 8]; 8] {
+        let mut pixbuf = [[0u8; 8]; 8];
+        let mut matrix = MatrixSampler::new(&mut pixbuf);
+        let mut sampler = MirrorSampler::new(&mut matrix, mode);
+        for (coords, pixel) in Sample::<Matrix2DSpace>::sample(&mut sampler, &Rectangle::everything()) {
+            *pixel = (coords.y * 4 + coords.x) as u8;
+        }
+        pixbuf
+    }
+
+    #[test]
+    fn test_quadrants_mirror_and_repeat() {
+        let pixbuf = number_quadrant(MirrorMode::Both);
+        assert_eq!(pixbuf[0], [0, 1, 2, 3, 3, 2, 1, 0]);
+        assert_eq!(pixbuf[1], [4, 5, 6, 7, 7, 6, 5, 4]);
+        assert_eq!(pixbuf[7], pixbuf[0], "The bottom half should be flipped");
+        assert_eq!(pixbuf[4], pixbuf[3]);
+
+        let pixbuf = number_quadrant(MirrorMode::Quad);
+        assert_eq!(pixbuf[0], [0, 1, 2, 3, 0, 1, 2, 3]);
+        assert_eq!(pixbuf[5], [4, 5, 6, 7, 4, 5, 6, 7]);
+        assert_eq!(pixbuf[4], pixbuf[0], "The bottom half should be repeated without flipping");
+    }
+}
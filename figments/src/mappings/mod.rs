@@ -1,3 +1,30 @@
+//! Mappings between coordinate spaces and physical pixel buffers, along with the [Sample](crate::render::Sample) implementations that use them
 pub mod linear;
 pub mod stride;
-pub mod embedded_graphics;
\ No newline at end of file
+pub mod matrix;
+pub mod embedded_graphics;
+pub mod composite;
+pub mod mirror;
+
+pub use linear::{LinearSampler, LinearSpace};
+pub use matrix::{Matrix2DSpace, MatrixMapping, MatrixMappingSampler, MatrixOrigin, MatrixSampler, MatrixSpace};
+pub use stride::{StrideLayout, StrideMapping, StrideSampler, StrideSpace, StrideView};
+#[cfg(feature="alloc")]
+pub use stride::DynStrideMapping;
+#[cfg(feature="serde")]
+pub use stride::StrideLayoutError;
+pub use composite::{CompositeMapping, CompositeSampler};
+pub use mirror::{MirrorMode, MirrorSampler};
+
+use crate::geometry::{CoordinateSpace, Rectangle};
+
+/// Mappings that can select an exact region of physical pixels with a rectangle in another [CoordinateSpace], such as [Virtual](crate::geometry::Virtual)
+///
+/// This allows something like a status indicator to always cover the same real pixels, no matter how large the display is.
+pub trait PhysicalMapping<Space: CoordinateSpace> {
+    /// The space that physical pixels are addressed in
+    type Physical: CoordinateSpace;
+
+    /// Returns the rectangle in `Space` that selects exactly the given physical pixels
+    fn physical_rect(&self, rect: &Rectangle<Self::Physical>) -> Rectangle<Space>;
+}
@@ -1,11 +1,19 @@
 use core::cmp::{max, min};
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
 use core::ops::IndexMut;
 
 use crate::geometry::*;
-use crate::liber8tion::interpolate::Fract8;
+use crate::render::{DisplayInfo, Sample, SampleIndexed};
+use crate::mappings::PhysicalMapping;
+use crate::logging::trace;
 
+#[cfg(feature="alloc")]
+use alloc::vec::Vec;
+
+/// A single strip of pixels within a [StrideMapping], which runs down the Y axis at a given X coordinate
 #[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
-struct Stride {
+pub struct Stride {
     pub length: usize,
     pub x: usize,
     pub y: usize,
@@ -14,20 +22,111 @@ struct Stride {
 }
 
 impl Stride {
-    pub const fn pixel_idx_for_offset(&self, offset: usize) -> usize {
-        if self.reverse {
-            self.physical_idx + self.length + self.y - 1 - offset
+    /// Returns the index of the physical pixel at the given Y coordinate, or None if the coordinate is outside of this stride
+    pub const fn checked_pixel_idx_for_offset(&self, offset: usize) -> Option<usize> {
+        if offset < self.y || offset >= self.y + self.length {
+            None
+        } else if self.reverse {
+            Some(self.physical_idx + self.length + self.y - 1 - offset)
         } else {
-            self.physical_idx + offset
+            Some(self.physical_idx + offset - self.y)
         }
     }
 }
 
+/// Layouts of strides that can be sampled with a [StrideSampler], such as a [StrideMapping] or a [DynStrideMapping]
+pub trait StrideLayout {
+    /// Returns every configured stride, in the order that they are wired
+    fn strides(&self) -> &[Stride];
+
+    /// Returns the physical size of the display this layout is configured for
+    fn size(&self) -> Rectangle<StrideSpace>;
+
+    /// Returns the number of physical pixels in this layout
+    fn pixel_count(&self) -> usize;
+
+    /// Returns the physical size of the display, for shaders that use a [RenderContext](crate::render::RenderContext)
+    fn display_info(&self) -> DisplayInfo {
+        let size = self.size();
+        DisplayInfo::new(size.width() + 1, size.height() + 1)
+    }
+
+    /// Returns the physical stride coordinate that a [Virtual] coordinate selects when sampling the whole layout, or None if it lands in a gap between or around the strides
+    fn virtual_to_physical(&self, coords: VirtualCoordinates) -> Option<StrideCoords> {
+        let size = self.size();
+        let physical = StrideCoords::new(
+            Virtual::to_physical(coords.x, size.width()) + size.left(),
+            Virtual::to_physical(coords.y, size.height()) + size.top()
+        );
+        self.strides().get(physical.x)?.checked_pixel_idx_for_offset(physical.y)?;
+        Some(physical)
+    }
+
+    /// Returns the smallest [Virtual] coordinate that selects a physical stride coordinate, or None if there is no pixel there
+    ///
+    /// This is the inverse of [StrideLayout::virtual_to_physical], which can differ by a little from the coordinate that a [StrideView] reports for the pixel because virtual coordinates are rounded down when they are scaled. Layouts that are more than 256 pixels across can't select every pixel, in which case the nearest pixel is selected instead.
+    fn physical_to_virtual(&self, coords: StrideCoords) -> Option<VirtualCoordinates> {
+        self.strides().get(coords.x)?.checked_pixel_idx_for_offset(coords.y)?;
+        let size = self.size();
+        let (x, _) = virtual_span(coords.x, coords.x, size.left(), size.width());
+        let (y, _) = virtual_span(coords.y, coords.y, size.top(), size.height());
+        Some(VirtualCoordinates::new(x, y))
+    }
+}
+
+/// Lays out each (x, y, pixel_num, reversed) one after another along the strip, passing the index of each [Stride] to `push` along with the stride itself. Returns the total number of pixels and the size of the display.
+///
+/// Strides without any pixels don't count towards the size, and a layout without any pixels at all is given a single empty coordinate as its size, so layouts loaded at runtime can't cause a panic here.
+fn build_strides(stride_json: impl Iterator<Item = (usize, usize, usize, bool)>, mut push: impl FnMut(usize, Stride)) -> (usize, Rectangle<StrideSpace>) {
+    let mut physical_idx = 0;
+    let mut size: Option<Rectangle<StrideSpace>> = None;
+    for (stride_idx, json_data) in stride_json.enumerate() {
+        let x = json_data.0;
+        let y = json_data.1;
+        let length = json_data.2;
+        let reverse = json_data.3;
+        push(stride_idx, Stride {
+            length,
+            x,
+            y,
+            reverse,
+            physical_idx
+        });
+        trace!("stride {}: x={} y={} length={} reverse={}", stride_idx, x, y, length, reverse);
+        physical_idx += length;
+        if length == 0 {
+            continue;
+        }
+        size = Some(match size.take() {
+            None => Rectangle::new(
+                Coordinates::new(x, y),
+                Coordinates::new(x, y + length - 1),
+            ),
+            Some(s) => Rectangle::new(
+                Coordinates::new(
+                    min(s.top_left.x, x),
+                    min(s.top_left.y, y)
+                ),
+                Coordinates::new(
+                    max(s.bottom_right.x, x),
+                    max(s.bottom_right.y, y + length - 1)
+                )
+            )
+        });
+    }
+    (physical_idx, size.unwrap_or_else(|| Rectangle::single(Coordinates::new(0, 0))))
+}
+
 /// A mapping between 2d [Virtual] coordinates and a 2d display composed of individual strips of pixels
+///
+/// The strides are stored inline, so the largest number of strides has to be known at compile time. Layouts that are loaded at runtime can use a [DynStrideMapping] instead.
 #[derive(Debug)]
 pub struct StrideMapping<const STRIDE_NUM: usize = 64> {
     strides: [Stride; STRIDE_NUM],
 
+    /// The number of strides that are actually configured, which may be less than STRIDE_NUM
+    stride_count: usize,
+
     /// The number of physical pixels in this map
     pub pixel_count: usize,
 
@@ -37,62 +136,386 @@ pub struct StrideMapping<const STRIDE_NUM: usize = 64> {
 
 impl<const STRIDE_NUM: usize> Default for StrideMapping<STRIDE_NUM> {
     fn default() -> Self {
+        // A single strip with one pixel for every virtual coordinate
         Self::from_json(&[
-            (0, 0, 255, false)
+            (0, 0, 256, false)
         ])
     }
 }
 
+impl<const STRIDE_NUM: usize> StrideLayout for StrideMapping<STRIDE_NUM> {
+    fn strides(&self) -> &[Stride] {
+        &self.strides[..self.stride_count]
+    }
+
+    fn size(&self) -> Rectangle<StrideSpace> {
+        self.size
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.pixel_count
+    }
+}
+
 impl<const STRIDE_NUM: usize> StrideMapping<STRIDE_NUM> {
+    /// Returns the physical size of the display, for shaders that use a [RenderContext](crate::render::RenderContext)
+    pub fn display_info(&self) -> DisplayInfo {
+        StrideLayout::display_info(self)
+    }
+
     /// Creates a new stride mapping from a sequence of (x, y, pixel_num, reversed)
     pub fn from_json(stride_json: &[(usize, usize, usize, bool)]) -> Self {
+        Self::from_strides(stride_json.iter().copied())
+    }
+
+    /// Parses a layout from a JSON array of strides, such as one stored on flash or received over the network
+    ///
+    /// Each stride is either an `[x, y, pixel_num, reversed]` array, or an object with `x`, `y`, `length`, and an optional `reverse` field. Unlike [StrideMapping::from_json], a layout that does not fit into `STRIDE_NUM` strides is an error instead of a panic.
+    ///
+    /// ```
+    /// use figments::mappings::StrideMapping;
+    ///
+    /// let map: StrideMapping = StrideMapping::from_json_str(r#"[[0, 0, 8, false], {"x": 1, "y": 0, "length": 8, "reverse": true}]"#).unwrap();
+    /// assert_eq!(map.pixel_count, 16);
+    /// ```
+    #[cfg(feature="serde")]
+    pub fn from_json_str(json: &str) -> Result<Self, StrideLayoutError> {
+        let mut strides = [(0, 0, 0, false); STRIDE_NUM];
+        let count = parse_json_layout(json, |idx, stride| {
+            if let Some(slot) = strides.get_mut(idx) {
+                *slot = stride;
+            }
+        })?;
+        if count > STRIDE_NUM {
+            return Err(StrideLayoutError::TooManyStrides { count, capacity: STRIDE_NUM });
+        }
+        Ok(Self::from_strides(strides[..count].iter().copied()))
+    }
+
+    /// Creates a mapping for a `width` x `height` matrix that is wired in a zig-zag, where every other column runs in the opposite direction
+    ///
+    /// Each column of the matrix is one stride, starting with the top left pixel and running downwards.
+    ///
+    /// ```
+    /// use figments::mappings::StrideMapping;
+    ///
+    /// let map: StrideMapping = StrideMapping::new_serpentine(16, 8);
+    /// assert_eq!(map.pixel_count, 128);
+    /// assert_eq!((map.size.width(), map.size.height()), (15, 7));
+    /// ```
+    pub fn new_serpentine(width: usize, height: usize) -> Self {
+        Self::from_strides((0..width).map(|x| (x, 0, height, x % 2 == 1)))
+    }
+
+    /// Creates a mapping for a `width` x `height` matrix where every column runs in the same direction, starting with the top left pixel and running downwards
+    pub fn new_progressive(width: usize, height: usize) -> Self {
+        Self::from_strides((0..width).map(|x| (x, 0, height, false)))
+    }
+
+    fn from_strides(stride_json: impl ExactSizeIterator<Item = (usize, usize, usize, bool)>) -> Self {
         let mut strides = [Stride::default(); STRIDE_NUM];
         let stride_count = stride_json.len();
-        let mut physical_idx = 0;
-        let mut size: Option<Rectangle<StrideSpace>> = None;
         assert!(stride_count <= STRIDE_NUM);
-        for stride_idx in 0..stride_count {
-            let json_data = stride_json[stride_idx];
-            let x = json_data.0;
-            let y = json_data.1;
-            let length = json_data.2;
-            let reverse = json_data.3;
-            strides[stride_idx] = Stride {
-                length,
-                x,
-                y,
-                reverse,
-                physical_idx
+        let (pixel_count, size) = build_strides(stride_json, |stride_idx, stride| strides[stride_idx] = stride);
+
+        Self {
+            strides,
+            stride_count,
+            pixel_count,
+            size
+        }
+    }
+}
+
+/// The reasons a stride layout can fail to load from JSON
+#[cfg(feature="serde")]
+#[derive(Debug)]
+pub enum StrideLayoutError {
+    /// The JSON could not be parsed as a list of strides
+    Malformed(serde_json_core::de::Error),
+    /// The layout has more strides than the mapping can hold
+    TooManyStrides {
+        /// The number of strides in the layout
+        count: usize,
+        /// The largest number of strides the mapping can hold
+        capacity: usize
+    },
+    /// The layout does not have any strides
+    Empty,
+    /// A stride does not have any pixels
+    EmptyStride {
+        /// The position of the stride within the layout
+        index: usize
+    }
+}
+
+#[cfg(feature="serde")]
+impl core::fmt::Display for StrideLayoutError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            StrideLayoutError::Malformed(err) => write!(f, "the stride layout is not valid JSON: {err}"),
+            StrideLayoutError::TooManyStrides { count, capacity } => write!(f, "the layout has {count} strides, but only {capacity} fit in the mapping"),
+            StrideLayoutError::Empty => write!(f, "the layout does not have any strides"),
+            StrideLayoutError::EmptyStride { index } => write!(f, "stride {index} does not have any pixels")
+        }
+    }
+}
+
+#[cfg(feature="serde")]
+impl From<serde_json_core::de::Error> for StrideLayoutError {
+    fn from(err: serde_json_core::de::Error) -> Self {
+        StrideLayoutError::Malformed(err)
+    }
+}
+
+/// A single stride within a JSON layout that is written as an object, instead of an `[x, y, pixel_num, reversed]` array
+#[cfg(feature="serde")]
+#[derive(serde::Deserialize)]
+struct StrideObject {
+    x: usize,
+    y: usize,
+    length: usize,
+    #[serde(default)]
+    reverse: bool
+}
+
+/// Returns the rest of the JSON text after any leading whitespace
+#[cfg(feature="serde")]
+fn skip_whitespace(json: &[u8]) -> &[u8] {
+    let start = json.iter().position(|c| !c.is_ascii_whitespace()).unwrap_or(json.len());
+    &json[start..]
+}
+
+/// Returns the length of the array or object at the start of the JSON text, up to and including its closing bracket
+///
+/// Strides never contain nested arrays or objects, so this only has to skip over brackets within strings. Anything that is nested anyway is left for serde to reject.
+#[cfg(feature="serde")]
+fn stride_len(json: &[u8]) -> Result<usize, serde_json_core::de::Error> {
+    let close = match json.first() {
+        Some(b'[') => b']',
+        Some(b'{') => b'}',
+        Some(_) => return Err(serde_json_core::de::Error::InvalidType),
+        None => return Err(serde_json_core::de::Error::EofWhileParsingList)
+    };
+    let mut in_string = false;
+    let mut escaped = false;
+    for (idx, &c) in json.iter().enumerate().skip(1) {
+        match (in_string, escaped, c) {
+            (true, true, _) => escaped = false,
+            (true, false, b'\\') => escaped = true,
+            (_, _, b'"') => in_string = !in_string,
+            (false, _, c) if c == close => return Ok(idx + 1),
+            _ => ()
+        }
+    }
+    Err(serde_json_core::de::Error::EofWhileParsingList)
+}
+
+/// Parses a JSON layout into the (x, y, pixel_num, reversed) tuples used by [StrideMapping::from_json], passing each one to `push` along with its index and returning the number of strides
+///
+/// serde-json-core can't tell ahead of time whether a value is an array or an object, so the list of strides is walked here and each stride is handed to it separately. Layouts that would not cover any pixels are rejected.
+#[cfg(feature="serde")]
+fn parse_json_layout(json: &str, mut push: impl FnMut(usize, (usize, usize, usize, bool))) -> Result<usize, StrideLayoutError> {
+    use serde_json_core::de::Error;
+
+    let mut rest = match skip_whitespace(json.as_bytes()).split_first() {
+        Some((b'[', rest)) => skip_whitespace(rest),
+        Some(_) => return Err(Error::InvalidType.into()),
+        None => return Err(Error::EofWhileParsingValue.into())
+    };
+    let mut count = 0;
+    if let Some(after) = rest.strip_prefix(b"]") {
+        rest = after;
+    } else {
+        loop {
+            let len = stride_len(rest)?;
+            let stride = match rest[0] {
+                b'[' => serde_json_core::from_slice::<(usize, usize, usize, bool)>(&rest[..len])?.0,
+                _ => {
+                    let (StrideObject { x, y, length, reverse }, _) = serde_json_core::from_slice(&rest[..len])?;
+                    (x, y, length, reverse)
+                }
+            };
+            if stride.2 == 0 {
+                return Err(StrideLayoutError::EmptyStride { index: count });
+            }
+            push(count, stride);
+            count += 1;
+
+            rest = match skip_whitespace(&rest[len..]).split_first() {
+                Some((b',', after)) => skip_whitespace(after),
+                Some((b']', after)) => {
+                    rest = after;
+                    break;
+                },
+                Some(_) => return Err(Error::ExpectedListCommaOrEnd.into()),
+                None => return Err(Error::EofWhileParsingList.into())
             };
-            physical_idx += length;
-            size = Some(match size.take() {
-                None => Rectangle::new(
-                    Coordinates::new(x, y),
-                    Coordinates::new(x, y + length - 1),
-                ),
-                Some(s) => Rectangle::new(
-                    Coordinates::new(
-                        min(s.top_left.x, x),
-                        min(s.top_left.y, y)
-                    ),
-                    Coordinates::new(
-                        max(s.bottom_right.x, x),
-                        max(s.bottom_right.y, y + length - 1)
-                    )
-                )
-            });
         }
+    }
+    if !skip_whitespace(rest).is_empty() {
+        return Err(Error::TrailingCharacters.into());
+    }
+    match count {
+        0 => Err(StrideLayoutError::Empty),
+        _ => Ok(count)
+    }
+}
+
+/// A [StrideMapping] that stores its strides in a [Vec], so any number of strides can be loaded at runtime, such as from a config file
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::mappings::{DynStrideMapping, StrideSampler};
+///
+/// let map = DynStrideMapping::from_json(&[(0, 0, 8, false), (1, 0, 8, true)]);
+/// let mut pixbuf = [0u8; 16];
+/// let mut sampler = StrideSampler::with_layout(&mut pixbuf, &map);
+/// assert_eq!(sampler.sample(&Rectangle::everything()).count(), 16);
+/// ```
+#[cfg(feature="alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynStrideMapping {
+    strides: Vec<Stride>,
+
+    /// The number of physical pixels in this map
+    pub pixel_count: usize,
 
+    /// The physical size of the display this map is configured for
+    pub size: Rectangle<StrideSpace>,
+}
+
+#[cfg(feature="alloc")]
+impl Default for DynStrideMapping {
+    fn default() -> Self {
+        // A single strip with one pixel for every virtual coordinate
+        Self::from_json(&[
+            (0, 0, 256, false)
+        ])
+    }
+}
+
+#[cfg(feature="alloc")]
+impl DynStrideMapping {
+    /// Creates a new stride mapping from a sequence of (x, y, pixel_num, reversed), which can have any number of strides
+    pub fn from_json(stride_json: &[(usize, usize, usize, bool)]) -> Self {
+        Self::from_strides(stride_json.iter().copied())
+    }
+
+    /// Parses a layout from a JSON array of strides, like [StrideMapping::from_json_str] but without any limit on the number of strides
+    #[cfg(feature="serde")]
+    pub fn from_json_str(json: &str) -> Result<Self, StrideLayoutError> {
+        let mut strides = Vec::new();
+        parse_json_layout(json, |_, stride| strides.push(stride))?;
+        Ok(Self::from_json(&strides))
+    }
+
+    /// Creates a mapping for a `width` x `height` matrix that is wired in a zig-zag, like [StrideMapping::new_serpentine]
+    pub fn new_serpentine(width: usize, height: usize) -> Self {
+        Self::from_strides((0..width).map(|x| (x, 0, height, x % 2 == 1)))
+    }
+
+    /// Creates a mapping for a `width` x `height` matrix where every column runs in the same direction, like [StrideMapping::new_progressive]
+    pub fn new_progressive(width: usize, height: usize) -> Self {
+        Self::from_strides((0..width).map(|x| (x, 0, height, false)))
+    }
+
+    fn from_strides(stride_json: impl ExactSizeIterator<Item = (usize, usize, usize, bool)>) -> Self {
+        let mut strides = Vec::with_capacity(stride_json.len());
+        let (pixel_count, size) = build_strides(stride_json, |_, stride| strides.push(stride));
         Self {
             strides,
-            pixel_count: physical_idx,
-            size: size.unwrap(),
+            pixel_count,
+            size
         }
     }
 }
 
+#[cfg(feature="alloc")]
+impl StrideLayout for DynStrideMapping {
+    fn strides(&self) -> &[Stride] {
+        &self.strides
+    }
+
+    fn size(&self) -> Rectangle<StrideSpace> {
+        self.size
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.pixel_count
+    }
+}
+
+#[cfg(feature="alloc")]
+impl PhysicalMapping<Virtual> for DynStrideMapping {
+    type Physical = StrideSpace;
+
+    fn physical_rect(&self, rect: &Rectangle<StrideSpace>) -> Rectangle<Virtual> {
+        physical_rect_within(&self.size, rect)
+    }
+}
+
+/// Converts an inclusive span of physical coordinates into the widest span of virtual coordinates that selects only those coordinates, which is the inverse of how a [StrideView] scales its rectangle
+fn virtual_span(start: usize, end: usize, origin: usize, len: usize) -> (u8, u8) {
+    if len == 0 {
+        return (u8::MIN, u8::MAX);
+    }
+    let start = min(start.saturating_sub(origin), len);
+    let end = min(end.saturating_sub(origin), len);
+    let first = (start * 255).div_ceil(len);
+    let last = ((end + 1) * 255).div_ceil(len) - 1;
+    (first as u8, min(last, 255) as u8)
+}
+
+impl<const STRIDE_NUM: usize> StrideMapping<STRIDE_NUM> {
+    /// Returns the physical stride coordinate that a [Virtual] coordinate selects when sampling the whole mapping, or None if it lands in a gap between or around the strides
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    /// use figments::mappings::stride::{StrideCoords, StrideMapping};
+    ///
+    /// let map: StrideMapping = StrideMapping::from_json(&[(0, 0, 8, false), (1, 4, 4, true)]);
+    /// assert_eq!(map.virtual_to_physical(VirtualCoordinates::new(255, 255)), Some(StrideCoords::new(1, 7)));
+    /// assert_eq!(map.virtual_to_physical(VirtualCoordinates::new(255, 0)), None);
+    /// ```
+    pub fn virtual_to_physical(&self, coords: VirtualCoordinates) -> Option<StrideCoords> {
+        StrideLayout::virtual_to_physical(self, coords)
+    }
+
+    /// Returns the smallest [Virtual] coordinate that selects a physical stride coordinate, or None if there is no pixel there
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    /// use figments::mappings::stride::{StrideCoords, StrideMapping};
+    ///
+    /// let map: StrideMapping = StrideMapping::from_json(&[(0, 0, 8, false), (1, 4, 4, true)]);
+    /// let coords = map.physical_to_virtual(StrideCoords::new(1, 5)).unwrap();
+    /// assert_eq!(map.virtual_to_physical(coords), Some(StrideCoords::new(1, 5)));
+    /// assert_eq!(map.physical_to_virtual(StrideCoords::new(1, 2)), None);
+    /// ```
+    pub fn physical_to_virtual(&self, coords: StrideCoords) -> Option<VirtualCoordinates> {
+        StrideLayout::physical_to_virtual(self, coords)
+    }
+}
+
+/// Converts a rectangle of stride coordinates into the [Virtual] rectangle that selects it within a display of the given size
+fn physical_rect_within(size: &Rectangle<StrideSpace>, rect: &Rectangle<StrideSpace>) -> Rectangle<Virtual> {
+    let (left, right) = virtual_span(rect.left(), rect.right(), size.left(), size.width());
+    let (top, bottom) = virtual_span(rect.top(), rect.bottom(), size.top(), size.height());
+    Rectangle::new_from_coordinates(left, top, right, bottom)
+}
+
+impl<const STRIDE_NUM: usize> PhysicalMapping<Virtual> for StrideMapping<STRIDE_NUM> {
+    type Physical = StrideSpace;
+
+    /// Converts a rectangle of stride coordinates into a [Virtual] rectangle. Mappings that are more than 256 pixels across can't select every pixel exactly, in which case the nearest pixels are selected.
+    fn physical_rect(&self, rect: &Rectangle<StrideSpace>) -> Rectangle<Virtual> {
+        physical_rect_within(&self.size, rect)
+    }
+}
+
 /// A [CoordinateSpace] where Y means which segment along a strip of LEDs, and X is which pixel within that segment
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct StrideSpace {}
 impl CoordinateSpace for StrideSpace {
     type Data = usize;
@@ -101,59 +524,60 @@ impl CoordinateSpace for StrideSpace {
 pub type StrideCoords = Coordinates<StrideSpace>;
 
 /// A [CoordinateView] that maps [Virtual] coordinates to stride based coordinates
+///
+/// Any other [VirtualSpace] can be used instead, such as [Virtual16] for displays that have more than 256 pixels along an axis.
 #[derive(Debug)]
-pub struct StrideView<'a, P, PB: IndexMut<usize, Output = P>> {
-    map: &'a StrideMapping,
+pub struct StrideView<'a, P, PB: IndexMut<usize, Output = P>, V: VirtualSpace = Virtual, M: StrideLayout = StrideMapping> {
+    map: &'a M,
     range: Rectangle<StrideSpace>,
     cur: StrideCoords,
     pixbuf: &'a mut PB,
+    _space: PhantomData<V>
 }
 
-impl<'a, P, PB: IndexMut<usize, Output = P>> StrideView<'a, P, PB> {
-
+impl<'a, P, PB: IndexMut<usize, Output = P>, V: VirtualSpace, M: StrideLayout> StrideView<'a, P, PB, V, M> {
     /// Returns the actual range of physical pixels that are selected for iteration
     pub fn range(&self) -> Rectangle<StrideSpace> {
         self.range
     }
 
     /// Creates a new sampler that uses a [StrideMapping] to map 2d virtual coordinates to a 1d linear strip of pixels
-    pub fn new(pixbuf: &'a mut PB, map: &'a StrideMapping, rect: &Rectangle<Virtual>) -> Self {
-        // Zero-index shape of the pixel picking area
-        let range: Rectangle<StrideSpace> = Rectangle::new(
+    pub fn new(pixbuf: &'a mut PB, map: &'a M, rect: &Rectangle<V>) -> Self {
+        let size = map.size();
+        // Zero-index shape of the pixel picking area, which can't extend past the edges of the mapping
+        let requested: Rectangle<StrideSpace> = Rectangle::new(
             Coordinates::new(
-                map.size.width() * Fract8::from_raw(rect.top_left.x) + map.size.left(),
-                map.size.height() * Fract8::from_raw(rect.top_left.y) + map.size.top()
+                V::to_physical(rect.top_left.x, size.width()) + size.left(),
+                V::to_physical(rect.top_left.y, size.height()) + size.top()
             ),
             Coordinates::new(
-                map.size.width() * Fract8::from_raw(rect.bottom_right.x) + map.size.left(),
-                map.size.height() * Fract8::from_raw(rect.bottom_right.y) + map.size.top()
+                V::to_physical(rect.bottom_right.x, size.width()) + size.left(),
+                V::to_physical(rect.bottom_right.y, size.height()) + size.top()
             )
         );
-        debug_assert!(
-            range.bottom_right.x <= map.size.width() &&
-            range.bottom_right.y <= map.size.height(),
-            "the range for this view is out of bounds range={:?} rect={:?}, map_size={:?}",
-            range,
-            rect,
-            (map.size.width(), map.size.height())
-        );
+        // Virtual coordinates always scale to somewhere within the mapping, so this only misses it when the rect is inside out, which selects nothing either way
+        let range = requested.clamp_to(&size).unwrap_or(requested);
         Self {
             map,
             range,
             cur: range.top_left,
-            pixbuf
+            pixbuf,
+            _space: PhantomData
         }
     }
 }
 
-impl<'a, P: 'a, PB: IndexMut<usize, Output = P>> Iterator for StrideView<'a, P, PB> {
-    type Item = (VirtualCoordinates, &'a mut P);
+impl<'a, P: 'a, PB: IndexMut<usize, Output = P>, V: VirtualSpace, M: StrideLayout> StrideView<'a, P, PB, V, M> {
+    /// Turns this view into an iterator that also yields the index of each pixel in the pixel buffer, which is where the mapping placed it along the strip
+    pub fn indexed(mut self) -> impl Iterator<Item = (Coordinates<V>, usize, &'a mut P)> {
+        core::iter::from_fn(move || self.next_indexed())
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        // Keep scanning until we reach the far right of the range
-        while self.range.height() > 0 && self.cur.x <= self.range.bottom_right.x {
-            //debug_assert!((self.cur.x as usize) < self.map.strides.len(), "stride out of bounds {:?}", self);
-            let cur_stride: &Stride = &self.map.strides[self.cur.x];
+    fn next_indexed(&mut self) -> Option<(Coordinates<V>, usize, &'a mut P)> {
+        let strides = self.map.strides();
+        // Keep scanning until we reach the far right of the range, or run out of strides. Sparse layouts can have a range that is wider than the number of strides.
+        while self.cur.x <= self.range.bottom_right.x && self.cur.x < strides.len() {
+            let cur_stride: &Stride = &strides[self.cur.x];
 
             // Skip ahead to the top of the current stride if we are starting from higher above.
             if self.cur.y < cur_stride.y {
@@ -161,7 +585,7 @@ impl<'a, P: 'a, PB: IndexMut<usize, Output = P>> Iterator for StrideView<'a, P,
             }
 
             // If we are past the bottom of our selection rectangle, or our current stride, go to the next stride.
-            if self.cur.y > self.range.bottom_right.y + 1 || self.cur.y > cur_stride.y + cur_stride.length {
+            if self.cur.y > self.range.bottom_right.y || self.cur.y >= cur_stride.y + cur_stride.length {
                 self.cur.x += 1;
                 // Reset our y position to the top of the rectangle; if the rectangle is higher than the y of the next stride, this is fixed at the top of the loop
                 self.cur.y = self.range.top_left.y;
@@ -180,27 +604,161 @@ impl<'a, P: 'a, PB: IndexMut<usize, Output = P>> Iterator for StrideView<'a, P,
                 physical_coords.y.saturating_mul(self.step_size.y)
             );*/
 
-            let x_pct = (physical_coords.x - self.range.left()) as f32 / (self.range.width() + 1) as f32;
-            let y_pct = (physical_coords.y - self.range.top()) as f32 / (self.range.height() + 1) as f32;
-
-            let virtual_coords = VirtualCoordinates::new(
-                (255f32 * x_pct) as u8,
-                (255f32 * y_pct) as u8
+            // Both edges of the range are inclusive, so the last row and column land on the largest virtual coordinate
+            let virtual_coords = Coordinates::new(
+                V::to_virtual(physical_coords.x - self.range.left(), self.range.width()),
+                V::to_virtual(physical_coords.y - self.range.top(), self.range.height())
             );
 
-            let idx = self.map.strides[physical_coords.x].pixel_idx_for_offset(physical_coords.y);
+            // Skip over any offsets that fall outside of the stride, instead of wrapping around to a far away pixel
+            let Some(idx) = strides[physical_coords.x].checked_pixel_idx_for_offset(physical_coords.y) else {
+                continue;
+            };
 
             let entry = unsafe {
                 &mut *(&mut self.pixbuf[idx] as *mut P)
             };
 
-            return Some((virtual_coords, entry));
+            return Some((virtual_coords, idx, entry));
         }
 
         None
     }
 }
 
+impl<'a, P: 'a, PB: IndexMut<usize, Output = P>, V: VirtualSpace, M: StrideLayout> Iterator for StrideView<'a, P, PB, V, M> {
+    type Item = (Coordinates<V>, &'a mut P);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_indexed().map(|(coords, _, pixel)| (coords, pixel))
+    }
+}
+
+impl<'a, P: 'a, PB: IndexMut<usize, Output = P>, V: VirtualSpace, M: StrideLayout> FusedIterator for StrideView<'a, P, PB, V, M> {}
+
+/// A [Sample] implementation that uses a [StrideMapping] to map 2d [Virtual] coordinates onto a 1d pixel buffer
+///
+/// Any other [VirtualSpace] can be sampled instead by creating the sampler with [StrideSampler::in_space], such as [Virtual16] which has enough resolution to select every pixel of a very long strip.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::mappings::{StrideMapping, StrideSampler};
+/// use rgb::Rgb;
+///
+/// // Two 8 pixel strips, wired in a zig-zag
+/// let map: StrideMapping = StrideMapping::from_json(&[(0, 0, 8, false), (1, 0, 8, true)]);
+/// let mut pixbuf = [Rgb::new(0u8, 0, 0); 16];
+/// let mut sampler = StrideSampler::new(&mut pixbuf, &map);
+/// for (coords, pixel) in sampler.sample(&Rectangle::everything()) {
+///     *pixel = Rgb::new(coords.x, coords.y, 0);
+/// }
+/// ```
+///
+/// Any [StrideLayout] can be sampled, such as a [DynStrideMapping] that was loaded at runtime.
+#[derive(Debug)]
+pub struct StrideSampler<'a, PB, V: VirtualSpace = Virtual, M: StrideLayout = StrideMapping> {
+    pixbuf: &'a mut PB,
+    map: &'a M,
+    _space: PhantomData<V>
+}
+
+impl<'a, PB> StrideSampler<'a, PB> {
+    /// Creates a new sampler over a pixel buffer using the given [StrideMapping]
+    pub fn new(pixbuf: &'a mut PB, map: &'a StrideMapping) -> Self {
+        Self::in_space(pixbuf, map)
+    }
+}
+
+impl<'a, PB, M: StrideLayout> StrideSampler<'a, PB, Virtual, M> {
+    /// Creates a new sampler over a pixel buffer using any [StrideLayout], such as a [DynStrideMapping] or a [StrideMapping] with a different number of strides
+    pub fn with_layout(pixbuf: &'a mut PB, map: &'a M) -> Self {
+        Self::in_space(pixbuf, map)
+    }
+}
+
+impl<'a, PB, V: VirtualSpace, M: StrideLayout> StrideSampler<'a, PB, V, M> {
+    /// Creates a new sampler over a pixel buffer that is addressed with coordinates from any [VirtualSpace]
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    /// use figments::mappings::{StrideMapping, StrideSampler};
+    ///
+    /// // A single strip that is far too long for every pixel to get its own 8 bit coordinate
+    /// let map: StrideMapping = StrideMapping::from_json(&[(0, 0, 1000, false)]);
+    /// let mut pixbuf = [0u8; 1000];
+    /// let mut sampler = StrideSampler::<_, Virtual16>::in_space(&mut pixbuf, &map);
+    /// let single = Rectangle::single(Virtual16Coordinates::new(0, 30000));
+    /// assert_eq!(sampler.sample(&single).count(), 1);
+    /// ```
+    pub fn in_space(pixbuf: &'a mut PB, map: &'a M) -> Self {
+        Self {
+            pixbuf,
+            map,
+            _space: PhantomData
+        }
+    }
+
+    /// Borrows the sampler to iterate over every pixel within a [Rectangle]
+    ///
+    /// Unlike [Sample::sample], the pixels are only borrowed for as long as the sampler is.
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    /// use figments::mappings::{StrideMapping, StrideSampler};
+    /// use rgb::Rgb;
+    ///
+    /// let map: StrideMapping = StrideMapping::from_json(&[(0, 0, 8, false), (1, 0, 8, true)]);
+    /// let mut pixbuf = [Rgb::new(0u8, 0, 0); 16];
+    /// let mut sampler = StrideSampler::new(&mut pixbuf, &map);
+    /// for (coords, pixel) in sampler.region(&Rectangle::everything()) {
+    ///     pixel.r = 255;
+    /// }
+    ///
+    /// // The sampler itself can also be iterated over to visit every pixel
+    /// for (coords, pixel) in &mut sampler {
+    ///     pixel.g = 255;
+    /// }
+    /// ```
+    pub fn region<P>(&mut self, rect: &Rectangle<V>) -> StrideView<'_, P, PB, V, M> where PB: IndexMut<usize, Output = P> {
+        StrideView::new(self.pixbuf, self.map, rect)
+    }
+}
+
+impl<'s, 'a, P: 's, PB: IndexMut<usize, Output = P>, V: VirtualSpace, M: StrideLayout> IntoIterator for &'s mut StrideSampler<'a, PB, V, M> {
+    type Item = (Coordinates<V>, &'s mut P);
+    type IntoIter = StrideView<'s, P, PB, V, M>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.region(&Rectangle::everything())
+    }
+}
+
+impl<'a, P: 'a, PB: IndexMut<usize, Output = P>, V: VirtualSpace, M: StrideLayout> Sample<'a, V> for StrideSampler<'a, PB, V, M> {
+    type Output = P;
+
+    fn sample(&mut self, rect: &Rectangle<V>) -> impl Iterator<Item = (Coordinates<V>, &'a mut Self::Output)> {
+        // Trick the borrow checker, until we can rewrite the sample trait to use a lifetime generic parameter
+        let bufref = unsafe {
+            (self.pixbuf as *mut PB).as_mut().unwrap()
+        };
+        StrideView::new(bufref, self.map, rect)
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.map.pixel_count()
+    }
+}
+
+impl<'a, P: 'a, PB: IndexMut<usize, Output = P>, V: VirtualSpace, M: StrideLayout> SampleIndexed<'a, V> for StrideSampler<'a, PB, V, M> {
+    fn sample_indexed(&mut self, rect: &Rectangle<V>) -> impl Iterator<Item = (Coordinates<V>, usize, &'a mut Self::Output)> {
+        // Trick the borrow checker, until we can rewrite the sample trait to use a lifetime generic parameter
+        let bufref = unsafe {
+            (self.pixbuf as *mut PB).as_mut().unwrap()
+        };
+        StrideView::new(bufref, self.map, rect).indexed()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use rgb::Rgb;
@@ -216,7 +774,7 @@ mod test {
         let mut sampler = StrideSampler::new(&mut pixbuf, &map);
         let mut num_sampled = 0;
 
-        // Sample all pixels. Since the default stride mapping is a 255px long strip, this should always have x = 0, and y = physical index
+        // Sample all pixels. Since the default stride mapping is a 256px long strip, this should always have x = 0, and y = physical index
         for (coords, pix) in sampler.sample(&Rectangle::everything()) {
             *pix = Rgb::new(pix.r, coords.x as u8, coords.y as u8);
             num_sampled += 1;
@@ -229,4 +787,360 @@ mod test {
             assert_eq!(pix, &Rgb::new(idx as u8, 0, idx as u8), "Pixel {idx} of {PIXEL_COUNT} has incorrect color {pix:?} while sampling everything: {pixbuf:?}");
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_long_stride_sample() {
+        // Strides longer than 255 pixels would overflow if any of the stride math was done with u8
+        const STRIDE_LENGTH: usize = 300;
+        const PIXEL_COUNT: usize = STRIDE_LENGTH * 2;
+        let mut pixbuf = [0u8; PIXEL_COUNT];
+        let map = StrideMapping::from_json(&[
+            (0, 0, STRIDE_LENGTH, false),
+            (1, 0, STRIDE_LENGTH, true)
+        ]);
+        assert_eq!(map.pixel_count, PIXEL_COUNT);
+        assert_eq!(map.size.height(), STRIDE_LENGTH - 1);
+
+        let mut sampler = StrideSampler::new(&mut pixbuf, &map);
+        for (_, pix) in sampler.sample(&Rectangle::everything()) {
+            *pix = 1;
+        }
+
+        assert!(pixbuf.iter().all(|pix| *pix == 1), "Every pixel should have been sampled: {pixbuf:?}");
+    }
+
+    #[test]
+    fn test_sparse_layout_sample() {
+        // The second stride is placed much further to the right than the number of strides, or even STRIDE_NUM
+        let map = StrideMapping::from_json(&[
+            (0, 0, 4, false),
+            (100, 0, 4, true)
+        ]);
+        assert!(map.size.width() >= map.strides.len());
+
+        let mut pixbuf = [0u8; 8];
+        let mut sampler = StrideSampler::new(&mut pixbuf, &map);
+        let mut view = sampler.region(&Rectangle::everything());
+        for (_, pix) in &mut view {
+            *pix = 1;
+        }
+        assert!(view.next().is_none(), "The view should stay finished once it runs out of strides");
+
+        assert!(pixbuf.iter().all(|pix| *pix == 1), "Every pixel should have been sampled: {pixbuf:?}");
+    }
+
+    #[test]
+    fn test_physical_rect_selects_exact_strides() {
+        let map = StrideMapping::from_json(&[
+            (0, 0, 8, false),
+            (1, 0, 8, true),
+            (2, 0, 8, false),
+            (3, 0, 8, true),
+            (4, 0, 8, false)
+        ]);
+
+        // Every possible physical rectangle should survive a round trip through virtual space
+        for left in 0..5 {
+            for right in left..5 {
+                for top in 0..8 {
+                    for bottom in top..8 {
+                        let physical = Rectangle::new_from_coordinates(left, top, right, bottom);
+                        let mut pixbuf = [0u8; 40];
+                        let mut sampler = StrideSampler::new(&mut pixbuf, &map);
+                        let view = sampler.region(&map.physical_rect(&physical));
+                        assert_eq!(view.range(), physical, "{physical:?} was converted to {:?}", map.physical_rect(&physical));
+                    }
+                }
+            }
+        }
+
+        // A status indicator on the third stride only touches that stride's pixels
+        let mut pixbuf = [0u8; 40];
+        let mut sampler = StrideSampler::new(&mut pixbuf, &map);
+        for (_, pix) in sampler.sample(&map.physical_rect(&Rectangle::new_from_coordinates(2, 0, 2, 7))) {
+            *pix = 1;
+        }
+        assert!(pixbuf[16..24].iter().all(|pix| *pix == 1), "The whole stride should be selected: {pixbuf:?}");
+        assert!(pixbuf[..16].iter().all(|pix| *pix == 0), "No earlier strides should be selected: {pixbuf:?}");
+    }
+
+    #[test]
+    fn test_rect_edges_are_inclusive() {
+        let map = StrideMapping::from_json(&[
+            (0, 0, 8, false),
+            (1, 0, 8, true),
+            (2, 0, 8, false)
+        ]);
+        let mut pixbuf = [0u8; 24];
+        let mut sampler = StrideSampler::new(&mut pixbuf, &map);
+        assert_eq!(sampler.sample(&Rectangle::everything()).count(), 24);
+        assert_eq!(sampler.sample(&Rectangle::single(VirtualCoordinates::top_left())).count(), 1);
+        assert_eq!(sampler.sample(&Rectangle::single(VirtualCoordinates::new(255, 255))).count(), 1);
+
+        // Every pixel should be visited exactly once, without spilling into the next stride
+        for (_, pix) in sampler.sample(&Rectangle::everything()) {
+            *pix += 1;
+        }
+        assert!(pixbuf.iter().all(|pix| *pix == 1), "Every pixel should have been sampled once: {pixbuf:?}");
+    }
+
+    #[test]
+    fn test_virtual_physical_round_trip() {
+        let map: StrideMapping = StrideMapping::from_json(&[
+            (0, 0, 8, false),
+            (1, 2, 6, true),
+            (2, 0, 3, false)
+        ]);
+
+        // Every physical pixel should survive a round trip through virtual space
+        for (x, stride) in map.strides().iter().enumerate() {
+            for y in stride.y..stride.y + stride.length {
+                let coords = StrideCoords::new(x, y);
+                let virt = map.physical_to_virtual(coords).unwrap();
+                assert_eq!(map.virtual_to_physical(virt), Some(coords), "Stride coordinate ({x}, {y}) did not round trip through {virt:?}");
+            }
+        }
+
+        assert_eq!(map.virtual_to_physical(VirtualCoordinates::new(0, 0)), Some(StrideCoords::new(0, 0)));
+        assert_eq!(map.virtual_to_physical(VirtualCoordinates::new(128, 128)), Some(StrideCoords::new(1, 3)));
+        assert_eq!(map.virtual_to_physical(VirtualCoordinates::new(255, 64)), Some(StrideCoords::new(2, 1)));
+    }
+
+    #[test]
+    fn test_virtual_gaps_are_none() {
+        let map: StrideMapping = StrideMapping::from_json(&[
+            (0, 0, 8, false),
+            (1, 2, 6, true),
+            (2, 0, 3, false)
+        ]);
+
+        assert_eq!(map.virtual_to_physical(VirtualCoordinates::new(128, 0)), None, "The top of the middle stride is a gap");
+        assert_eq!(map.virtual_to_physical(VirtualCoordinates::new(255, 255)), None, "The bottom of the short stride is a gap");
+        assert_eq!(map.physical_to_virtual(StrideCoords::new(1, 1)), None);
+        assert_eq!(map.physical_to_virtual(StrideCoords::new(2, 3)), None);
+        assert_eq!(map.physical_to_virtual(StrideCoords::new(3, 0)), None, "Coordinates past the last stride have no pixel");
+    }
+
+    #[test]
+    fn test_sampled_indexes_match_physical_position() {
+        let map: StrideMapping = StrideMapping::default();
+        let mut pixbuf: [usize; 256] = core::array::from_fn(|idx| idx);
+        let mut sampler = StrideSampler::new(&mut pixbuf, &map);
+        let mut count = 0;
+        for (_, idx, pixel) in sampler.sample_indexed(&Rectangle::everything()) {
+            assert_eq!(idx, *pixel, "The index should be the physical position of the pixel");
+            count += 1;
+        }
+        assert_eq!(count, map.pixel_count);
+
+        // Reversed strides count down from the bottom, while still reporting the physical position
+        let map: StrideMapping = StrideMapping::from_json(&[
+            (0, 0, 4, false),
+            (1, 0, 4, true)
+        ]);
+        let mut pixbuf: [usize; 8] = core::array::from_fn(|idx| idx);
+        let mut sampler = StrideSampler::new(&mut pixbuf, &map);
+        let everything = Rectangle::everything();
+        let indexes: [(usize, usize); 8] = {
+            let mut indexed = sampler.sample_indexed(&everything).map(|(_, idx, pixel)| (idx, *pixel));
+            core::array::from_fn(|_| indexed.next().unwrap())
+        };
+        assert_eq!(indexes.map(|(idx, _)| idx), [0, 1, 2, 3, 7, 6, 5, 4]);
+        assert!(indexes.iter().all(|(idx, pixel)| idx == pixel));
+
+        let mut strip = [0u8; 4];
+        assert!(strip.sample_indexed(&Rectangle::new_from_coordinates(1, 0, 2, 0)).map(|(coords, idx, _)| (coords.x, idx)).eq([(1, 1), (2, 2)]), "Linear strips should use their X coordinate as the index");
+    }
+
+    #[test]
+    fn test_checked_offsets_stay_within_stride() {
+        let map: StrideMapping = StrideMapping::from_json(&[
+            (0, 0, 8, false),
+            (1, 2, 6, true),
+            (2, 3, 4, false)
+        ]);
+        let reversed = &map.strides()[1];
+        assert_eq!(reversed.checked_pixel_idx_for_offset(2), Some(13), "The top of a reversed stride is its last pixel");
+        assert_eq!(reversed.checked_pixel_idx_for_offset(7), Some(8), "The bottom of a reversed stride is its first pixel");
+        assert_eq!(reversed.checked_pixel_idx_for_offset(8), None, "Offsets past the end of a reversed stride should not wrap around");
+        assert_eq!(reversed.checked_pixel_idx_for_offset(usize::MAX), None);
+        assert_eq!(reversed.checked_pixel_idx_for_offset(1), None, "Offsets above a stride should not be mapped");
+
+        let forward = &map.strides()[2];
+        assert_eq!(forward.checked_pixel_idx_for_offset(3), Some(14), "The top of a forward stride is its first pixel");
+        assert_eq!(forward.checked_pixel_idx_for_offset(6), Some(17));
+        assert_eq!(forward.checked_pixel_idx_for_offset(7), None);
+        assert_eq!(forward.checked_pixel_idx_for_offset(0), None);
+    }
+
+    #[test]
+    fn test_forward_stride_below_top_does_not_overrun_into_next_stride() {
+        // Forward strides that started below the top of the display used to be offset by their y twice, so their lower pixels landed on the strides wired after them
+        let map: StrideMapping = StrideMapping::from_json(&[
+            (0, 2, 4, false),
+            (1, 0, 6, false)
+        ]);
+        let mut pixbuf = [0u8; 10];
+        for (coords, pixel) in StrideSampler::new(&mut pixbuf, &map).sample(&Rectangle::new_from_coordinates(0, 0, 0, 255)) {
+            *pixel = coords.y.saturating_add(1);
+        }
+        assert!(pixbuf[4..].iter().all(|pixel| *pixel == 0), "Drawing the first stride should not touch the second one, but got {pixbuf:?}");
+        assert!(pixbuf[..4].iter().all(|pixel| *pixel > 0), "Every pixel of the first stride should be drawn, but got {pixbuf:?}");
+    }
+
+    #[test]
+    fn test_bounds_cover_virtual_space() {
+        let map: StrideMapping = StrideMapping::from_json(&[(0, 0, 8, false), (1, 2, 6, true)]);
+        let mut pixbuf = [Rgb::new(0u8, 0, 0); 14];
+        let sampler = StrideSampler::new(&mut pixbuf, &map);
+        assert_eq!(sampler.pixel_count(), 14);
+        assert_eq!(sampler.bounds(), Rectangle::everything(), "A virtual sampler is always stretched across the whole space");
+    }
+
+    #[test]
+    fn test_virtual16_is_finer_on_long_strips() {
+        // A single strip that is much longer than the 256 steps of the 8 bit virtual space
+        let map: StrideMapping = StrideMapping::from_json(&[(0, 0, 1000, false)]);
+        let mut pixbuf = [0u16; 1000];
+
+        let mut sampler = StrideSampler::new(&mut pixbuf, &map);
+        for y in 0..=u8::MAX {
+            for (_, pixel) in sampler.region(&Rectangle::single(VirtualCoordinates::new(0, y))) {
+                *pixel = 1;
+            }
+        }
+        assert_eq!(pixbuf.iter().filter(|pixel| **pixel == 1).count(), 256, "Most pixels of a long strip can't be picked out with 8 bit coordinates");
+
+        let mut sampler = StrideSampler::<_, Virtual16>::in_space(&mut pixbuf, &map);
+        for y in 0..=u16::MAX {
+            for (_, pixel) in sampler.region(&Rectangle::single(Virtual16Coordinates::new(0, y))) {
+                *pixel = 2;
+            }
+        }
+
+        let mut previous = None;
+        for (coords, _) in sampler.region(&Rectangle::everything()) {
+            assert!(previous < Some(coords.y), "Every pixel should have its own 16 bit coordinate, but {coords:?} repeats");
+            previous = Some(coords.y);
+        }
+        assert_eq!(previous, Some(u16::MAX), "The last pixel should land on the end of the space");
+        assert!(pixbuf.iter().all(|pixel| *pixel == 2), "Every pixel should be reachable with 16 bit coordinates");
+    }
+
+    #[cfg(feature="alloc")]
+    #[test]
+    fn test_dyn_mapping_without_pixels() {
+        let empty = DynStrideMapping::from_json(&[]);
+        assert_eq!(empty.pixel_count, 0);
+        let mut pixbuf = [0u8; 4];
+        assert_eq!(StrideSampler::with_layout(&mut pixbuf, &empty).sample(&Rectangle::everything()).count(), 0, "An empty layout should not select any pixels");
+
+        // A stride without any pixels is skipped over, instead of stretching the display
+        let map = DynStrideMapping::from_json(&[(0, 0, 4, false), (1, 0, 0, false), (2, 2, 0, true)]);
+        assert_eq!(map.pixel_count, 4);
+        assert_eq!(map.size, Rectangle::new_from_coordinates(0, 0, 0, 3));
+        assert_eq!(StrideSampler::with_layout(&mut pixbuf, &map).sample(&Rectangle::everything()).count(), 4);
+        assert_eq!(DynStrideMapping::new_serpentine(4, 0).pixel_count, 0);
+    }
+
+    #[cfg(feature="alloc")]
+    #[test]
+    fn test_dyn_mapping_has_no_stride_limit() {
+        // More strides than the default StrideMapping can hold
+        const STRIDES: usize = 40;
+        const LENGTH: usize = 8;
+        let layout: [(usize, usize, usize, bool); STRIDES] = array::from_fn(|x| (x, 0, LENGTH, x % 2 == 1));
+        let map = DynStrideMapping::from_json(&layout);
+        assert_eq!(map.strides().len(), STRIDES);
+        assert_eq!(map.pixel_count, STRIDES * LENGTH);
+        assert_eq!(map.size, Rectangle::new_from_coordinates(0, 0, STRIDES - 1, LENGTH - 1));
+        assert_eq!(map, DynStrideMapping::new_serpentine(STRIDES, LENGTH));
+        let progressive: [(usize, usize, usize, bool); STRIDES] = array::from_fn(|x| (x, 0, LENGTH, false));
+        assert_eq!(DynStrideMapping::from_json(&progressive), DynStrideMapping::new_progressive(STRIDES, LENGTH));
+
+        let mut pixbuf = [0u8; STRIDES * LENGTH];
+        let mut sampler = StrideSampler::with_layout(&mut pixbuf, &map);
+        for (_, pix) in sampler.sample(&Rectangle::everything()) {
+            *pix += 1;
+        }
+        assert!(pixbuf.iter().all(|pix| *pix == 1), "Every pixel should have been sampled once: {pixbuf:?}");
+
+        // The dynamic mapping samples exactly like a fixed size mapping with the same layout
+        let fixed: StrideMapping<STRIDES> = StrideMapping::from_json(&layout);
+        let mut fixed_pixbuf = [VirtualCoordinates::top_left(); STRIDES * LENGTH];
+        let mut dyn_pixbuf = fixed_pixbuf;
+        for (coords, pix) in StrideSampler::with_layout(&mut fixed_pixbuf, &fixed).sample(&Rectangle::everything()) {
+            *pix = coords;
+        }
+        for (coords, pix) in StrideSampler::with_layout(&mut dyn_pixbuf, &map).sample(&Rectangle::everything()) {
+            *pix = coords;
+        }
+        assert_eq!(fixed_pixbuf, dyn_pixbuf);
+        assert_eq!(map.physical_rect(&Rectangle::new_from_coordinates(2, 0, 2, 7)), fixed.physical_rect(&Rectangle::new_from_coordinates(2, 0, 2, 7)));
+    }
+
+    #[cfg(feature="serde")]
+    #[test]
+    fn test_json_layout_parses_both_forms() {
+        let map: StrideMapping = StrideMapping::from_json_str(r#"[
+            [0, 0, 8, false],
+            {"x": 1, "y": 2, "length": 6, "reverse": true},
+            {"x": 2, "y": 0, "length": 8}
+        ]"#).unwrap();
+        let expected: StrideMapping = StrideMapping::from_json(&[(0, 0, 8, false), (1, 2, 6, true), (2, 0, 8, false)]);
+        assert_eq!(map.strides(), expected.strides());
+        assert_eq!(map.pixel_count, 22);
+        assert_eq!(map.size, expected.size);
+
+        let dynamic = DynStrideMapping::from_json_str("[[0, 0, 8, false], [1, 2, 6, true], [2, 0, 8, false]]").unwrap();
+        assert_eq!(dynamic.strides(), expected.strides());
+    }
+
+    #[cfg(feature="serde")]
+    #[test]
+    fn test_json_layout_too_large() {
+        let json = "[[0, 0, 4, false], [1, 0, 4, true], [2, 0, 4, false]]";
+        match StrideMapping::<2>::from_json_str(json) {
+            Err(StrideLayoutError::TooManyStrides { count: 3, capacity: 2 }) => (),
+            other => panic!("A layout with too many strides should be rejected, but got {other:?}")
+        }
+        assert!(StrideMapping::<3>::from_json_str(json).is_ok(), "A layout that exactly fits should be accepted");
+        assert_eq!(DynStrideMapping::from_json_str(json).unwrap().strides().len(), 3, "A dynamic mapping has no limit");
+    }
+
+    #[cfg(feature="serde")]
+    #[test]
+    fn test_json_layout_malformed() {
+        for json in ["", "[[0, 0, 8]]", "[[0, 0, 8, \"yes\"]]", "{\"x\": 0}", "[{\"x\": 0, \"y\": 0}]", "[[0, 0, -1, false]]", "[[0, 0, 8, false],]", "[[0, 0, 8, false]] x", "[[0, 0, 8, false] [1, 0, 8, false]]", "[{\"x]\": 0, \"y\": 0, \"length\": 8}"] {
+            assert!(matches!(StrideMapping::<4>::from_json_str(json), Err(StrideLayoutError::Malformed(_))), "{json:?} should not parse");
+        }
+        assert!(matches!(StrideMapping::<4>::from_json_str("[]"), Err(StrideLayoutError::Empty)));
+        assert!(matches!(StrideMapping::<4>::from_json_str("[[0, 0, 8, false], [1, 0, 0, false]]"), Err(StrideLayoutError::EmptyStride { index: 1 })));
+    }
+
+    #[test]
+    fn test_serpentine_snakes_through_columns() {
+        let map: StrideMapping = StrideMapping::new_serpentine(8, 8);
+        assert_eq!(map.pixel_count, 64);
+        assert_eq!(map.size, Rectangle::new_from_coordinates(0, 0, 7, 7));
+
+        let mut pixbuf: [usize; 64] = core::array::from_fn(|idx| idx);
+        let mut sampler = StrideSampler::new(&mut pixbuf, &map);
+        let mut order = [0; 64];
+        for (slot, (_, idx)) in order.iter_mut().zip(sampler.sample(&Rectangle::everything())) {
+            *slot = *idx;
+        }
+
+        // Each column is visited from top to bottom, so odd columns count backwards through the strip
+        let expected: [usize; 64] = array::from_fn(|step| {
+            let (x, y) = (step / 8, step % 8);
+            if x % 2 == 0 { x * 8 + y } else { x * 8 + 7 - y }
+        });
+        assert_eq!(order, expected);
+        assert_eq!(&order[6..10], &[6, 7, 15, 14], "The strip should turn around at the bottom of the first column");
+
+        let map: StrideMapping = StrideMapping::new_progressive(8, 8);
+        let mut sampler = StrideSampler::new(&mut pixbuf, &map);
+        assert!(sampler.sample(&Rectangle::everything()).map(|(_, idx)| *idx).eq(0..64), "Every column should run in the same direction");
+    }
+}
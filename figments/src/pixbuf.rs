@@ -0,0 +1,112 @@
+//! Containers for the pixel buffers that effects are rendered into
+use core::mem;
+
+use crate::geometry::*;
+use crate::render::Sample;
+
+/// A pair of pixel buffers, where effects are drawn into the back buffer while the front buffer is sent out to the hardware
+///
+/// Drawing never touches the front buffer, so a frame that is still being written out by another task can't be torn by the next frame. Once the back buffer is finished, [DoubleBuffered::swap] makes it the new front buffer.
+///
+/// After a swap, the back buffer holds the frame before the one that was just finished. Effects that only draw over part of the display should [clear](crate::render::Painter::clear) it first, or copy the front buffer back with [DoubleBuffered::swap_and_copy].
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::pixbuf::DoubleBuffered;
+///
+/// let mut pixbuf: DoubleBuffered<[Rgb<u8>; 4]> = DoubleBuffered::default();
+/// pixbuf.fill_solid(Rgb::new(255u8, 0, 0));
+/// assert_eq!(pixbuf.as_ref(), &[Rgb::new(0, 0, 0); 4]);
+/// pixbuf.swap();
+/// assert_eq!(pixbuf.as_ref(), &[Rgb::new(255, 0, 0); 4]);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct DoubleBuffered<P> {
+    front: P,
+    back: P
+}
+
+impl<P> DoubleBuffered<P> {
+    /// Creates a new pair of buffers, which starts out showing `front`
+    pub const fn new(front: P, back: P) -> Self {
+        Self {
+            front,
+            back
+        }
+    }
+
+    /// Returns the buffer that is ready to be sent out to the hardware
+    pub const fn front(&self) -> &P {
+        &self.front
+    }
+
+    /// Returns the buffer that is being drawn into
+    pub const fn back(&self) -> &P {
+        &self.back
+    }
+
+    /// Returns the buffer that is being drawn into, for drawing into it directly instead of through [Sample]
+    pub const fn back_mut(&mut self) -> &mut P {
+        &mut self.back
+    }
+
+    /// Commits the back buffer, making it the new front buffer
+    pub fn swap(&mut self) {
+        mem::swap(&mut self.front, &mut self.back);
+    }
+
+    /// Commits the back buffer like [DoubleBuffered::swap], then copies it into the new back buffer so the next frame starts from the one that was just finished
+    pub fn swap_and_copy(&mut self) where P: Clone {
+        self.swap();
+        self.back.clone_from(&self.front);
+    }
+}
+
+impl<'a, Space: CoordinateSpace, P: Sample<'a, Space>> Sample<'a, Space> for DoubleBuffered<P> {
+    type Output = P::Output;
+
+    fn sample(&mut self, rect: &Rectangle<Space>) -> impl Iterator<Item = (Coordinates<Space>, &'a mut Self::Output)> {
+        self.back.sample(rect)
+    }
+
+    fn bounds(&self) -> Rectangle<Space> {
+        self.back.bounds()
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.back.pixel_count()
+    }
+}
+
+impl<Pixel, P: AsRef<[Pixel]>> AsRef<[Pixel]> for DoubleBuffered<P> {
+    fn as_ref(&self) -> &[Pixel] {
+        self.front.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mappings::linear::LinearSpace;
+
+    #[test]
+    fn test_back_buffer_is_hidden_until_swap() {
+        let mut pixbuf = DoubleBuffered::new([0u8; 4], [0u8; 4]);
+        for (coords, pixel) in Sample::<LinearSpace>::sample(&mut pixbuf, &Rectangle::everything()) {
+            *pixel = coords.x as u8 + 1;
+        }
+        assert_eq!(AsRef::<[u8]>::as_ref(&pixbuf), &[0; 4], "Drawing should not touch the front buffer");
+        assert_eq!(pixbuf.back(), &[1, 2, 3, 4]);
+
+        pixbuf.swap();
+        assert_eq!(AsRef::<[u8]>::as_ref(&pixbuf), &[1, 2, 3, 4], "Swapping should show the finished frame");
+        assert_eq!(pixbuf.back(), &[0; 4], "The old front buffer should become the back buffer");
+
+        pixbuf.back_mut()[0] = 9;
+        assert_eq!(pixbuf.front(), &[1, 2, 3, 4], "Drawing the next frame should not touch the front buffer");
+
+        pixbuf.swap_and_copy();
+        assert_eq!(pixbuf.front(), &[9, 0, 0, 0]);
+        assert_eq!(pixbuf.back(), &[9, 0, 0, 0], "The finished frame should be copied into the back buffer");
+    }
+}
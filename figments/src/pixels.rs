@@ -1,11 +1,88 @@
+use core::cmp::min;
+use core::ops::{Add, BitOr, Mul, Sub};
+
 use rgb::{Rgb, Rgba, Bgr, Bgra, Grb};
 
-use crate::{liber8tion::interpolate::Fract8, prelude::Fract8Ops};
+use crate::{liber8tion::interpolate::{Fract8, Fract8Sub}, prelude::Fract8Ops};
+
+/// How the color of a surface is combined with the colors underneath it
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    /// The surface is drawn over what is underneath it
+    #[default]
+    Normal,
+    /// The surface is added to what is underneath it, saturating at full brightness, which is useful for glows and accumulating light
+    Add,
+    /// The surface is multiplied with what is underneath it, so it can only darken it, which is useful for masks
+    Multiply,
+    /// The inverse of [BlendMode::Multiply], so the surface can only brighten what is underneath it
+    Screen
+}
+
+impl BlendMode {
+    /// Combines a single color channel of a surface with the channel underneath it
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// assert_eq!(BlendMode::Normal.blend_channel(100, 200), 200);
+    /// assert_eq!(BlendMode::Add.blend_channel(100, 200), 255);
+    /// assert_eq!(BlendMode::Multiply.blend_channel(255, 200), 200);
+    /// assert_eq!(BlendMode::Screen.blend_channel(0, 200), 200);
+    /// ```
+    #[inline(always)]
+    pub const fn blend_channel(self, under: u8, over: u8) -> u8 {
+        match self {
+            BlendMode::Normal => over,
+            BlendMode::Add => under.saturating_add(over),
+            BlendMode::Multiply => (under as u16 * over as u16 / 255) as u8,
+            BlendMode::Screen => 255 - ((255 - under) as u16 * (255 - over) as u16 / 255) as u8
+        }
+    }
+}
 
 /// Types that can add the color of another pixel to itself
 pub trait AdditivePixelSink<Src> {
     /// Blend a given pixel as an overlay by a given percentage
     fn add(&mut self, pixel: Src, opacity: Fract8);
+
+    /// Combines a given pixel with this one using a [BlendMode], then blends the result as an overlay by a given percentage
+    ///
+    /// Sinks that can't mix colors, such as [Threshold], treat every mode like [BlendMode::Normal].
+    #[inline(always)]
+    fn blend(&mut self, pixel: Src, opacity: Fract8, _mode: BlendMode) {
+        self.add(pixel, opacity)
+    }
+}
+
+/// Types that can composite a pixel whose color channels have already been multiplied by its alpha
+///
+/// [AdditivePixelSink] uses straight alpha, where a pixel keeps its full color no matter how transparent it is. Prefer premultiplied alpha when a shader's colors are scaled by how much of the pixel they cover, such as antialiased edges, or when colors are averaged or interpolated with transparent pixels. Mixing straight alpha colors drags in the color of the transparent pixels, which shows up as a dark fringe around semi-transparent edges over bright backgrounds. Shaders that draw a solid color with a separate alpha should keep using straight alpha.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::liber8tion::interpolate::Fract8;
+/// use rgb::Rgba;
+///
+/// // Half of a red pixel, already scaled by its alpha
+/// let mut pixel = Rgb::new(0u8, 0, 255);
+/// pixel.add_premultiplied(Rgba::new(128u8, 0, 0, 128), Fract8::MAX);
+/// assert_eq!(pixel, Rgb::new(128, 0, 127));
+/// ```
+pub trait PremultipliedPixelSink<Src> {
+    /// Composites a premultiplied pixel over this one, scaled by a given percentage
+    fn add_premultiplied(&mut self, pixel: Src, opacity: Fract8);
+}
+
+/// Composites a single premultiplied channel over the channel underneath it, where the alpha has already been combined with the opacity
+#[inline(always)]
+const fn premultiplied_channel(under: u8, over: u8, alpha: u8) -> u8 {
+    let blended = over as u16 + under as u16 * (255 - alpha) as u16 / 255;
+    if blended > 255 {
+        255
+    } else {
+        blended as u8
+    }
 }
 
 macro_rules! rgb_pixel_sink {
@@ -19,6 +96,12 @@ macro_rules! rgb_pixel_sink {
                     _ => *self = self.blend8(Self { r: pixel.r, g: pixel.g, b: pixel.b }, opacity)
                 }
             }
+
+            #[inline(always)]
+            fn blend(&mut self, pixel: $src_pixel<u8>, opacity: Fract8, mode: BlendMode) {
+                let blended = Self { r: mode.blend_channel(self.r, pixel.r), g: mode.blend_channel(self.g, pixel.g), b: mode.blend_channel(self.b, pixel.b) };
+                self.add(blended, opacity)
+            }
         }
     };
 }
@@ -28,12 +111,32 @@ macro_rules! rgba_pixel_sink {
         impl AdditivePixelSink<$src_pixel<u8>> for $dest_pixel<u8> {
             #[inline(always)]
             fn add(&mut self, pixel: $src_pixel<u8>, opacity: Fract8) {
-                match opacity {
+                // The pixel's own alpha is combined with the opacity of the whole surface
+                match Fract8::from_raw(pixel.a * opacity) {
                     Fract8::MIN => (),
                     Fract8::MAX => *self = Self { r: pixel.r, g: pixel.g, b: pixel.b },
-                    _ => *self = self.blend8(Self { r: pixel.r, g: pixel.g, b: pixel.b }, Fract8::from_raw(pixel.a * opacity))
+                    alpha => *self = self.blend8(Self { r: pixel.r, g: pixel.g, b: pixel.b }, alpha)
                 }
             }
+
+            #[inline(always)]
+            fn blend(&mut self, pixel: $src_pixel<u8>, opacity: Fract8, mode: BlendMode) {
+                let blended = Self { r: mode.blend_channel(self.r, pixel.r), g: mode.blend_channel(self.g, pixel.g), b: mode.blend_channel(self.b, pixel.b) };
+                self.add(blended, Fract8::from_raw(pixel.a * opacity))
+            }
+        }
+
+        impl PremultipliedPixelSink<$src_pixel<u8>> for $dest_pixel<u8> {
+            #[inline(always)]
+            fn add_premultiplied(&mut self, pixel: $src_pixel<u8>, opacity: Fract8) {
+                // The color channels are scaled by the opacity the same way as the alpha, so they stay premultiplied
+                let alpha = pixel.a * opacity;
+                *self = Self {
+                    r: premultiplied_channel(self.r, pixel.r * opacity, alpha),
+                    g: premultiplied_channel(self.g, pixel.g * opacity, alpha),
+                    b: premultiplied_channel(self.b, pixel.b * opacity, alpha)
+                };
+            }
         }
     };
 }
@@ -54,4 +157,673 @@ rgb_pixel_sink!(Bgr Bgr);
 rgb_pixel_sink!(Bgr Rgb);
 rgb_pixel_sink!(Bgr Grb);
 rgba_pixel_sink!(Bgr Rgba);
-rgba_pixel_sink!(Bgr Bgra);
\ No newline at end of file
+rgba_pixel_sink!(Bgr Bgra);
+
+/// A pixel with a dedicated white channel, such as on SK6812 RGBW strips
+///
+/// Converting from `Rgb<u8>` moves the white that is common to every color channel onto the white channel.
+///
+/// ```
+/// use figments::prelude::*;
+///
+/// assert_eq!(Rgbw::from(Rgb::new(255u8, 128, 64)), Rgbw::new(191, 64, 0, 64));
+/// ```
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgbw<T> {
+    /// Red
+    pub r: T,
+    /// Green
+    pub g: T,
+    /// Blue
+    pub b: T,
+    /// White
+    pub w: T
+}
+
+impl<T> Rgbw<T> {
+    /// Creates a new pixel from each channel
+    pub const fn new(r: T, g: T, b: T, w: T) -> Self {
+        Self { r, g, b, w }
+    }
+}
+
+impl From<Rgb<u8>> for Rgbw<u8> {
+    fn from(value: Rgb<u8>) -> Self {
+        let white = min(value.r, min(value.g, value.b));
+        Self::new(value.r - white, value.g - white, value.b - white, white)
+    }
+}
+
+/// Mixes the white channel back into every color channel, for displaying an RGBW pixel on plain RGB hardware
+///
+/// ```
+/// use figments::prelude::*;
+///
+/// assert_eq!(Rgb::from(Rgbw::new(191u8, 64, 0, 64)), Rgb::new(255, 128, 64));
+/// ```
+impl From<Rgbw<u8>> for Rgb<u8> {
+    fn from(value: Rgbw<u8>) -> Self {
+        Rgb::new(value.r.saturating_add(value.w), value.g.saturating_add(value.w), value.b.saturating_add(value.w))
+    }
+}
+
+macro_rules! rgbw_pixel_sink {
+    ($src_pixel:ident) => {
+        impl AdditivePixelSink<$src_pixel<u8>> for Rgbw<u8> {
+            #[inline(always)]
+            fn add(&mut self, pixel: $src_pixel<u8>, opacity: Fract8) {
+                self.add(Rgbw::from(Rgb::new(pixel.r, pixel.g, pixel.b)), opacity)
+            }
+
+            #[inline(always)]
+            fn blend(&mut self, pixel: $src_pixel<u8>, opacity: Fract8, mode: BlendMode) {
+                self.blend(Rgbw::from(Rgb::new(pixel.r, pixel.g, pixel.b)), opacity, mode)
+            }
+        }
+    };
+}
+
+macro_rules! rgbaw_pixel_sink {
+    ($src_pixel:ident) => {
+        impl AdditivePixelSink<$src_pixel<u8>> for Rgbw<u8> {
+            #[inline(always)]
+            fn add(&mut self, pixel: $src_pixel<u8>, opacity: Fract8) {
+                // The pixel's own alpha is combined with the opacity of the whole surface
+                self.add(Rgbw::from(Rgb::new(pixel.r, pixel.g, pixel.b)), Fract8::from_raw(pixel.a * opacity))
+            }
+
+            #[inline(always)]
+            fn blend(&mut self, pixel: $src_pixel<u8>, opacity: Fract8, mode: BlendMode) {
+                self.blend(Rgbw::from(Rgb::new(pixel.r, pixel.g, pixel.b)), Fract8::from_raw(pixel.a * opacity), mode)
+            }
+        }
+    };
+}
+
+impl AdditivePixelSink<Rgbw<u8>> for Rgbw<u8> {
+    #[inline(always)]
+    fn add(&mut self, pixel: Rgbw<u8>, opacity: Fract8) {
+        match opacity {
+            Fract8::MIN => (),
+            Fract8::MAX => *self = pixel,
+            _ => *self = self.blend8(pixel, opacity)
+        }
+    }
+
+    #[inline(always)]
+    fn blend(&mut self, pixel: Rgbw<u8>, opacity: Fract8, mode: BlendMode) {
+        let blended = Rgbw::new(mode.blend_channel(self.r, pixel.r), mode.blend_channel(self.g, pixel.g), mode.blend_channel(self.b, pixel.b), mode.blend_channel(self.w, pixel.w));
+        self.add(blended, opacity)
+    }
+}
+
+rgbw_pixel_sink!(Rgb);
+rgbw_pixel_sink!(Grb);
+rgbw_pixel_sink!(Bgr);
+rgbaw_pixel_sink!(Rgba);
+rgbaw_pixel_sink!(Bgra);
+
+/// A single channel pixel for monochrome strips, such as warm white LEDs where only the brightness matters
+///
+/// Converting from `Rgb<u8>` uses the luminance of the color, so shaders that emit colors can still be drawn onto a monochrome pixbuf.
+///
+/// ```
+/// use figments::prelude::*;
+///
+/// assert_eq!(Gray::from(Rgb::new(255u8, 255, 255)), Gray::new(255));
+/// assert_eq!(Gray::from(Rgb::new(0u8, 255, 0)), Gray::new(182));
+/// ```
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gray<T> {
+    /// Brightness
+    pub v: T
+}
+
+impl<T> Gray<T> {
+    /// Creates a new pixel from its brightness
+    pub const fn new(v: T) -> Self {
+        Self { v }
+    }
+}
+
+impl From<Rgb<u8>> for Gray<u8> {
+    fn from(value: Rgb<u8>) -> Self {
+        Self::new(luminance(value.r, value.g, value.b))
+    }
+}
+
+/// Copies the brightness onto every color channel, for displaying a monochrome pixel on RGB hardware
+impl From<Gray<u8>> for Rgb<u8> {
+    fn from(value: Gray<u8>) -> Self {
+        Rgb::new(value.v, value.v, value.v)
+    }
+}
+
+macro_rules! gray_pixel_sink {
+    ($src_pixel:ident) => {
+        impl AdditivePixelSink<$src_pixel<u8>> for Gray<u8> {
+            #[inline(always)]
+            fn add(&mut self, pixel: $src_pixel<u8>, opacity: Fract8) {
+                self.add(Gray::new(luminance(pixel.r, pixel.g, pixel.b)), opacity)
+            }
+
+            #[inline(always)]
+            fn blend(&mut self, pixel: $src_pixel<u8>, opacity: Fract8, mode: BlendMode) {
+                self.blend(Gray::new(luminance(pixel.r, pixel.g, pixel.b)), opacity, mode)
+            }
+        }
+    };
+}
+
+macro_rules! graya_pixel_sink {
+    ($src_pixel:ident) => {
+        impl AdditivePixelSink<$src_pixel<u8>> for Gray<u8> {
+            #[inline(always)]
+            fn add(&mut self, pixel: $src_pixel<u8>, opacity: Fract8) {
+                // The pixel's own alpha is combined with the opacity of the whole surface
+                self.add(Gray::new(luminance(pixel.r, pixel.g, pixel.b)), Fract8::from_raw(pixel.a * opacity))
+            }
+
+            #[inline(always)]
+            fn blend(&mut self, pixel: $src_pixel<u8>, opacity: Fract8, mode: BlendMode) {
+                self.blend(Gray::new(luminance(pixel.r, pixel.g, pixel.b)), Fract8::from_raw(pixel.a * opacity), mode)
+            }
+        }
+    };
+}
+
+impl AdditivePixelSink<Gray<u8>> for Gray<u8> {
+    #[inline(always)]
+    fn add(&mut self, pixel: Gray<u8>, opacity: Fract8) {
+        match opacity {
+            Fract8::MIN => (),
+            Fract8::MAX => *self = pixel,
+            _ => *self = self.blend8(pixel, opacity)
+        }
+    }
+
+    #[inline(always)]
+    fn blend(&mut self, pixel: Gray<u8>, opacity: Fract8, mode: BlendMode) {
+        self.add(Gray::new(mode.blend_channel(self.v, pixel.v)), opacity)
+    }
+}
+
+gray_pixel_sink!(Rgb);
+gray_pixel_sink!(Grb);
+gray_pixel_sink!(Bgr);
+graya_pixel_sink!(Rgba);
+graya_pixel_sink!(Bgra);
+
+/// A wrapper around a pixel or a single color channel, which turns the [Fract8Ops] functions into operators so effects read more like math
+///
+/// * `px * fract` scales the brightness of the pixel
+/// * `a + b` adds two pixels, saturating at full brightness like [Fract8Ops::saturating_add]
+/// * `a - b` subtracts one pixel from another, stopping at black like [Fract8Sub::saturating_sub]
+/// * `a | (b, fract)` blends from one pixel towards another, like [Fract8Ops::blend8]
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::liber8tion::interpolate::Fract8;
+///
+/// let red = Px(Rgb::new(200u8, 0, 0));
+/// let blue = Px(Rgb::new(0u8, 0, 200));
+/// assert_eq!(red + blue, Px(Rgb::new(200, 0, 200)));
+/// assert_eq!(red + red, Px(Rgb::new(255, 0, 0)));
+/// assert_eq!((red + blue) - red, blue);
+/// assert_eq!(red | (blue, Fract8::MAX), blue);
+/// assert_eq!((red * Fract8::from_raw(128)).0, Rgb::new(200u8, 0, 0) * Fract8::from_raw(128));
+/// ```
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct Px<T = Rgb<u8>>(pub T);
+
+impl<T> Px<T> {
+    /// Unwraps the pixel
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Px<T> {
+    fn from(value: T) -> Self {
+        Px(value)
+    }
+}
+
+impl<T: Mul<Fract8, Output = T>> Mul<Fract8> for Px<T> {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Fract8) -> Self::Output {
+        Px(self.0 * rhs)
+    }
+}
+
+impl<T: Fract8Ops> Add for Px<T> {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Px(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl<T: Fract8Sub> Sub for Px<T> {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        Px(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl<T: Fract8Ops> BitOr<(Px<T>, Fract8)> for Px<T> {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, (other, scale): (Px<T>, Fract8)) -> Self::Output {
+        Px(self.0.blend8(other.0, scale))
+    }
+}
+
+/// A monochrome pixel that turns on when the luminance of the colors drawn onto it reaches `LEVEL`
+///
+/// This allows color shaders to be drawn onto on/off displays, with the threshold tuned for the contrast of the display.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::liber8tion::interpolate::Fract8;
+///
+/// let mut dim: Threshold = Default::default();
+/// dim.add(Rgb::new(100u8, 100, 100), Fract8::MAX);
+/// assert!(!dim.is_on());
+///
+/// let mut sensitive: Threshold<64> = Default::default();
+/// sensitive.add(Rgb::new(100u8, 100, 100), Fract8::MAX);
+/// assert!(sensitive.is_on());
+/// ```
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threshold<const LEVEL: u8 = 128>(pub bool);
+
+impl<const LEVEL: u8> Threshold<LEVEL> {
+    /// Returns true if the pixel is turned on
+    pub const fn is_on(&self) -> bool {
+        self.0
+    }
+
+    /// Blends the luminance of a color over this pixel, then turns it on or off
+    fn blend_luminance(&mut self, luminance: u8, opacity: Fract8) {
+        let current = if self.0 { u8::MAX } else { u8::MIN };
+        self.0 = current.blend8(luminance, opacity) >= LEVEL;
+    }
+}
+
+impl<const LEVEL: u8> From<Threshold<LEVEL>> for bool {
+    fn from(value: Threshold<LEVEL>) -> Self {
+        value.0
+    }
+}
+
+/// Calculates the perceived brightness of a color, using the Rec. 709 weights for each channel
+const fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u32 * 54 + g as u32 * 183 + b as u32 * 19) >> 8) as u8
+}
+
+impl<const LEVEL: u8> AdditivePixelSink<Rgb<u8>> for Threshold<LEVEL> {
+    fn add(&mut self, pixel: Rgb<u8>, opacity: Fract8) {
+        if opacity != Fract8::MIN {
+            self.blend_luminance(luminance(pixel.r, pixel.g, pixel.b), opacity);
+        }
+    }
+}
+
+impl<const LEVEL: u8> AdditivePixelSink<Rgba<u8>> for Threshold<LEVEL> {
+    fn add(&mut self, pixel: Rgba<u8>, opacity: Fract8) {
+        match Fract8::from_raw(pixel.a * opacity) {
+            Fract8::MIN => (),
+            alpha => self.blend_luminance(luminance(pixel.r, pixel.g, pixel.b), alpha)
+        }
+    }
+}
+
+/// Wide color types that can be converted back down to `Rgb<u8>` for output, such as after accumulating several additive layers in `Rgb<u16>` without clipping
+///
+/// ```
+/// use figments::prelude::*;
+///
+/// let wide: Rgb<u16> = Rgb::new(510, 255, 0);
+/// assert_eq!(wide.clip(), Rgb::new(255, 255, 0));
+/// assert_eq!(wide.scale_to_max(), Rgb::new(255, 128, 0));
+/// ```
+pub trait Tonemap {
+    /// Clamps each channel to 255 on its own, which turns over-range colors towards white
+    fn clip(self) -> Rgb<u8>;
+
+    /// Scales every channel down by the same amount so the brightest one fits, which keeps the hue intact
+    fn scale_to_max(self) -> Rgb<u8>;
+
+    /// Compresses each channel with the Reinhard operator `c / (1 + c)`, which never clips but darkens the whole range
+    fn reinhard(self) -> Rgb<u8>;
+}
+
+impl Tonemap for Rgb<u16> {
+    fn clip(self) -> Rgb<u8> {
+        let clip = |c: u16| c.min(u8::MAX as u16) as u8;
+        Rgb::new(clip(self.r), clip(self.g), clip(self.b))
+    }
+
+    fn scale_to_max(self) -> Rgb<u8> {
+        let max = self.r.max(self.g).max(self.b) as u32;
+        if max <= u8::MAX as u32 {
+            return Rgb::new(self.r as u8, self.g as u8, self.b as u8);
+        }
+        let scale = |c: u16| ((c as u32 * u8::MAX as u32 + max / 2) / max) as u8;
+        Rgb::new(scale(self.r), scale(self.g), scale(self.b))
+    }
+
+    fn reinhard(self) -> Rgb<u8> {
+        // With each channel measured in units of 255, c / (1 + c) is 255c / (255 + c)
+        let compress = |c: u16| (c as u32 * u8::MAX as u32 / (u8::MAX as u32 + c as u32)) as u8;
+        Rgb::new(compress(self.r), compress(self.g), compress(self.b))
+    }
+}
+
+/// Types that can summarize the colors within a buffer of pixels, such as for matching ambient lighting to the display
+pub trait ColorStats {
+    /// Returns the average color of every pixel
+    fn average_color(&self) -> Rgb<u8>;
+
+    /// Returns the hue that is most common across every pixel, where more colorful pixels are counted more heavily than dull ones. Shades of gray have no hue, so a buffer with no color at all reports a hue of zero.
+    fn dominant_hue(&self) -> u8;
+}
+
+/// Calculates the hue of a color along with its chroma, which is zero for shades of gray
+fn hue_and_chroma(pixel: Rgb<u8>) -> (u8, u8) {
+    let high = pixel.r.max(pixel.g).max(pixel.b);
+    let low = pixel.r.min(pixel.g).min(pixel.b);
+    let chroma = high - low;
+    if chroma == 0 {
+        return (0, 0);
+    }
+
+    // Each of the six sextants of the color wheel cover 1/6th of the 0-255 hue range
+    let (base, a, b) = if high == pixel.r {
+        (0, pixel.g, pixel.b)
+    } else if high == pixel.g {
+        (85, pixel.b, pixel.r)
+    } else {
+        (171, pixel.r, pixel.g)
+    };
+    let hue = base + (a as i32 - b as i32) * 43 / chroma as i32;
+    (hue.rem_euclid(256) as u8, chroma)
+}
+
+impl<P: Copy + Into<Rgb<u8>>> ColorStats for [P] {
+    fn average_color(&self) -> Rgb<u8> {
+        if self.is_empty() {
+            return Rgb::new(0, 0, 0);
+        }
+
+        let (r, g, b) = self.iter().fold((0u64, 0u64, 0u64), |(r, g, b), pixel| {
+            let rgb: Rgb<u8> = (*pixel).into();
+            (r + rgb.r as u64, g + rgb.g as u64, b + rgb.b as u64)
+        });
+        let count = self.len() as u64;
+        Rgb::new((r / count) as u8, (g / count) as u8, (b / count) as u8)
+    }
+
+    fn dominant_hue(&self) -> u8 {
+        const BUCKETS: usize = 32;
+        const BUCKET_SIZE: u8 = (256 / BUCKETS) as u8;
+
+        // Each bucket tracks the total chroma of its pixels, along with where in the bucket they sit so the result is not rounded to the bucket size
+        let mut weights = [0u64; BUCKETS];
+        let mut offsets = [0u64; BUCKETS];
+        for pixel in self {
+            let (hue, chroma) = hue_and_chroma((*pixel).into());
+            let bucket = (hue / BUCKET_SIZE) as usize;
+            weights[bucket] += chroma as u64;
+            offsets[bucket] += (hue % BUCKET_SIZE) as u64 * chroma as u64;
+        }
+
+        let (bucket, weight) = weights.iter().enumerate().fold((0, 0), |best, (idx, weight)| {
+            if *weight > best.1 {
+                (idx, *weight)
+            } else {
+                best
+            }
+        });
+
+        // With no weight at all, every pixel was gray
+        offsets[bucket].checked_div(weight).map_or(0, |offset| bucket as u8 * BUCKET_SIZE + offset as u8)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn composite<Dst: AdditivePixelSink<Src> + Default, Src>(pixel: Src, opacity: Fract8) -> Dst {
+        let mut output = Dst::default();
+        output.add(pixel, opacity);
+        output
+    }
+
+    #[test]
+    fn test_alpha_combines_with_opacity() {
+        let half = Fract8::from_raw(128);
+        let overlay = Rgba::new(255u8, 255, 255, 128);
+        // Half alpha at half opacity is a quarter of the way to the overlay
+        assert_eq!(composite::<Rgb<u8>, _>(overlay, half), Rgb::new(64, 64, 64));
+        assert_eq!(composite::<Grb<u8>, _>(overlay, half), Grb::new_grb(64, 64, 64));
+        assert_eq!(composite::<Bgr<u8>, _>(Bgra::new_bgra(255u8, 255, 255, 128), half), Bgr::new_bgr(64, 64, 64));
+
+        // A fully opaque surface still respects the pixel's alpha
+        assert_eq!(composite::<Rgb<u8>, _>(overlay, Fract8::MAX), Rgb::new(128, 128, 128));
+        assert_eq!(composite::<Rgb<u8>, _>(Rgba::new(255u8, 0, 0, 0), Fract8::MAX), Rgb::new(0, 0, 0));
+        assert_eq!(composite::<Rgb<u8>, _>(Rgba::new(255u8, 0, 0, 255), Fract8::MAX), Rgb::new(255, 0, 0));
+    }
+
+    #[test]
+    fn test_premultiplied_alpha() {
+        let blue = Rgb::new(0u8, 0, 255);
+        let premultiplied = |overlay: Rgba<u8>, opacity: Fract8| {
+            let mut output = blue;
+            output.add_premultiplied(overlay, opacity);
+            output
+        };
+        let straight = |overlay: Rgba<u8>| {
+            let mut output = blue;
+            AdditivePixelSink::add(&mut output, overlay, Fract8::MAX);
+            output
+        };
+
+        // The same half transparent red, written both ways, lands on the same color
+        assert_eq!(straight(Rgba::new(255, 0, 0, 128)), Rgb::new(128, 0, 127));
+        assert_eq!(premultiplied(Rgba::new(128, 0, 0, 128), Fract8::MAX), Rgb::new(128, 0, 127));
+
+        // Averaging opaque red with transparent black, such as along an antialiased edge, only works with premultiplied colors
+        let edge = Rgba::new(128, 0, 0, 128);
+        assert_eq!(straight(edge), Rgb::new(64, 0, 127), "Straight alpha should darken the edge");
+        assert_eq!(premultiplied(edge, Fract8::MAX), Rgb::new(128, 0, 127), "Premultiplied alpha should keep the edge at full brightness");
+
+        assert_eq!(premultiplied(Rgba::new(255, 0, 0, 255), Fract8::MAX), Rgb::new(255, 0, 0), "An opaque pixel should replace what is underneath it");
+        assert_eq!(premultiplied(Rgba::new(0, 0, 0, 0), Fract8::MAX), blue, "A transparent pixel should not change anything");
+        assert_eq!(premultiplied(Rgba::new(255, 0, 0, 255), Fract8::MIN), blue, "A transparent surface should not change anything");
+        assert_eq!(premultiplied(Rgba::new(255, 0, 0, 255), Fract8::from_raw(128)), straight(Rgba::new(255, 0, 0, 128)), "The opacity should scale the color and alpha together");
+        assert_eq!(premultiplied(Rgba::new(255, 255, 0, 0), Fract8::MAX), Rgb::new(255, 255, 255), "Additive light with no alpha should saturate instead of wrapping");
+    }
+
+    #[test]
+    fn test_blend_modes_respect_alpha() {
+        let under = Rgb::new(200u8, 100, 50);
+        let blend = |overlay: Rgba<u8>, mode: BlendMode| {
+            let mut output = under;
+            output.blend(overlay, Fract8::MAX, mode);
+            output
+        };
+        assert_eq!(blend(Rgba::new(100, 100, 100, 255), BlendMode::Add), Rgb::new(255, 200, 150));
+        assert_eq!(blend(Rgba::new(100, 100, 100, 0), BlendMode::Add), under, "A transparent pixel should not change anything in any mode");
+        assert_eq!(blend(Rgba::new(0, 0, 0, 128), BlendMode::Multiply), Rgb::new(100, 50, 25), "Multiplying by half transparent black should only darken by half");
+        assert_eq!(blend(Rgba::new(255, 255, 255, 255), BlendMode::Multiply), under, "Multiplying by white should not change anything");
+        assert_eq!(blend(Rgba::new(255, 255, 255, 255), BlendMode::Screen), Rgb::new(255, 255, 255));
+
+        let mut rgbw = Rgbw::new(100u8, 0, 0, 100);
+        rgbw.blend(Rgb::new(255u8, 255, 255), Fract8::MAX, BlendMode::Add);
+        assert_eq!(rgbw, Rgbw::new(100, 0, 0, 255), "White should be added onto the white channel");
+        for mode in [BlendMode::Normal, BlendMode::Add, BlendMode::Multiply, BlendMode::Screen] {
+            assert!(mode.blend_channel(0, 0) == 0 && mode.blend_channel(255, 255) == 255, "{mode:?} should keep black and white as they are");
+        }
+    }
+
+    #[test]
+    fn test_white_round_trips_through_rgbw() {
+        let white = Rgb::new(255u8, 255, 255);
+        assert_eq!(Rgbw::from(white), Rgbw::new(0, 0, 0, 255), "Pure white should only light the white channel");
+        assert_eq!(Rgb::from(Rgbw::from(white)), white);
+
+        for color in [Rgb::new(0u8, 0, 0), Rgb::new(255, 128, 64), Rgb::new(10, 200, 10), Rgb::new(0, 0, 255)] {
+            assert_eq!(Rgb::from(Rgbw::from(color)), color, "{color:?} should survive a round trip");
+        }
+    }
+
+    #[test]
+    fn test_rgbw_sink_blends_every_channel() {
+        let half = Fract8::from_raw(128);
+        assert_eq!(composite::<Rgbw<u8>, _>(Rgb::new(255u8, 255, 255), Fract8::MAX), Rgbw::new(0, 0, 0, 255));
+        assert_eq!(composite::<Rgbw<u8>, _>(Rgb::new(255u8, 128, 128), half), Rgbw::new(64, 0, 0, 64));
+        assert_eq!(composite::<Rgbw<u8>, _>(Rgba::new(255u8, 255, 255, 128), half), Rgbw::new(0, 0, 0, 64));
+        assert_eq!(composite::<Rgbw<u8>, _>(Rgbw::new(200u8, 100, 0, 50), Fract8::MIN), Rgbw::default());
+        assert_eq!(Rgbw::new(200u8, 100, 0, 50) * half, Rgbw::new(100, 50, 0, 25));
+    }
+
+    #[test]
+    fn test_gray_uses_luminance_weights() {
+        assert_eq!(Gray::from(Rgb::new(255u8, 0, 0)), Gray::new(53));
+        assert_eq!(Gray::from(Rgb::new(0u8, 255, 0)), Gray::new(182));
+        assert_eq!(Gray::from(Rgb::new(0u8, 0, 255)), Gray::new(18));
+        assert_eq!(Gray::from(Rgb::new(0u8, 0, 0)), Gray::new(0));
+        assert_eq!(Gray::from(Rgb::new(255u8, 255, 255)), Gray::new(255), "The weights should add up so that white is full brightness");
+        assert_eq!(Rgb::from(Gray::new(100u8)), Rgb::new(100, 100, 100));
+    }
+
+    #[test]
+    fn test_gray_sink_blends_luminance() {
+        let half = Fract8::from_raw(128);
+        assert_eq!(composite::<Gray<u8>, _>(Rgb::new(255u8, 255, 255), Fract8::MAX), Gray::new(255));
+        assert_eq!(composite::<Gray<u8>, _>(Rgb::new(255u8, 255, 255), half), Gray::new(128));
+        assert_eq!(composite::<Gray<u8>, _>(Grb::new_grb(255u8, 0, 0), Fract8::MAX), Gray::new(182), "Each source format should use the weight of its own green channel");
+        assert_eq!(composite::<Gray<u8>, _>(Rgba::new(255u8, 255, 255, 128), half), Gray::new(64));
+        assert_eq!(composite::<Gray<u8>, _>(Gray::new(200u8), Fract8::MIN), Gray::default());
+
+        let mut gray = Gray::new(100u8);
+        gray.blend(Rgb::new(255u8, 255, 255), Fract8::MAX, BlendMode::Multiply);
+        assert_eq!(gray, Gray::new(100), "Multiplying by white should not change anything");
+        gray.blend(Gray::new(100), Fract8::MAX, BlendMode::Add);
+        assert_eq!(gray, Gray::new(200));
+        assert_eq!(Gray::new(200u8) * half, Gray::new(100));
+    }
+
+    #[test]
+    fn test_raising_threshold_turns_on_fewer_pixels() {
+        let overlays: [Rgb<u8>; 256] = core::array::from_fn(|idx| Rgb::new(idx as u8, (idx as u8).wrapping_mul(7), 255 - idx as u8));
+        fn count_on<const LEVEL: u8>(overlays: &[Rgb<u8>]) -> usize {
+            overlays.iter().filter(|overlay| composite::<Threshold<LEVEL>, _>(**overlay, Fract8::MAX).is_on()).count()
+        }
+
+        let low = count_on::<64>(&overlays);
+        let medium = count_on::<128>(&overlays);
+        let high = count_on::<192>(&overlays);
+        assert!(low > medium && medium > high && high > 0, "Expected fewer pixels to turn on at higher thresholds: {low} {medium} {high}");
+
+        // White and black are always on and off, regardless of the threshold
+        assert!(composite::<Threshold<255>, _>(Rgb::new(255u8, 255, 255), Fract8::MAX).is_on());
+        assert!(!composite::<Threshold<1>, _>(Rgb::new(0u8, 0, 0), Fract8::MAX).is_on());
+        // A transparent overlay leaves the pixel alone
+        assert!(!composite::<Threshold<1>, _>(Rgba::new(255u8, 255, 255, 0), Fract8::MAX).is_on());
+    }
+
+    #[test]
+    fn test_tonemap_over_range() {
+        let over: Rgb<u16> = Rgb::new(1020, 510, 100);
+        assert_eq!(over.clip(), Rgb::new(255, 255, 100));
+        // The ratios between channels survive scaling, so orange stays orange
+        assert_eq!(over.scale_to_max(), Rgb::new(255, 128, 25));
+        let compressed = over.reinhard();
+        assert!(compressed.r > compressed.g && compressed.g > compressed.b, "Reinhard should keep the order of the channels: {compressed:?}");
+        assert_eq!(Rgb::new(u16::MAX, 0, 0).reinhard().r, 254);
+
+        // Colors that already fit are unchanged, apart from Reinhard which always compresses
+        let fits: Rgb<u16> = Rgb::new(255, 12, 0);
+        assert_eq!(fits.clip(), Rgb::new(255, 12, 0));
+        assert_eq!(fits.scale_to_max(), Rgb::new(255, 12, 0));
+        assert_eq!(fits.reinhard(), Rgb::new(127, 11, 0));
+    }
+
+    #[test]
+    fn test_pure_red_stats() {
+        let pixbuf = [Rgb::new(255u8, 0, 0); 16];
+        assert_eq!(pixbuf.average_color(), Rgb::new(255, 0, 0));
+        assert_eq!(pixbuf.dominant_hue(), 0);
+
+        // The same is true for other channel orders
+        let pixbuf = [Grb::new_grb(0u8, 255, 0); 16];
+        assert_eq!(pixbuf.average_color(), Rgb::new(255, 0, 0));
+        assert_eq!(pixbuf.dominant_hue(), 0);
+    }
+
+    #[test]
+    fn test_average_color() {
+        let pixbuf = [Rgb::new(200u8, 0, 10), Rgb::new(100, 50, 30)];
+        assert_eq!(pixbuf.average_color(), Rgb::new(150, 25, 20));
+        let empty: [Rgb<u8>; 0] = [];
+        assert_eq!(empty.average_color(), Rgb::new(0, 0, 0));
+    }
+
+    #[test]
+    fn test_dominant_hue() {
+        // Mostly blue, with a few bright red pixels and a lot of white
+        let mut pixbuf = [Rgb::new(255u8, 255, 255); 32];
+        pixbuf[..10].fill(Rgb::new(0, 0, 200));
+        pixbuf[10..13].fill(Rgb::new(255, 0, 0));
+        assert_eq!(pixbuf.dominant_hue(), 171);
+
+        let pixbuf = [Rgb::new(0u8, 255, 0), Rgb::new(0, 200, 0), Rgb::new(50, 50, 50)];
+        assert_eq!(pixbuf.dominant_hue(), 85);
+
+        // There is no hue to be found in grays
+        assert_eq!([Rgb::new(80u8, 80, 80); 4].dominant_hue(), 0);
+    }
+
+    #[test]
+    fn test_px_operators_delegate_to_fract8_ops() {
+        let a = Rgb::new(200u8, 100, 0);
+        let b = Rgb::new(100u8, 100, 255);
+        for raw in [0, 1, 64, 128, 255] {
+            let scale = Fract8::from_raw(raw);
+            assert_eq!((Px(a) * scale).0, a * scale);
+            assert_eq!((Px(a) | (Px(b), scale)).0, a.blend8(b, scale), "Blending by {raw} should match blend8");
+            assert_eq!((Px(raw) * scale).0, raw * scale, "Single channels should scale the same way");
+        }
+        assert_eq!((Px(a) + Px(b)).0, Fract8Ops::saturating_add(a, b));
+        assert_eq!((Px(200u8) + Px(100)).0, 255);
+    }
+
+    #[test]
+    fn test_px_arithmetic_saturates() {
+        let a = Px(Rgba::new(200u8, 100, 0, 255));
+        let b = Px(Rgba::new(100u8, 100, 255, 128));
+        assert_eq!(a + b, Px(Rgba::new(255, 200, 255, 255)), "Adding should stop at full brightness");
+        assert_eq!(a - b, Px(Rgba::new(100, 0, 0, 127)), "Subtracting should stop at black");
+        assert_eq!(b - a, Px(Rgba::new(0, 0, 255, 0)));
+        assert_eq!((a + b) - b, Px(Rgba::new(155, 100, 0, 127)), "Saturation loses whatever went past full brightness");
+
+        let white = Px(Rgb::new(255u8, 255, 255));
+        let gray = Px(Rgb::new(128u8, 128, 128));
+        assert_eq!(white + gray, white);
+        assert_eq!(gray - white, Px::default());
+        assert_eq!(white * Fract8::MIN, Px::default());
+        assert_eq!(white * Fract8::from_raw(128) - gray, Px::default());
+        assert_eq!((white - gray).into_inner(), Rgb::new(127, 127, 127));
+    }
+}
@@ -2,7 +2,7 @@ pub use crate::{
     geometry::*,
     render::*,
     pixels::*,
-    liber8tion::Hsv
+    liber8tion::{Hsv, DimValue}
 };
 
 #[cfg(feature="alloc")]
@@ -10,6 +10,6 @@ pub use crate::{
     surface::*
 };
 
-pub use crate::liber8tion::interpolate::Fract8Ops;
+pub use crate::liber8tion::interpolate::{Fract8Ops, Fract8Sub};
 
 pub use rgb::Rgb;
\ No newline at end of file
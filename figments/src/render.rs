@@ -1,6 +1,12 @@
 //! The core rendering engine types
 use super::geometry::*;
 
+use core::cell::RefCell;
+use core::marker::PhantomData;
+
+use num::traits::ToPrimitive;
+use rgb::{Rgb, Rgba};
+
 use crate::{liber8tion::interpolate::Fract8, pixels::*};
 
 /// Types that can provide direct hardware access to individual pixels within a given [Virtual] rectangle shaped selection for reading and writing
@@ -12,6 +18,36 @@ pub trait Sample<'a, Space: CoordinateSpace> {
     //FIXME: Moving 'a into sample<'a>() and type Iterator<'a>: Iterator<...> would allow implementations without unsafe {} blocks on basic arrays
     /// Provides a [PixelView] over the given [Rectangle] selection
     fn sample(&mut self, rect: &Rectangle<Space>) -> impl Iterator<Item = (Coordinates<Space>, &'a mut Self::Output)>;
+
+    /// Returns the area of the coordinate space that is actually backed by pixels, so effects can adapt to the size of the display
+    ///
+    /// By default, this is the entire coordinate space, which is always the case for spaces that are stretched to fit the display such as [Virtual].
+    fn bounds(&self) -> Rectangle<Space> {
+        Rectangle::everything()
+    }
+
+    /// Returns the number of physical pixels that can be sampled
+    ///
+    /// By default, this returns 0 to mean that the size is unknown. Samplers that know how many pixels they are backed by should override this, as [Virtual] coordinates alone can't tell how many pixels are behind them.
+    fn pixel_count(&self) -> usize {
+        0
+    }
+}
+
+/// Extension to [Sample] for selecting pixels with a [RotatedRectangle] instead of an axis-aligned [Rectangle]
+pub trait SampleRotated<'a>: Sample<'a, Virtual> {
+    /// Provides every pixel whose coordinates fall within the rotated rectangle
+    fn sample_rotated(&mut self, rect: &RotatedRectangle) -> impl Iterator<Item = (VirtualCoordinates, &'a mut Self::Output)> {
+        self.sample(rect.bounding_box()).filter(move |(coords, _)| rect.contains(coords))
+    }
+}
+
+impl<'a, T: Sample<'a, Virtual> + ?Sized> SampleRotated<'a> for T {}
+
+/// Extension to [Sample] for samplers that can report where each pixel is in the underlying pixel buffer, for effects that keep their own state for every LED or for debugging a mapping
+pub trait SampleIndexed<'a, Space: CoordinateSpace>: Sample<'a, Space> {
+    /// Provides every pixel within the given [Rectangle] like [Sample::sample], along with the index of the pixel in the underlying buffer
+    fn sample_indexed(&mut self, rect: &Rectangle<Space>) -> impl Iterator<Item = (Coordinates<Space>, usize, &'a mut Self::Output)>;
 }
 
 /// Function type that can provide an RGB color given a location in [Virtual] space and global rendering state
@@ -34,23 +70,463 @@ impl<T, U, Space: CoordinateSpace, Pixel> Shader<U, Space, Pixel> for T where T:
     }
 }
 
-/// Types which can draw a shader over some pre-defined geometrical regions
-pub trait Painter<U, Space: CoordinateSpace, Input> {
-    /// Draws the shader over the entire area, eg Rectangle::everything()
-    fn fill(&mut self, shader: &impl Shader<U, Space, Input>, uniforms: &U);
+/// Creates a [Shader] from a closure, whose argument types are inferred from the uniform type and [CoordinateSpace] given to this function
+///
+/// Closures that are passed directly as a shader need an annotation on every argument, as the closure alone can't tell which [Shader] it should implement. Naming the types here instead allows the closure to be written without any annotations.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::mappings::linear::LinearSpace;
+/// use rgb::Rgb;
+///
+/// let shader = shader_fn::<usize, LinearSpace, _>(|coords, frame| Rgb::new(coords.x as u8, *frame as u8, 0));
+///
+/// let mut pixbuf = [Rgb::new(0u8, 0, 0); 4];
+/// pixbuf.fill(&shader, &42);
+/// assert_eq!(pixbuf[3], Rgb::new(3, 42, 0));
+/// ```
+pub fn shader_fn<U, Space: CoordinateSpace, Pixel>(shader: impl Send + Fn(&Coordinates<Space>, &U) -> Pixel) -> impl Shader<U, Space, Pixel> {
+    shader
+}
 
-    /// Draws the shader over a given rectangle
-    fn paint(&mut self, shader: &impl Shader<U, Space, Input>, uniforms: &U, rect: &Rectangle<Space>);
+/// A [Shader] wrapper for closures that mutate their captured state, such as a counter or a random number generator
+///
+/// Shaders are normally drawn through a shared reference, so `FnMut` closures can't be used directly. This wrapper allows it, by keeping the closure in a [RefCell]. Drawing the same [StatefulShader] from within itself will panic.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::mappings::linear::LinearSpace;
+/// use rgb::Rgb;
+///
+/// let mut count = 0u8;
+/// let shader = StatefulShader::new(|_: &Coordinates<LinearSpace>, _: &()| {
+///     count += 1;
+///     Rgb::new(count, 0, 0)
+/// });
+/// let mut pixbuf = [Rgb::new(0u8, 0, 0); 4];
+/// pixbuf.fill(&shader, &());
+/// assert_eq!(pixbuf[3], Rgb::new(4, 0, 0));
+/// ```
+#[derive(Debug, Default)]
+pub struct StatefulShader<F>(RefCell<F>);
+
+impl<F> StatefulShader<F> {
+    /// Wraps a closure that mutates its state
+    pub const fn new(shader: F) -> Self {
+        Self(RefCell::new(shader))
+    }
+
+    /// Unwraps the closure, along with any state it has captured
+    pub fn into_inner(self) -> F {
+        self.0.into_inner()
+    }
+}
+
+impl<T, U, Space: CoordinateSpace, Pixel> Shader<U, Space, Pixel> for StatefulShader<T> where T: Send + FnMut(&Coordinates<Space>, &U) -> Pixel {
+    fn draw(&self, surface_coords: &Coordinates<Space>, uniforms: &U) -> Pixel {
+        (self.0.borrow_mut())(surface_coords, uniforms)
+    }
+}
+
+/// A [Shader] wrapper that lets the inner shader calculate colors in a wider type such as `Rgb<i16>` or `Rgb<u16>`, then clamps each channel into a `u8`.
+///
+/// This avoids the sudden discontinuities created when channel math wraps around from bright back to dark.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct SaturatingShader<S, T = i16> {
+    inner: S,
+    wide: PhantomData<fn() -> T>
+}
+
+impl<S, T> SaturatingShader<S, T> {
+    /// Wraps a shader that produces wide color channels
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            wide: PhantomData
+        }
+    }
+}
+
+#[inline(always)]
+fn saturate8<T: ToPrimitive>(value: T) -> u8 {
+    value.to_i64().map_or(0, |v| v.clamp(u8::MIN as i64, u8::MAX as i64) as u8)
+}
+
+impl<U, Space: CoordinateSpace, T: ToPrimitive, S: Shader<U, Space, Rgb<T>>> Shader<U, Space, Rgb<u8>> for SaturatingShader<S, T> {
+    fn draw(&self, surface_coords: &Coordinates<Space>, uniforms: &U) -> Rgb<u8> {
+        let wide = self.inner.draw(surface_coords, uniforms);
+        Rgb::new(saturate8(wide.r), saturate8(wide.g), saturate8(wide.b))
+    }
+}
+
+impl<U, Space: CoordinateSpace, T: ToPrimitive, S: Shader<U, Space, Rgba<T>>> Shader<U, Space, Rgba<u8>> for SaturatingShader<S, T> {
+    fn draw(&self, surface_coords: &Coordinates<Space>, uniforms: &U) -> Rgba<u8> {
+        let wide = self.inner.draw(surface_coords, uniforms);
+        Rgba::new(saturate8(wide.r), saturate8(wide.g), saturate8(wide.b), saturate8(wide.a))
+    }
+}
+
+/// A [Shader] wrapper that lets a shader written for one [CoordinateSpace] draw into another, by converting each coordinate before it is passed to the inner shader
+///
+/// This is useful for compositing shaders that are easier to describe in their own space, such as a radial pattern, onto a display with a different mapping.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct MappedShader<S, M, Space> {
+    inner: S,
+    mapping: M,
+    space: PhantomData<fn() -> Space>
+}
+
+impl<S, M, Space> MappedShader<S, M, Space> {
+    /// Wraps a shader authored in `Space`, using a function that converts coordinates into `Space`
+    pub const fn new(inner: S, mapping: M) -> Self {
+        Self {
+            inner,
+            mapping,
+            space: PhantomData
+        }
+    }
+}
+
+impl<U, Src: CoordinateSpace, Space: CoordinateSpace, Pixel, S: Shader<U, Space, Pixel>, M> Shader<U, Src, Pixel> for MappedShader<S, M, Space> where M: Send + Fn(&Coordinates<Src>) -> Coordinates<Space> {
+    fn draw(&self, surface_coords: &Coordinates<Src>, uniforms: &U) -> Pixel {
+        self.inner.draw(&(self.mapping)(surface_coords), uniforms)
+    }
+}
+
+/// A shader that has been baked into a lookup table of 256 colors along the [Virtual] X axis, which can then be replayed as a [Shader] for the cost of a single array index per pixel
+///
+/// This is useful for fixed gradients, or other patterns that are expensive to calculate and only vary along X.
+///
+/// ```
+/// use figments::prelude::*;
+/// use rgb::Rgb;
+///
+/// let gradient = |coords: &VirtualCoordinates, _: &()| Rgb::new(coords.x, 255 - coords.x, 0);
+/// let lut = Lut256::from_shader(&gradient, &());
+/// assert_eq!(lut.draw(&VirtualCoordinates::new(10, 200), &()), Rgb::new(10, 245, 0));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Lut256<Pixel>([Pixel; 256]);
+
+impl<Pixel> Lut256<Pixel> {
+    /// Bakes a shader by drawing it once at each X coordinate along the top row of the [Virtual] space
+    pub fn from_shader<U>(shader: &impl Shader<U, Virtual, Pixel>, uniforms: &U) -> Self {
+        Self(core::array::from_fn(|x| shader.draw(&VirtualCoordinates::new(x as u8, 0), uniforms)))
+    }
+
+    /// Returns the baked color at a given X coordinate
+    pub const fn get(&self, x: u8) -> &Pixel {
+        &self.0[x as usize]
+    }
+}
+
+impl<U, Pixel: Copy + Send> Shader<U, Virtual, Pixel> for Lut256<Pixel> {
+    fn draw(&self, surface_coords: &VirtualCoordinates, _uniforms: &U) -> Pixel {
+        self.0[surface_coords.x as usize]
+    }
+}
+
+/// The physical size of a display, in pixels
+///
+/// [Virtual] coordinates always run from 0-255 on both axes, which stretches shaders across displays that are not square. Shaders can use this to draw shapes in proportion to the real pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayInfo {
+    /// The number of pixels across the display
+    pub width: usize,
+    /// The number of pixels down the display
+    pub height: usize
+}
+
+impl DisplayInfo {
+    /// Describes a display of the given number of pixels across and down
+    pub const fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height
+        }
+    }
+
+    /// Returns the ratio between the width and the height of the display, where larger numbers are wider
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height.max(1) as f32
+    }
+
+    /// Shrinks the shorter axis of a [Virtual] coordinate, so that one unit on either axis covers the same physical distance
+    ///
+    /// The longer axis still runs from 0-255, so a shader that measures distances with these coordinates draws a circle as a circle instead of an oval.
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// let display = DisplayInfo::new(32, 16);
+    /// assert_eq!(display.square_coordinates(&VirtualCoordinates::new(255, 255)), VirtualCoordinates::new(255, 127));
+    /// ```
+    pub fn square_coordinates(&self, coords: &VirtualCoordinates) -> VirtualCoordinates {
+        let longest = self.width.max(self.height).max(1);
+        VirtualCoordinates::new(
+            (coords.x as usize * self.width.max(1) / longest) as u8,
+            (coords.y as usize * self.height.max(1) / longest) as u8
+        )
+    }
+}
+
+/// Uniforms that carry the [DisplayInfo] of the display being rendered to, alongside the application's own uniforms
+///
+/// A surface pool created with `RenderContext<U>` as its uniform type passes this to every shader. Shaders that don't need the display size can still be used in such a pool by wrapping them in a [WithoutContext].
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::mappings::MatrixSampler;
+/// use rgb::Rgb;
+///
+/// let aware = |coords: &VirtualCoordinates, ctx: &RenderContext<u8>| {
+///     let square = ctx.display.square_coordinates(coords);
+///     Rgb::new(square.x, square.y, ctx.uniforms)
+/// };
+/// let legacy = WithoutContext::new(|_: &VirtualCoordinates, frame: &u8| Rgb::new(0, 0, *frame * 2));
+///
+/// let mut pixbuf = [[Rgb::new(0, 0, 0); 8]; 4];
+/// let mut sampler = MatrixSampler::new(&mut pixbuf);
+/// let ctx = RenderContext::new(10, DisplayInfo::new(8, 4));
+/// for (coords, pixel) in Sample::<Virtual>::sample(&mut sampler, &Rectangle::everything()) {
+///     *pixel = aware.draw(&coords, &ctx);
+/// }
+/// for (coords, pixel) in Sample::<Virtual>::sample(&mut sampler, &Rectangle::new_from_coordinates(0, 0, 31, 63)) {
+///     *pixel = legacy.draw(&coords, &ctx);
+/// }
+/// assert_eq!(pixbuf[3][7], Rgb::new(224, 96, 10));
+/// assert_eq!(pixbuf[0][0], Rgb::new(0, 0, 20));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderContext<U> {
+    /// The application's own uniforms
+    pub uniforms: U,
+    /// The size of the display being rendered to
+    pub display: DisplayInfo
+}
+
+impl<U> RenderContext<U> {
+    /// Bundles the application's uniforms with the size of the display
+    pub const fn new(uniforms: U, display: DisplayInfo) -> Self {
+        Self {
+            uniforms,
+            display
+        }
+    }
 }
 
-impl<'a, U, Space: CoordinateSpace, Input: 'static, T> Painter<U, Space, Input> for T where T: Sample<'a, Space>, T::Output: AdditivePixelSink<Input> {
-    fn fill(&mut self, shader: &impl Shader<U, Space, Input>, uniforms: &U) {
+/// A [Shader] wrapper that lets a shader written for plain uniforms draw with a [RenderContext], by ignoring the [DisplayInfo]
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WithoutContext<S>(S);
+
+impl<S> WithoutContext<S> {
+    /// Wraps a shader that doesn't need to know the size of the display
+    pub const fn new(inner: S) -> Self {
+        Self(inner)
+    }
+}
+
+impl<U, Space: CoordinateSpace, Pixel, S: Shader<U, Space, Pixel>> Shader<RenderContext<U>, Space, Pixel> for WithoutContext<S> {
+    fn draw(&self, surface_coords: &Coordinates<Space>, uniforms: &RenderContext<U>) -> Pixel {
+        self.0.draw(surface_coords, &uniforms.uniforms)
+    }
+}
+
+/// Types which can draw a shader over some pre-defined geometrical regions
+///
+/// This is implemented for every [Sample], so any pixbuf or sampler can be painted on.
+pub trait Painter<'a, Space: CoordinateSpace>: Sample<'a, Space> {
+    /// Draws the shader over the entire area, eg Rectangle::everything()
+    fn fill<U, Input: 'static>(&mut self, shader: &impl Shader<U, Space, Input>, uniforms: &U) where Self::Output: AdditivePixelSink<Input> {
         self.paint(shader, uniforms, &Rectangle::everything());
     }
 
-    fn paint(&mut self, shader: &impl Shader<U, Space, Input>, uniforms: &U, rect: &Rectangle<Space>) {
+    /// Draws the shader over a given rectangle
+    fn paint<U, Input: 'static>(&mut self, shader: &impl Shader<U, Space, Input>, uniforms: &U, rect: &Rectangle<Space>) where Self::Output: AdditivePixelSink<Input> {
         for (coords, pixel) in self.sample(rect) {
             pixel.add(shader.draw(&coords, uniforms), Fract8::MAX);
         }
     }
-}
\ No newline at end of file
+
+    /// Resets every pixel that can be sampled back to its default, which is black for every color type
+    ///
+    /// Unlike replacing the whole pixbuf, this only touches the pixels that are covered by the sampler.
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// let mut pixbuf = [Rgb::new(255u8, 0, 0); 4];
+    /// pixbuf.clear();
+    /// assert_eq!(pixbuf, [Rgb::new(0, 0, 0); 4]);
+    /// ```
+    fn clear(&mut self) where Self::Output: Default {
+        for (_, pixel) in self.sample(&Rectangle::everything()) {
+            *pixel = Default::default();
+        }
+    }
+
+    /// Draws a single color at full opacity over every pixel that can be sampled
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    ///
+    /// let mut pixbuf = [Rgb::new(0u8, 0, 0); 4];
+    /// pixbuf.fill_solid(Rgb::new(0u8, 0, 255));
+    /// assert_eq!(pixbuf, [Rgb::new(0, 0, 255); 4]);
+    /// ```
+    fn fill_solid<Input: Copy>(&mut self, color: Input) where Self::Output: AdditivePixelSink<Input> {
+        for (_, pixel) in self.sample(&Rectangle::everything()) {
+            pixel.add(color, Fract8::MAX);
+        }
+    }
+}
+
+impl<'a, Space: CoordinateSpace, T: Sample<'a, Space> + ?Sized> Painter<'a, Space> for T {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mappings::linear::LinearSpace;
+
+    fn ramp(coords: &Coordinates<LinearSpace>, _uniforms: &()) -> Rgb<i16> {
+        // Ramps up twice as fast as there are coordinates, and goes negative on the blue channel
+        let x = coords.x as i16;
+        Rgb::new(x * 2, 255 - x * 2, x - 128)
+    }
+
+    #[test]
+    fn test_saturating_shader_removes_wraparound() {
+        let shader = SaturatingShader::new(ramp);
+        let mut previous = Rgb::new(0, 255, 0);
+        for x in 0..256 {
+            let pixel: Rgb<u8> = shader.draw(&Coordinates::new(x, 0), &());
+            assert!(pixel.r >= previous.r, "red wrapped around at x={x}: {previous:?} -> {pixel:?}");
+            assert!(pixel.g <= previous.g, "green wrapped around at x={x}: {previous:?} -> {pixel:?}");
+            assert!(pixel.b >= previous.b, "blue wrapped around at x={x}: {previous:?} -> {pixel:?}");
+            previous = pixel;
+        }
+        assert_eq!(previous, Rgb::new(255, 0, 127));
+    }
+
+    #[test]
+    fn test_stateful_shader_counts_pixels() {
+        let mut count = 0;
+        let mut pixbuf = [0u8; 16];
+        {
+            let shader = StatefulShader::new(|coords: &Coordinates<LinearSpace>, _: &()| {
+                count += 1;
+                // Each pixel sees the count of every pixel drawn before it
+                assert_eq!(coords.x + 1, count);
+                count as u8
+            });
+            for (coords, pixel) in pixbuf.sample(&Rectangle::everything()) {
+                *pixel = shader.draw(&coords, &());
+            }
+        }
+
+        assert_eq!(count, 16);
+        assert_eq!(pixbuf[15], 16);
+    }
+
+    #[test]
+    fn test_lut_reproduces_shader() {
+        let shader = |coords: &VirtualCoordinates, frame: &u8| Rgb::new(coords.x.wrapping_mul(3), coords.x ^ *frame, 255 - coords.x);
+        let lut = Lut256::from_shader(&shader, &7);
+        for x in 0..=255 {
+            for y in [0, 128, 255] {
+                let coords = VirtualCoordinates::new(x, y);
+                assert_eq!(lut.draw(&coords, &()), shader.draw(&coords, &7), "The LUT differs from the shader at {coords:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_rotated_bar_lights_diagonal() {
+        use crate::mappings::MatrixSampler;
+
+        let mut pixbuf = [[false; 16]; 16];
+        let bar = RotatedRectangle::new(VirtualCoordinates::center(), 200, 8, 32);
+        for (_, pixel) in MatrixSampler::new(&mut pixbuf).sample_rotated(&bar) {
+            *pixel = true;
+        }
+
+        for (y, row) in pixbuf.iter().enumerate() {
+            for (x, lit) in row.iter().enumerate() {
+                let on_diagonal = x == y && (4..=12).contains(&x);
+                assert_eq!(*lit, on_diagonal, "Pixel ({x}, {y}) should only be lit if it is on the diagonal");
+            }
+        }
+
+        // Without any rotation, the same bar is a horizontal line through a single row
+        let mut pixbuf = [[false; 16]; 16];
+        let flat = RotatedRectangle::new(VirtualCoordinates::new(136, 136), 200, 8, 0);
+        assert_eq!(*flat.bounding_box(), Rectangle::new_from_coordinates(36, 132, 236, 140));
+        for (_, pixel) in MatrixSampler::new(&mut pixbuf).sample_rotated(&flat) {
+            *pixel = true;
+        }
+        assert!(pixbuf[8][2..=14].iter().all(|lit| *lit));
+        assert_eq!(pixbuf.iter().flatten().filter(|lit| **lit).count(), 13, "Only the center row should be lit");
+    }
+
+    /// Lights every pixel within a radius of the center of the display, and returns how many pixels were lit across the middle row and down the middle column
+    fn circle_extent(correct_aspect: bool) -> (usize, usize) {
+        use crate::mappings::MatrixSampler;
+
+        let circle = |coords: &VirtualCoordinates, ctx: &RenderContext<u8>| {
+            let (center, coords) = match correct_aspect {
+                true => (ctx.display.square_coordinates(&VirtualCoordinates::new(128, 128)), ctx.display.square_coordinates(coords)),
+                false => (VirtualCoordinates::new(128, 128), *coords)
+            };
+            let dx = coords.x as i32 - center.x as i32;
+            let dy = coords.y as i32 - center.y as i32;
+            if dx * dx + dy * dy <= (ctx.uniforms as i32).pow(2) { 1u8 } else { 0 }
+        };
+
+        let mut pixbuf = [[0u8; 32]; 16];
+        let ctx = RenderContext::new(40, DisplayInfo::new(32, 16));
+        for (coords, pixel) in Sample::<Virtual>::sample(&mut MatrixSampler::new(&mut pixbuf), &Rectangle::everything()) {
+            *pixel = circle.draw(&coords, &ctx);
+        }
+        let across = pixbuf[8].iter().filter(|pixel| **pixel == 1).count();
+        let down = pixbuf.iter().filter(|row| row[16] == 1).count();
+        (across, down)
+    }
+
+    #[test]
+    fn test_aspect_correction_draws_round_circles() {
+        let (across, down) = circle_extent(false);
+        assert!(across >= down * 2 - 1, "Without correction, the circle should be stretched across the wide display: {across}x{down}");
+
+        let (across, down) = circle_extent(true);
+        assert!(across.abs_diff(down) <= 1, "With correction, the circle should be as wide as it is tall: {across}x{down}");
+        assert!(across > 1, "The circle should not be empty");
+    }
+
+    #[test]
+    fn test_clear_and_fill_only_touch_sampled_pixels() {
+        use crate::mappings::{MatrixMapping, MatrixMappingSampler};
+
+        // The mapping only covers the first 4 pixels of the buffer
+        let untouched = Rgb::new(9u8, 9, 9);
+        let mut pixbuf = [untouched; 8];
+        let map = MatrixMapping::new(2, 2);
+
+        Painter::<Virtual>::fill_solid(&mut MatrixMappingSampler::new(&mut pixbuf, &map), Rgb::new(255u8, 0, 0));
+        assert_eq!(pixbuf[..4], [Rgb::new(255, 0, 0); 4]);
+        assert_eq!(pixbuf[4..], [untouched; 4], "Pixels outside of the sampler should not be filled");
+
+        Painter::<Virtual>::fill_solid(&mut MatrixMappingSampler::new(&mut pixbuf, &map), Rgba::new(0u8, 0, 255, 128));
+        assert_eq!(pixbuf[0], Rgb::new(127, 0, 128), "The alpha of the color should still be respected");
+
+        Painter::<Virtual>::clear(&mut MatrixMappingSampler::new(&mut pixbuf, &map));
+        assert_eq!(pixbuf[..4], [Rgb::default(); 4]);
+        assert_eq!(pixbuf[4..], [untouched; 4], "Pixels outside of the sampler should not be cleared");
+    }
+
+    #[test]
+    fn test_without_context_ignores_display() {
+        let shader = WithoutContext::new(|coords: &VirtualCoordinates, frame: &u8| Rgb::new(coords.x, *frame, 0));
+        let coords = VirtualCoordinates::new(12, 34);
+        assert_eq!(shader.draw(&coords, &RenderContext::new(5, DisplayInfo::new(100, 1))), Rgb::new(12, 5, 0));
+    }
+}
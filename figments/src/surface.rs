@@ -1,17 +1,19 @@
 use crate::liber8tion::interpolate::Fract8;
 use crate::prelude::*;
+use crate::mappings::PhysicalMapping;
 
 use spin::Mutex;
 use alloc::boxed::Box;
 use alloc::vec::Vec;
 use alloc::sync::Arc;
 
-use core::{marker::PhantomData, ops::DerefMut};
+use core::{marker::PhantomData, ops::{Deref, DerefMut, Mul}};
 use core::fmt::{Debug, Formatter};
 use ringbuf::{StaticRb, traits::*};
 use portable_atomic::AtomicBool;
+use num::traits::SaturatingAdd;
 
-impl<U, Space: CoordinateSpace, Pixel> Debug for ShaderBinding<U, Space, Pixel> where Rectangle<Space>: Debug {
+impl<U, Space: CoordinateSpace, Pixel, SH> Debug for ShaderBinding<U, Space, Pixel, SH> where Rectangle<Space>: Debug {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("ShaderBinding")
             .field("rect", &self.rect)
@@ -21,30 +23,119 @@ impl<U, Space: CoordinateSpace, Pixel> Debug for ShaderBinding<U, Space, Pixel>
                 Some(_) => "Some(...)"
             })
             .field("visible", &self.visible)
+            .field("group", &self.group)
+            .field("z_order", &self.z_order)
+            .field("blend_mode", &self.blend_mode)
+            .field("transition", &self.transition.as_ref().map(Transition::progress))
             .finish()
     }
 }
-struct ShaderBinding<U, Space: CoordinateSpace, Pixel> {
-    shader: Option<Box<dyn Shader<U, Space, Pixel>>>,
+struct ShaderBinding<U, Space: CoordinateSpace, Pixel, SH> {
+    shader: Option<SH>,
+    /// The shaders that are still fading out after [BufferedSurface::transition_to]
+    transition: Option<Transition<SH>>,
     rect: Rectangle<Space>,
     opacity: Fract8,
     visible: bool,
-    offset: Coordinates<Space>
+    offset: Coordinates<Space>,
+    scale: (u8, u8),
+    virtual_offset: VirtualCoordinates,
+    local_coordinates: bool,
+    group: Option<u8>,
+    z_order: i32,
+    blend_mode: BlendMode,
+    /// Counts how many times the slot has been recycled, so updates from a surface that was closed are never applied to the one that replaced it
+    generation: u32,
+    _space: PhantomData<fn(U) -> Pixel>
 }
 
-struct SurfaceUpdate<U, Space: CoordinateSpace, Pixel> {
-    #[allow(clippy::type_complexity)]
-    shader: Option<Option<Box<dyn Shader<U, Space, Pixel>>>>,
+/// A cross-fade from the shaders that were bound before [BufferedSurface::transition_to] to the current shader of a surface
+struct Transition<SH> {
+    /// The shader that is fading out, or None to fade in over whatever is underneath the surface
+    outgoing: Option<SH>,
+    /// The shader that `outgoing` was still fading in from when this transition interrupted another one, along with how far that fade had gotten
+    interrupted: Option<(Option<SH>, Fract8)>,
+    frames: u16,
+    /// Counts the commits since the fade started
+    elapsed: u16
+}
+
+impl<SH> Transition<SH> {
+    fn new(outgoing: Option<SH>, interrupted: Option<(Option<SH>, Fract8)>, frames: u16) -> Self {
+        Self {
+            outgoing,
+            interrupted,
+            frames,
+            elapsed: 0
+        }
+    }
+
+    /// Returns how far the new shader has faded in
+    fn progress(&self) -> Fract8 {
+        let elapsed = self.elapsed.min(self.frames);
+        Fract8::from_raw((elapsed as u32 * 255 / self.frames as u32) as u8)
+    }
+
+    /// Moves the fade ahead by one frame
+    fn advance(&mut self) {
+        self.elapsed = self.elapsed.saturating_add(1).min(self.frames);
+    }
+
+    fn is_finished(&self) -> bool {
+        self.progress() == Fract8::MAX
+    }
+
+    /// Starts a new transition away from the current shader, which begins from wherever this transition currently is
+    ///
+    /// Only one interrupted fade is kept, so the shader this transition was fading out from is dropped, or replaces `current` if it is still the most visible.
+    fn interrupt(self, current: Option<SH>, frames: u16) -> Self {
+        let progress = self.progress();
+        if progress == Fract8::MAX {
+            return Transition::new(current, None, frames);
+        }
+        let outgoing = match self.interrupted {
+            Some((under, fade)) if fade.to_raw() < 128 => under,
+            _ => self.outgoing
+        };
+        Transition::new(current, Some((outgoing, progress)), frames)
+    }
+}
+
+/// Blends two layers of a cross-fade, which each have a color and how much of the pixel they cover
+fn crossfade<Pixel: Fract8Ops + Copy>(from: Option<(Pixel, Fract8)>, to: Option<(Pixel, Fract8)>, progress: Fract8) -> Option<(Pixel, Fract8)> {
+    let from_weight = from.map_or(0, |(_, coverage)| coverage.to_raw() as u16 * (255 - progress.to_raw()) as u16 / 255);
+    let to_weight = to.map_or(0, |(_, coverage)| coverage.to_raw() as u16 * progress.to_raw() as u16 / 255);
+    let coverage = Fract8::from_raw((from_weight + to_weight) as u8);
+    match (from, to) {
+        (Some((pixel, _)), None) | (None, Some((pixel, _))) => Some((pixel, coverage)),
+        (Some((from, _)), Some((to, _))) if coverage > Fract8::MIN => Some((from.blend8(to, Fract8::from_raw((to_weight * 255 / (from_weight + to_weight)) as u8)), coverage)),
+        _ => None
+    }
+}
+
+struct SurfaceUpdate<U, Space: CoordinateSpace, Pixel, SH> {
+    shader: Option<Option<SH>>,
+    /// Cross-fades to the new shader over this many frames instead of replacing the old one immediately
+    transition_frames: Option<u16>,
     rect: Option<Rectangle<Space>>,
     opacity: Option<Fract8>,
     visible: Option<bool>,
     offset: Option<Coordinates<Space>>,
+    scale: Option<(u8, u8)>,
+    virtual_offset: Option<VirtualCoordinates>,
+    local_coordinates: Option<bool>,
+    group: Option<Option<u8>>,
+    z_order: Option<i32>,
+    blend_mode: Option<BlendMode>,
+    close: bool,
     slot: usize,
+    generation: u32,
+    _space: PhantomData<fn(U) -> Pixel>
 }
 
-type UpdateRB<U, Space, Pixel> = StaticRb<SurfaceUpdate<U, Space, Pixel>, 32>;
+type UpdateRB<U, Space, Pixel, SH> = StaticRb<SurfaceUpdate<U, Space, Pixel, SH>, 32>;
 
-impl<U, Space: CoordinateSpace, Pixel> Debug for SurfaceUpdate<U, Space, Pixel> {
+impl<U, Space: CoordinateSpace, Pixel, SH> Debug for SurfaceUpdate<U, Space, Pixel, SH> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("SurfaceUpdate")
             .field("slot", &self.slot)
@@ -52,10 +143,11 @@ impl<U, Space: CoordinateSpace, Pixel> Debug for SurfaceUpdate<U, Space, Pixel>
     }
 }
 
-impl<U, Space: CoordinateSpace, Pixel> SurfaceUpdate<U, Space, Pixel> {
+impl<U, Space: CoordinateSpace, Pixel, SH> SurfaceUpdate<U, Space, Pixel, SH> {
     fn merge(&mut self, mut other: Self) {
         if other.shader.is_some() {
-            self.shader = other.shader.take()
+            self.shader = other.shader.take();
+            self.transition_frames = other.transition_frames.take();
         }
         if other.rect.is_some() {
             self.rect = other.rect.take()
@@ -69,44 +161,78 @@ impl<U, Space: CoordinateSpace, Pixel> SurfaceUpdate<U, Space, Pixel> {
         if other.offset.is_some() {
             self.offset = other.offset.take()
         }
+        if other.scale.is_some() {
+            self.scale = other.scale.take()
+        }
+        if other.virtual_offset.is_some() {
+            self.virtual_offset = other.virtual_offset.take()
+        }
+        if other.local_coordinates.is_some() {
+            self.local_coordinates = other.local_coordinates.take()
+        }
+        if other.group.is_some() {
+            self.group = other.group.take()
+        }
+        if other.z_order.is_some() {
+            self.z_order = other.z_order.take()
+        }
+        if other.blend_mode.is_some() {
+            self.blend_mode = other.blend_mode.take()
+        }
+        self.close |= other.close;
     }
 }
 
-impl<U, Space: CoordinateSpace, Pixel> Default for SurfaceUpdate<U, Space, Pixel> {
+impl<U, Space: CoordinateSpace, Pixel, SH> Default for SurfaceUpdate<U, Space, Pixel, SH> {
     fn default() -> Self {
         SurfaceUpdate {
             shader: None,
+            transition_frames: None,
             rect: None,
             opacity: None,
             visible: None,
             offset: None,
-            slot: usize::MAX
+            scale: None,
+            virtual_offset: None,
+            local_coordinates: None,
+            group: None,
+            z_order: None,
+            blend_mode: None,
+            close: false,
+            slot: usize::MAX,
+            generation: 0,
+            _space: PhantomData
         }
     }
 }
 
 /// A thread-safe [Surface] implementation where changes are buffered before they are committed in batches
-pub struct BufferedSurface<U, Space: CoordinateSpace, Pixel> {
-    updater: Arc<UpdateQueue<U, Space, Pixel>>,
-    slot: usize
+///
+/// The shaders are stored as a `Box<dyn Shader>` by default. See [ShaderStorage] for storing them without dynamic dispatch.
+pub struct BufferedSurface<U, Space: CoordinateSpace, Pixel, SH = Box<dyn Shader<U, Space, Pixel>>> {
+    updater: Arc<UpdateQueue<U, Space, Pixel, SH>>,
+    slot: usize,
+    generation: u32
 }
 
-impl<U, Space: CoordinateSpace, Pixel> Debug for BufferedSurface<U, Space, Pixel> {
+impl<U, Space: CoordinateSpace, Pixel, SH> Debug for BufferedSurface<U, Space, Pixel, SH> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("BufferedSurface").field("updater", &self.updater).field("slot", &self.slot).finish()
+        f.debug_struct("BufferedSurface").field("updater", &self.updater).field("slot", &self.slot).field("generation", &self.generation).finish()
     }
 }
 
 
-impl<U, Space: CoordinateSpace, Pixel> Surface for BufferedSurface<U, Space, Pixel> {
+impl<U, Space: CoordinateSpace, Pixel, SH: Shader<U, Space, Pixel>> Surface for BufferedSurface<U, Space, Pixel, SH> {
     type Uniforms = U;
     type CoordinateSpace = Space;
     type Pixel = Pixel;
+    type Shaders = SH;
 
     fn clear_shader(&mut self) {
         self.updater.push(SurfaceUpdate {
             shader: Some(None),
             slot: self.slot,
+            generation: self.generation,
             ..Default::default()
         }).unwrap();
     }
@@ -115,22 +241,25 @@ impl<U, Space: CoordinateSpace, Pixel> Surface for BufferedSurface<U, Space, Pix
         self.updater.push(SurfaceUpdate {
             rect: Some(rect),
             slot: self.slot,
+            generation: self.generation,
             ..Default::default()
         }).unwrap();
     }
 
-    fn set_shader<T: Shader<U, Space, Pixel> + 'static>(&mut self, shader: T) {
+    fn set_shader<T: Shader<U, Space, Pixel> + 'static>(&mut self, shader: T) where SH: ShaderStorage<U, Space, Pixel, T> {
         self.updater.push(SurfaceUpdate {
-            shader: Some(Some(Box::new(shader))),
+            shader: Some(Some(SH::store(shader))),
             slot: self.slot,
+            generation: self.generation,
             ..Default::default()
         }).unwrap();
     }
 
-    fn set_opacity(&mut self, opacity: Fract8) {
+    fn set_opacity(&mut self, opacity: impl Into<Fract8>) {
         self.updater.push(SurfaceUpdate {
-            opacity: Some(opacity),
+            opacity: Some(opacity.into()),
             slot: self.slot,
+            generation: self.generation,
             ..Default::default()
         }).unwrap();
     }
@@ -139,6 +268,7 @@ impl<U, Space: CoordinateSpace, Pixel> Surface for BufferedSurface<U, Space, Pix
         self.updater.push(SurfaceUpdate {
             visible: Some(visible),
             slot: self.slot,
+            generation: self.generation,
             ..Default::default()
         }).unwrap();
     }
@@ -147,23 +277,154 @@ impl<U, Space: CoordinateSpace, Pixel> Surface for BufferedSurface<U, Space, Pix
         self.updater.push(SurfaceUpdate {
             offset: Some(offset),
             slot: self.slot,
+            generation: self.generation,
+            ..Default::default()
+        }).unwrap();
+    }
+
+    fn set_scale(&mut self, x: u8, y: u8) {
+        self.updater.push(SurfaceUpdate {
+            scale: Some((x, y)),
+            slot: self.slot,
+            generation: self.generation,
+            ..Default::default()
+        }).unwrap();
+    }
+
+    fn set_virtual_offset(&mut self, offset: VirtualCoordinates) {
+        self.updater.push(SurfaceUpdate {
+            virtual_offset: Some(offset),
+            slot: self.slot,
+            generation: self.generation,
+            ..Default::default()
+        }).unwrap();
+    }
+
+    fn set_local_coordinates(&mut self, local: bool) {
+        self.updater.push(SurfaceUpdate {
+            local_coordinates: Some(local),
+            slot: self.slot,
+            generation: self.generation,
+            ..Default::default()
+        }).unwrap();
+    }
+
+    fn set_group(&mut self, group: Option<u8>) {
+        self.updater.push(SurfaceUpdate {
+            group: Some(group),
+            slot: self.slot,
+            generation: self.generation,
+            ..Default::default()
+        }).unwrap();
+    }
+}
+
+impl<U, Space: CoordinateSpace, Pixel, SH: Shader<U, Space, Pixel>> BufferedSurface<U, Space, Pixel, SH> {
+    /// Cross-fades from the current shader to a new one over the given number of frames, instead of switching to it immediately like [Surface::set_shader]
+    ///
+    /// Every commit of the pool counts as one frame, starting with the one that applies the transition, and the old shader is dropped on the first commit after the fade has finished. Rendering the pool more than once between commits draws the same step of the fade again. Surfaces without a shader fade the new one in over whatever is underneath them. Starting another transition before the fade has finished continues from the colors that are currently on screen.
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    /// use figments::mappings::linear::LinearSpace;
+    ///
+    /// let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+    /// let mut sfc = pool.new_surface(Rectangle::everything()).unwrap();
+    /// sfc.set_shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(255, 0, 0));
+    /// pool.commit();
+    ///
+    /// sfc.transition_to(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(0, 0, 255), 2);
+    /// pool.commit();
+    /// let mut pixbuf = [Rgb::new(0u8, 0, 0); 1];
+    /// pool.render_frame(&mut pixbuf[..], &());
+    /// assert_eq!(pixbuf, [Rgb::new(128, 0, 127)]);
+    /// pool.commit();
+    /// pool.render_frame(&mut pixbuf[..], &());
+    /// assert_eq!(pixbuf, [Rgb::new(0, 0, 255)]);
+    /// ```
+    pub fn transition_to<T: Shader<U, Space, Pixel> + 'static>(&mut self, shader: T, frames: u16) where SH: ShaderStorage<U, Space, Pixel, T> {
+        self.updater.push(SurfaceUpdate {
+            shader: Some(Some(SH::store(shader))),
+            transition_frames: Some(frames),
+            slot: self.slot,
+            generation: self.generation,
             ..Default::default()
         }).unwrap();
     }
 }
 
-impl<U, Space: CoordinateSpace, Pixel> Debug for UpdateQueue<U, Space, Pixel> {
+impl<U, Space: CoordinateSpace, Pixel, SH> BufferedSurface<U, Space, Pixel, SH> {
+    /// Moves the surface in front of or behind the other surfaces in its pool
+    ///
+    /// Surfaces are drawn from the lowest z-order to the highest, so the surface with the highest z-order ends up on top. Surfaces with the same z-order are drawn in the order they were created. Every surface starts out with a z-order of 0.
+    pub fn set_z_order(&mut self, z: i32) {
+        self.updater.push(SurfaceUpdate {
+            z_order: Some(z),
+            slot: self.slot,
+            generation: self.generation,
+            ..Default::default()
+        }).unwrap();
+    }
+
+    /// Changes how the surface is combined with the surfaces underneath it, which is [BlendMode::Normal] by default
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.updater.push(SurfaceUpdate {
+            blend_mode: Some(mode),
+            slot: self.slot,
+            generation: self.generation,
+            ..Default::default()
+        }).unwrap();
+    }
+
+    /// Removes the surface from its pool, which frees up its slot to be reused by the next new surface
+    ///
+    /// The surface stops rendering on the next commit. Dropping a surface without closing it leaves it in the pool, so it keeps rendering with whatever state it had.
+    pub fn close(self) {
+        self.updater.push(SurfaceUpdate {
+            close: true,
+            slot: self.slot,
+            generation: self.generation,
+            ..Default::default()
+        }).unwrap();
+    }
+
+    /// Puts every property of the surface back to how a freshly created surface would be, covering the entire display without a shader
+    ///
+    /// This is sent as a single update, so a recycled surface never renders with a mix of its old and new state.
+    pub fn reset(&mut self) {
+        self.updater.push(SurfaceUpdate {
+            shader: Some(None),
+            transition_frames: None,
+            rect: Some(Rectangle::everything()),
+            opacity: Some(Fract8::MAX),
+            visible: Some(true),
+            offset: Some(Coordinates::top_left()),
+            scale: Some((1, 1)),
+            virtual_offset: Some(VirtualCoordinates::top_left()),
+            local_coordinates: Some(false),
+            group: Some(None),
+            z_order: Some(0),
+            blend_mode: Some(BlendMode::Normal),
+            close: false,
+            slot: self.slot,
+            generation: self.generation,
+            _space: PhantomData
+        }).unwrap();
+    }
+}
+
+impl<U, Space: CoordinateSpace, Pixel, SH> Debug for UpdateQueue<U, Space, Pixel, SH> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("UpdateQueue").finish()
     }
 }
 
-struct UpdateQueue<U, Space: CoordinateSpace, Pixel> {
-    pending: Mutex<UpdateRB<U, Space, Pixel>>,
+struct UpdateQueue<U, Space: CoordinateSpace, Pixel, SH> {
+    pending: Mutex<UpdateRB<U, Space, Pixel, SH>>,
     damaged: AtomicBool
 }
 
-impl<U, Space: CoordinateSpace, Pixel> Default for UpdateQueue<U, Space, Pixel> {
+impl<U, Space: CoordinateSpace, Pixel, SH> Default for UpdateQueue<U, Space, Pixel, SH> {
     fn default() -> Self {
         Self {
             pending: Mutex::new(Default::default()),
@@ -172,12 +433,12 @@ impl<U, Space: CoordinateSpace, Pixel> Default for UpdateQueue<U, Space, Pixel>
     }
 }
 
-impl<U, Space: CoordinateSpace, Pixel> UpdateQueue<U, Space, Pixel> {
-    fn push(&self, update: SurfaceUpdate<U, Space, Pixel>) -> Result<(), SurfaceUpdate<U, Space, Pixel>> {
+impl<U, Space: CoordinateSpace, Pixel, SH> UpdateQueue<U, Space, Pixel, SH> {
+    fn push(&self, update: SurfaceUpdate<U, Space, Pixel, SH>) -> Result<(), SurfaceUpdate<U, Space, Pixel, SH>> {
         let mut locked = self.pending.lock();
         let mut existing_slot = None;
         for existing in locked.iter_mut() {
-            if existing.slot == update.slot {
+            if existing.slot == update.slot && existing.generation == update.generation {
                 existing_slot = Some(existing);
                 break
             }
@@ -197,7 +458,7 @@ impl<U, Space: CoordinateSpace, Pixel> UpdateQueue<U, Space, Pixel> {
         Ok(())
     }
 
-    fn try_take(&self) -> Option<UpdateRB<U, Space, Pixel>> {
+    fn try_take(&self) -> Option<UpdateRB<U, Space, Pixel, SH>> {
         if self.damaged.load(core::sync::atomic::Ordering::Acquire) {
             let mut updates = self.pending.lock();
             self.damaged.store(false, core::sync::atomic::Ordering::Relaxed);
@@ -208,25 +469,84 @@ impl<U, Space: CoordinateSpace, Pixel> UpdateQueue<U, Space, Pixel> {
     }
 }
 
-#[derive(Default)]
-struct ShaderChain<U, Space: CoordinateSpace, Pixel> {
-    bindings: Vec<ShaderBinding<U, Space, Pixel>>,
-    updates: Arc<UpdateQueue<U, Space, Pixel>>
+/// The reasons a [Surfaces] implementation can fail to create a new surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceError {
+    /// The pool has already handed out as many surfaces as it can hold
+    OutOfSlots,
+    /// The requested rectangle is upside down, so it would not cover any pixels
+    InvalidRect
+}
+
+impl core::fmt::Display for SurfaceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SurfaceError::OutOfSlots => write!(f, "no surface slots are left in the pool"),
+            SurfaceError::InvalidRect => write!(f, "the surface rectangle is upside down")
+        }
+    }
+}
+
+struct ShaderChain<U, Space: CoordinateSpace, Pixel, SH = Box<dyn Shader<U, Space, Pixel>>> {
+    bindings: Vec<ShaderBinding<U, Space, Pixel, SH>>,
+    /// The slots of the bindings, sorted by their z-order
+    draw_order: Vec<usize>,
+    /// Slots of closed surfaces, which are reused before any new slots are added
+    free_slots: Vec<usize>,
+    updates: Arc<UpdateQueue<U, Space, Pixel, SH>>,
+    capacity: Option<usize>
+}
+
+impl<U, Space: CoordinateSpace, Pixel, SH> Default for ShaderChain<U, Space, Pixel, SH> {
+    fn default() -> Self {
+        Self {
+            bindings: Vec::new(),
+            draw_order: Vec::new(),
+            free_slots: Vec::new(),
+            updates: Default::default(),
+            capacity: None
+        }
+    }
 }
 
-impl<U, Space: CoordinateSpace, Pixel> Debug for ShaderChain<U, Space, Pixel> where Space: Debug, Space::Data: Debug {
+impl<U, Space: CoordinateSpace, Pixel, SH> Debug for ShaderChain<U, Space, Pixel, SH> where Space: Debug, Space::Data: Debug {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("ShaderChain").field("bindings", &self.bindings).field("updates", &self.updates).finish()
     }
 }
 
-impl<U: 'static, Space: CoordinateSpace, Pixel> ShaderChain<U, Space, Pixel> {
+impl<U: 'static, Space: CoordinateSpace, Pixel, SH> ShaderChain<U, Space, Pixel, SH> {
     pub fn commit(&mut self) {
+        for binding in self.bindings.iter_mut() {
+            if binding.transition.as_ref().is_some_and(Transition::is_finished) {
+                binding.transition = None;
+            }
+        }
         if let Some(mut queue) = self.updates.try_take() {
+            let mut reordered = false;
             for update in queue.iter_mut() {
                 let target_slot = &mut self.bindings[update.slot];
+                if target_slot.generation != update.generation {
+                    // The surface that sent this was closed, and its slot now belongs to another surface
+                    continue;
+                }
+                if update.close {
+                    let generation = target_slot.generation.wrapping_add(1);
+                    *target_slot = ShaderBinding::new(Rectangle::everything(), generation);
+                    target_slot.visible = false;
+                    self.free_slots.push(update.slot);
+                    reordered = true;
+                    continue;
+                }
                 if let Some(shader) = update.shader.take() {
-                    target_slot.shader = shader;
+                    let current = core::mem::replace(&mut target_slot.shader, shader);
+                    target_slot.transition = match update.transition_frames.take() {
+                        Some(0) | None => None,
+                        Some(frames) => Some(match target_slot.transition.take() {
+                            Some(transition) => transition.interrupt(current, frames),
+                            None => Transition::new(current, None, frames)
+                        })
+                    };
                 }
                 if let Some(opacity) = update.opacity.take() {
                     target_slot.opacity = opacity;
@@ -237,62 +557,347 @@ impl<U: 'static, Space: CoordinateSpace, Pixel> ShaderChain<U, Space, Pixel> {
                 if let Some(visible) = update.visible.take() {
                     target_slot.visible = visible;
                 }
+                if let Some(offset) = update.offset.take() {
+                    target_slot.offset = offset;
+                }
+                if let Some(scale) = update.scale.take() {
+                    target_slot.scale = scale;
+                }
+                if let Some(virtual_offset) = update.virtual_offset.take() {
+                    target_slot.virtual_offset = virtual_offset;
+                }
+                if let Some(local_coordinates) = update.local_coordinates.take() {
+                    target_slot.local_coordinates = local_coordinates;
+                }
+                if let Some(group) = update.group.take() {
+                    target_slot.group = group;
+                }
+                if let Some(z_order) = update.z_order.take() {
+                    target_slot.z_order = z_order;
+                    reordered = true;
+                }
+                if let Some(blend_mode) = update.blend_mode.take() {
+                    target_slot.blend_mode = blend_mode;
+                }
+            }
+            if reordered {
+                self.sort_draw_order();
             }
         }
+        for transition in self.bindings.iter_mut().filter_map(|binding| binding.transition.as_mut()) {
+            transition.advance();
+        }
     }
 
-    fn new_surface(&mut self, area: Rectangle<Space>) -> Result<BufferedSurface<U, Space, Pixel>, ()> {
-        let next_slot = self.bindings.len();
-        self.bindings.push(ShaderBinding {
-            opacity: Fract8::MAX,
-            shader: None,
-            rect: area,
-            visible: true,
-            offset: Coordinates::top_left()
-        });
+    fn sort_draw_order(&mut self) {
+        let bindings = &self.bindings;
+        // Ties are broken by slot, so surfaces with the same z-order are drawn in the order they were created
+        self.draw_order.sort_by_key(|slot| (bindings[*slot].z_order, *slot));
+    }
+
+    fn new_surface(&mut self, area: Rectangle<Space>) -> Result<BufferedSurface<U, Space, Pixel, SH>, SurfaceError> {
+        if area.left() > area.right() || area.top() > area.bottom() {
+            return Err(SurfaceError::InvalidRect);
+        }
+        let (slot, generation) = match self.free_slots.pop() {
+            Some(slot) => {
+                // Bump the generation again, so a snapshot taken while the slot was free doesn't apply to the new surface
+                let generation = self.bindings[slot].generation.wrapping_add(1);
+                self.bindings[slot] = ShaderBinding::new(area, generation);
+                (slot, generation)
+            },
+            None => {
+                let next_slot = self.bindings.len();
+                if self.capacity.is_some_and(|capacity| next_slot >= capacity) {
+                    return Err(SurfaceError::OutOfSlots);
+                }
+                self.bindings.push(ShaderBinding::new(area, 0));
+                self.draw_order.push(next_slot);
+                (next_slot, 0)
+            }
+        };
+        self.sort_draw_order();
 
         Ok(BufferedSurface {
             updater: Arc::clone(&self.updates),
-            slot: next_slot
+            slot,
+            generation
         })
     }
 }
 
 /// A thread-safe [Surfaces] implementation where changes are buffered before they are committed in batches
-#[derive(Default)]
-pub struct BufferedSurfacePool<U, Space: CoordinateSpace, Pixel> {
-    pool: ShaderChain<U, Space, Pixel>
+///
+/// The shaders are stored as a `Box<dyn Shader>` by default, which supports any shader at the cost of a dynamic dispatch for every pixel. Pools that only ever use a fixed set of shaders can instead store them in an enum created with [shader_kind!](crate::shader_kind).
+pub struct BufferedSurfacePool<U, Space: CoordinateSpace, Pixel, SH = Box<dyn Shader<U, Space, Pixel>>> {
+    pool: ShaderChain<U, Space, Pixel, SH>
+}
+
+impl<U, Space: CoordinateSpace, Pixel> Default for BufferedSurfacePool<U, Space, Pixel> {
+    fn default() -> Self {
+        Self {
+            pool: Default::default()
+        }
+    }
 }
 
-impl<U: 'static, Space: CoordinateSpace, Pixel> BufferedSurfacePool<U, Space, Pixel> {
+impl<U: 'static, Space: CoordinateSpace, Pixel, SH> BufferedSurfacePool<U, Space, Pixel, SH> {
+    /// Creates a new empty pool, which can use a different [ShaderStorage] than the default
+    pub fn new() -> Self {
+        Self {
+            pool: Default::default()
+        }
+    }
+
+    /// Creates a new empty pool that can hold at most the given number of surfaces, after which [Surfaces::new_surface] fails with [SurfaceError::OutOfSlots]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            pool: ShaderChain {
+                bindings: Vec::with_capacity(capacity),
+                draw_order: Vec::with_capacity(capacity),
+                capacity: Some(capacity),
+                ..Default::default()
+            }
+        }
+    }
+
     /// Commits the queue of pending surface changes
     pub fn commit(&mut self) {
         self.pool.commit();
     }
+
+    /// Shows or hides every surface in the pool at once
+    pub fn set_all_visible(&mut self, visible: bool) {
+        self.pool.bindings.iter_mut().for_each(|binding| binding.visible = visible);
+    }
+
+    /// Shows or hides every surface that is tagged with the given group
+    ///
+    /// Groups are matched against the committed state of each surface, and the change takes effect immediately. Any pending changes from the surfaces themselves will still override this on the next commit.
+    pub fn set_group_visible(&mut self, group: u8, visible: bool) {
+        self.group_bindings(group).for_each(|binding| binding.visible = visible);
+    }
+
+    /// Sets the opacity of every surface that is tagged with the given group
+    ///
+    /// Like [BufferedSurfacePool::set_group_visible], this takes effect immediately.
+    pub fn set_group_opacity(&mut self, group: u8, opacity: impl Into<Fract8>) {
+        let opacity = opacity.into();
+        self.group_bindings(group).for_each(|binding| binding.opacity = opacity);
+    }
+
+    fn group_bindings(&mut self, group: u8) -> impl Iterator<Item = &mut ShaderBinding<U, Space, Pixel, SH>> {
+        self.pool.bindings.iter_mut().filter(move |binding| binding.group == Some(group))
+    }
+
+    /// Blanks the output back to the default pixel, then composites the surfaces on top of it. This is the common case of rendering a fresh frame in a single call.
+    pub fn render_frame<'a, S, HwPixel>(&'a self, output: &'a mut S, uniforms: &U)
+        where
+            Self: RenderSource<U, Space, Pixel, HwPixel>,
+            S: Sample<'a, Space, Output = HwPixel> + ?Sized,
+            HwPixel: Default + 'a {
+        for (_, pixel) in output.sample(&Rectangle::everything()) {
+            *pixel = HwPixel::default();
+        }
+        self.render_to(output, uniforms);
+    }
+
+    /// Fades the existing contents of the output towards black by the given amount, then composites the surfaces on top of it. Calling this every frame instead of clearing the output creates feedback effects such as trails.
+    pub fn render_with_decay<'a, S, HwPixel>(&'a self, output: &'a mut S, uniforms: &U, decay: Fract8)
+        where
+            Self: RenderSource<U, Space, Pixel, HwPixel>,
+            S: Sample<'a, Space, Output = HwPixel> + ?Sized,
+            HwPixel: Mul<Fract8, Output = HwPixel> + Copy + 'a {
+        let remaining = Fract8::MAX - decay;
+        for (_, pixel) in output.sample(&Rectangle::everything()) {
+            *pixel = *pixel * remaining;
+        }
+        self.render_to(output, uniforms);
+    }
+
+    /// Captures the committed state of every surface in the pool, except for their shaders
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    /// use figments::mappings::linear::LinearSpace;
+    ///
+    /// let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+    /// let mut sfc = pool.new_surface(Rectangle::everything()).unwrap();
+    /// sfc.set_shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(255, 0, 0));
+    /// pool.commit();
+    /// let scene = pool.snapshot();
+    ///
+    /// sfc.set_visible(false);
+    /// pool.commit();
+    ///
+    /// // Switch back to the saved scene
+    /// pool.restore(&scene);
+    /// let mut pixbuf = [Rgb::new(0u8, 0, 0); 4];
+    /// pool.render_frame(&mut pixbuf[..], &());
+    /// assert_eq!(pixbuf, [Rgb::new(255, 0, 0); 4]);
+    /// ```
+    pub fn snapshot(&self) -> PoolSnapshot<Space> {
+        PoolSnapshot {
+            surfaces: self.pool.bindings.iter().map(|binding| SurfaceState {
+                rect: binding.rect,
+                opacity: binding.opacity,
+                visible: binding.visible,
+                offset: binding.offset,
+                scale: binding.scale,
+                virtual_offset: binding.virtual_offset,
+                local_coordinates: binding.local_coordinates,
+                group: binding.group,
+                z_order: binding.z_order,
+                blend_mode: binding.blend_mode,
+                generation: binding.generation
+            }).collect()
+        }
+    }
+
+    /// Puts every surface back to the state it had when the [PoolSnapshot] was taken, while keeping their current shaders
+    ///
+    /// Like [BufferedSurfacePool::set_group_visible], this takes effect immediately. Surfaces that were created after the snapshot was taken are left as they are, even if they reuse the slot of a surface that has since been closed.
+    pub fn restore(&mut self, snapshot: &PoolSnapshot<Space>) {
+        for (binding, state) in self.pool.bindings.iter_mut().zip(snapshot.surfaces.iter()) {
+            if binding.generation != state.generation {
+                continue;
+            }
+            binding.rect = state.rect;
+            binding.opacity = state.opacity;
+            binding.visible = state.visible;
+            binding.offset = state.offset;
+            binding.scale = state.scale;
+            binding.virtual_offset = state.virtual_offset;
+            binding.local_coordinates = state.local_coordinates;
+            binding.group = state.group;
+            binding.z_order = state.z_order;
+            binding.blend_mode = state.blend_mode;
+        }
+        self.pool.sort_draw_order();
+    }
 }
 
-impl<U: 'static, Space: CoordinateSpace, Pixel: Copy + Fract8Ops + 'static + Copy> Surfaces for BufferedSurfacePool<U, Space, Pixel> {
-    type Error = ();
-    type Surface = BufferedSurface<U, Space, Pixel>;
+/// The state of a single surface within a [PoolSnapshot]
+#[derive(Clone, Copy)]
+struct SurfaceState<Space: CoordinateSpace> {
+    rect: Rectangle<Space>,
+    opacity: Fract8,
+    visible: bool,
+    offset: Coordinates<Space>,
+    scale: (u8, u8),
+    virtual_offset: VirtualCoordinates,
+    local_coordinates: bool,
+    group: Option<u8>,
+    z_order: i32,
+    blend_mode: BlendMode,
+    generation: u32
+}
+
+/// A saved copy of the rects, opacities, visibility, z-orders and other properties of every surface in a [BufferedSurfacePool], created with [BufferedSurfacePool::snapshot]
+///
+/// Shaders are not included, since they can't be copied.
+#[derive(Clone)]
+pub struct PoolSnapshot<Space: CoordinateSpace> {
+    surfaces: Vec<SurfaceState<Space>>
+}
+
+impl<Space: CoordinateSpace> Debug for PoolSnapshot<Space> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PoolSnapshot").field("surfaces", &self.surfaces.len()).finish()
+    }
+}
+
+impl<Space: CoordinateSpace> PoolSnapshot<Space> {
+    /// Returns the number of surfaces that were captured
+    pub fn len(&self) -> usize {
+        self.surfaces.len()
+    }
+
+    /// Returns true if the pool had no surfaces when the snapshot was taken
+    pub fn is_empty(&self) -> bool {
+        self.surfaces.is_empty()
+    }
+}
+
+impl<U: 'static, Space: CoordinateSpace, Pixel: Copy + Fract8Ops + 'static + Copy, SH: Shader<U, Space, Pixel>> Surfaces for BufferedSurfacePool<U, Space, Pixel, SH> {
+    type Error = SurfaceError;
+    type Surface = BufferedSurface<U, Space, Pixel, SH>;
     
     fn new_surface(&mut self, area: Rectangle<<Self::Surface as Surface>::CoordinateSpace>) -> Result<Self::Surface, Self::Error> {
         self.pool.new_surface(area)
     }
 }
 
-impl<U: 'static, Space: CoordinateSpace + core::fmt::Debug, Pixel: 'static + Debug, HwPixel: AdditivePixelSink<Pixel> + 'static> RenderSource<U, Space, Pixel, HwPixel> for BufferedSurfacePool<U, Space, Pixel> where Space::Data: core::fmt::Debug {
+impl<U, Space: CoordinateSpace, Pixel, SH> ShaderBinding<U, Space, Pixel, SH> {
+    fn new(rect: Rectangle<Space>, generation: u32) -> Self {
+        Self {
+            opacity: Fract8::MAX,
+            shader: None,
+            transition: None,
+            rect,
+            visible: true,
+            offset: Coordinates::top_left(),
+            scale: (1, 1),
+            virtual_offset: VirtualCoordinates::top_left(),
+            local_coordinates: false,
+            group: None,
+            z_order: 0,
+            blend_mode: BlendMode::Normal,
+            generation,
+            _space: PhantomData
+        }
+    }
+}
+
+impl<U, Space: CoordinateSpace, Pixel: Fract8Ops + Copy, SH: Shader<U, Space, Pixel>> ShaderBinding<U, Space, Pixel, SH> {
+    /// Draws the shader at a coordinate that has been shifted by a fraction of a coordinate unit, by blending it with its neighbors
+    fn draw_subpixel(&self, shader: &SH, coords: &Coordinates<Space>, uniforms: &U) -> Pixel {
+        let fraction_x = Fract8::from_raw(self.virtual_offset.x);
+        let fraction_y = Fract8::from_raw(self.virtual_offset.y);
+        let one = Space::Data::from(1);
+        let draw_row = |row: Coordinates<Space>| {
+            let pixel = shader.draw(&row, uniforms);
+            match fraction_x {
+                Fract8::MIN => pixel,
+                _ => pixel.blend8(shader.draw(&Coordinates::new(row.x.saturating_add(&one), row.y), uniforms), fraction_x)
+            }
+        };
+        let pixel = draw_row(*coords);
+        match fraction_y {
+            Fract8::MIN => pixel,
+            _ => pixel.blend8(draw_row(Coordinates::new(coords.x, coords.y.saturating_add(&one))), fraction_y)
+        }
+    }
+}
+
+impl<U: 'static, Space: CoordinateSpace + core::fmt::Debug, Pixel: 'static + Debug + Fract8Ops + Copy, HwPixel: AdditivePixelSink<Pixel> + 'static, SH: Shader<U, Space, Pixel>> RenderSource<U, Space, Pixel, HwPixel> for BufferedSurfacePool<U, Space, Pixel, SH> where Space::Data: core::fmt::Debug {
     fn render_to<'a, S>(&self, output: &mut S, uniforms: &U)
         where 
             S: Sample<'a, Space, Output = HwPixel> + ?Sized {
-        for surface in &self.pool.bindings {
+        for surface in self.pool.draw_order.iter().map(|slot| &self.pool.bindings[*slot]) {
             let opacity = surface.opacity;
             if opacity > Fract8::MIN && surface.visible {
-                if let Some(ref shader) = surface.shader {
-                    let rect = &surface.rect;
+                let rect = &surface.rect;
+                if let Some(ref transition) = surface.transition {
+                    let progress = transition.progress();
+                    let layer = |shader: &Option<SH>, coords: &Coordinates<Space>| shader.as_ref().map(|shader| (surface.draw_subpixel(shader, coords, uniforms), Fract8::MAX));
                     for (virt_coords, output_pixel) in output.sample(rect) {
-                        let adjusted = virt_coords + surface.offset;
-                        let shader_pixel = shader.draw(&adjusted, uniforms);
-                        output_pixel.add(shader_pixel, opacity);
+                        let virt_coords = if surface.local_coordinates { virt_coords.relative_to(rect) } else { virt_coords };
+                        let adjusted = virt_coords.scaled(surface.scale.0, surface.scale.1) + surface.offset;
+                        let outgoing = match transition.interrupted {
+                            Some((ref under, fade)) => crossfade(layer(under, &adjusted), layer(&transition.outgoing, &adjusted), fade),
+                            None => layer(&transition.outgoing, &adjusted)
+                        };
+                        if let Some((shader_pixel, coverage)) = crossfade(outgoing, layer(&surface.shader, &adjusted), progress) {
+                            output_pixel.blend(shader_pixel, opacity * coverage, surface.blend_mode);
+                        }
+                    }
+                } else if let Some(ref shader) = surface.shader {
+                    for (virt_coords, output_pixel) in output.sample(rect) {
+                        let virt_coords = if surface.local_coordinates { virt_coords.relative_to(rect) } else { virt_coords };
+                        let adjusted = virt_coords.scaled(surface.scale.0, surface.scale.1) + surface.offset;
+                        let shader_pixel = surface.draw_subpixel(shader, &adjusted, uniforms);
+                        output_pixel.blend(shader_pixel, opacity, surface.blend_mode);
                     }
                 }
             }
@@ -334,8 +939,8 @@ impl<'a, S: Surface<Uniforms = U, Pixel = Pixel>, SS: Surfaces<Surface = S>, SF:
     }
 
     /// Sets the initial opacity
-    pub fn opacity(mut self, opacity: Fract8) -> Self {
-        self.opacity = Some(opacity);
+    pub fn opacity(mut self, opacity: impl Into<Fract8>) -> Self {
+        self.opacity = Some(opacity.into());
         self
     }
 
@@ -357,29 +962,21 @@ impl<'a, S: Surface<Uniforms = U, Pixel = Pixel>, SS: Surfaces<Surface = S>, SF:
         self
     }
 
-    /// Constructs the surface
-    pub fn finish(self) -> Result<SS::Surface, SS::Error> {
-        let sfc = self.surfaces.new_surface(match self.rect {
-            None => Rectangle::everything(),
-            Some(r) => r
-        });
-
-        match sfc {
-            Ok(mut s) => {
-                if self.opacity.is_some() {
-                    s.set_opacity(self.opacity.unwrap());
-                }
-                if self.shader.is_some() {
-                    s.set_shader(self.shader.unwrap());
-                }
-                if self.visible.is_some() {
-                    s.set_visible(self.visible.unwrap());
-                }
+    /// Constructs the surface, or passes along the error from [Surfaces::new_surface] if it could not be created
+    pub fn finish(self) -> Result<SS::Surface, SS::Error> where S::Shaders: ShaderStorage<U, S::CoordinateSpace, Pixel, SF> {
+        let mut sfc = self.surfaces.new_surface(self.rect.unwrap_or_else(Rectangle::everything))?;
 
-                Ok(s)
-            },
-            err => err
+        if let Some(opacity) = self.opacity {
+            sfc.set_opacity(opacity);
+        }
+        if let Some(shader) = self.shader {
+            sfc.set_shader(shader);
         }
+        if let Some(visible) = self.visible {
+            sfc.set_visible(visible);
+        }
+
+        Ok(sfc)
     }
 }
 
@@ -394,8 +991,33 @@ pub trait Surface {
     /// The format of the pixels produced by this shader
     type Pixel;
 
+    /// How the shaders of this surface are stored, which decides what types of shaders can be given to [Surface::set_shader]
+    type Shaders: Shader<Self::Uniforms, Self::CoordinateSpace, Self::Pixel>;
+
     /// Sets the shader for this surface
-    fn set_shader<T: Shader<Self::Uniforms, Self::CoordinateSpace, Self::Pixel> + 'static>(&mut self, shader: T);
+    ///
+    /// Only shaders that can be held by [Surface::Shaders] are accepted, which is every shader for the default `Box<dyn Shader>` storage.
+    fn set_shader<T: Shader<Self::Uniforms, Self::CoordinateSpace, Self::Pixel> + 'static>(&mut self, shader: T) where Self::Shaders: ShaderStorage<Self::Uniforms, Self::CoordinateSpace, Self::Pixel, T>;
+
+    /// Sets the shader for this surface from a closure or function, whose argument types are inferred from the surface
+    ///
+    /// ```
+    /// use figments::prelude::*;
+    /// use figments::mappings::linear::LinearSpace;
+    /// use rgb::Rgb;
+    ///
+    /// let mut pool: BufferedSurfacePool<u8, LinearSpace, Rgb<u8>> = Default::default();
+    /// let mut sfc = pool.new_surface(Rectangle::everything()).unwrap();
+    /// sfc.set_shader_fn(|coords, frame| Rgb::new(coords.x as u8, *frame, 0));
+    /// pool.commit();
+    ///
+    /// let mut pixbuf = [Rgb::new(0, 0, 0); 4];
+    /// pool.render_to(&mut pixbuf[..], &42);
+    /// assert_eq!(pixbuf[3], Rgb::new(3, 42, 0));
+    /// ```
+    fn set_shader_fn<F>(&mut self, shader: F) where F: Fn(&Coordinates<Self::CoordinateSpace>, &Self::Uniforms) -> Self::Pixel + Send + 'static, Self::Shaders: ShaderStorage<Self::Uniforms, Self::CoordinateSpace, Self::Pixel, F> {
+        self.set_shader(shader);
+    }
 
     /// Clears the shader
     fn clear_shader(&mut self);
@@ -403,14 +1025,37 @@ pub trait Surface {
     /// Changes the size of the surface
     fn set_rect(&mut self, rect: Rectangle<Self::CoordinateSpace>);
 
+    /// Pins the surface to an exact region of physical pixels within a mapping, instead of a proportion of the display
+    fn set_physical_rect<M: PhysicalMapping<Self::CoordinateSpace>>(&mut self, map: &M, rect: &Rectangle<M::Physical>) {
+        self.set_rect(map.physical_rect(rect));
+    }
+
     /// Sets the opacity of this surface, where 0 is completely transparent and 255 is completely opaque
-    fn set_opacity(&mut self, opacity: Fract8);
+    ///
+    /// The opacity can be given either as a [Fract8], or as a raw `u8`.
+    fn set_opacity(&mut self, opacity: impl Into<Fract8>);
 
     /// Sets the visibility of the surface without adjusting the stored opacity
     fn set_visible(&mut self, visible: bool);
 
     /// Sets the scroll offset of the surface without adjusting shader coordinates
     fn set_offset(&mut self, offset: Coordinates<Self::CoordinateSpace>);
+
+    /// Sets a zoom factor that multiplies each coordinate before it is passed to the shader, where larger values make the shader's pattern repeat more often
+    fn set_scale(&mut self, x: u8, y: u8);
+
+    /// Sets a fractional scroll offset, where each axis moves the surface by 0-255 256ths of a single coordinate, in addition to the whole coordinates from [Surface::set_offset]
+    ///
+    /// The shader is drawn at each neighboring coordinate and blended together, so content can scroll smoothly even on a display with very few pixels.
+    fn set_virtual_offset(&mut self, offset: VirtualCoordinates);
+
+    /// Selects whether the shader sees coordinates relative to the surface's own rect, instead of the global coordinates of the display
+    ///
+    /// With local coordinates, a [Virtual] shader always sees 0-255 across the surface's rect, no matter where the rect is placed or how large it is. See [Coordinates::relative_to] for other spaces.
+    fn set_local_coordinates(&mut self, local: bool);
+
+    /// Tags the surface with a group, so that it can be controlled along with other surfaces in the same group without holding on to every surface
+    fn set_group(&mut self, group: Option<u8>);
 }
 
 impl<T: DerefMut<Target = S>, S: Surface> Surface for [T] {
@@ -420,7 +1065,9 @@ impl<T: DerefMut<Target = S>, S: Surface> Surface for [T] {
 
     type Pixel = S::Pixel;
 
-    fn set_shader<SH: Shader<Self::Uniforms, Self::CoordinateSpace, Self::Pixel> + 'static>(&mut self, _shader: SH) {
+    type Shaders = S::Shaders;
+
+    fn set_shader<SH: Shader<Self::Uniforms, Self::CoordinateSpace, Self::Pixel> + 'static>(&mut self, _shader: SH) where Self::Shaders: ShaderStorage<Self::Uniforms, Self::CoordinateSpace, Self::Pixel, SH> {
         unimplemented!();
     }
 
@@ -432,7 +1079,8 @@ impl<T: DerefMut<Target = S>, S: Surface> Surface for [T] {
         self.iter_mut().for_each(|f| { f.set_rect(rect); });
     }
 
-    fn set_opacity(&mut self, opacity: Fract8) {
+    fn set_opacity(&mut self, opacity: impl Into<Fract8>) {
+        let opacity = opacity.into();
         self.iter_mut().for_each(|f| { f.set_opacity(opacity); });
     }
 
@@ -443,6 +1091,83 @@ impl<T: DerefMut<Target = S>, S: Surface> Surface for [T] {
     fn set_offset(&mut self, offset: Coordinates<Self::CoordinateSpace>) {
         self.iter_mut().for_each(|f| { f.set_offset(offset); });
     }
+
+    fn set_scale(&mut self, x: u8, y: u8) {
+        self.iter_mut().for_each(|f| { f.set_scale(x, y); });
+    }
+
+    fn set_virtual_offset(&mut self, offset: VirtualCoordinates) {
+        self.iter_mut().for_each(|f| { f.set_virtual_offset(offset); });
+    }
+
+    fn set_local_coordinates(&mut self, local: bool) {
+        self.iter_mut().for_each(|f| { f.set_local_coordinates(local); });
+    }
+
+    fn set_group(&mut self, group: Option<u8>) {
+        self.iter_mut().for_each(|f| { f.set_group(group); });
+    }
+}
+
+/// A [Surface] wrapper that accepts shaders authored in a different [CoordinateSpace] than the pool it was created from
+///
+/// Every shader set on this surface is wrapped in a [MappedShader], which converts the pool's coordinates into the shader's space with the given mapping. Everything else, such as the rect and opacity, is still set in the pool's space through the wrapped surface.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::mappings::linear::LinearSpace;
+/// use figments::liber8tion::interpolate::Fract8;
+/// use rgb::Rgb;
+///
+/// let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+/// // Stretch the 16 pixels of the strip across the whole virtual space
+/// let mut sfc = MappedSurface::new(
+///     pool.new_surface(Rectangle::everything()).unwrap(),
+///     |coords: &Coordinates<LinearSpace>| VirtualCoordinates::new((coords.x * 16) as u8, 0)
+/// );
+/// sfc.set_shader(|coords: &VirtualCoordinates, _: &()| Rgb::new(coords.x, 0, 0));
+/// sfc.set_opacity(Fract8::from_raw(128));
+/// ```
+#[derive(Debug)]
+pub struct MappedSurface<S, Space, M> {
+    surface: S,
+    mapping: M,
+    space: PhantomData<fn() -> Space>
+}
+
+impl<S: Surface, Space: CoordinateSpace, M> MappedSurface<S, Space, M> where M: Clone + Send + 'static + Fn(&Coordinates<S::CoordinateSpace>) -> Coordinates<Space> {
+    /// Wraps a surface, using a function that converts the surface's coordinates into the space that shaders will be authored in
+    pub fn new(surface: S, mapping: M) -> Self {
+        Self {
+            surface,
+            mapping,
+            space: PhantomData
+        }
+    }
+
+    /// Sets a shader that is authored in the mapped space
+    pub fn set_shader<T: Shader<S::Uniforms, Space, S::Pixel> + 'static>(&mut self, shader: T) where S::Uniforms: 'static, S::Pixel: 'static, S::Shaders: ShaderStorage<S::Uniforms, S::CoordinateSpace, S::Pixel, MappedShader<T, M, Space>> {
+        self.surface.set_shader(MappedShader::new(shader, self.mapping.clone()));
+    }
+
+    /// Unwraps the underlying surface
+    pub fn into_inner(self) -> S {
+        self.surface
+    }
+}
+
+impl<S, Space, M> Deref for MappedSurface<S, Space, M> {
+    type Target = S;
+
+    fn deref(&self) -> &Self::Target {
+        &self.surface
+    }
+}
+
+impl<S, Space, M> DerefMut for MappedSurface<S, Space, M> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.surface
+    }
 }
 
 impl<U, Space: CoordinateSpace, Pixel> Shader<U, Space, Pixel> for Box<dyn Shader<U, Space, Pixel>> {
@@ -451,6 +1176,128 @@ impl<U, Space: CoordinateSpace, Pixel> Shader<U, Space, Pixel> for Box<dyn Shade
     }
 }
 
+/// How a [BufferedSurfacePool] stores the shader of each surface
+///
+/// The default storage is a `Box<dyn Shader>`, which can hold any shader. An enum of a fixed set of shaders can be used instead to avoid calling through a vtable for every pixel, and is most easily created with [shader_kind!](crate::shader_kind).
+///
+/// Storage is implemented once for every type of shader `T` it can hold, so giving a surface a shader that its storage can't hold is a compile error instead of a panic.
+pub trait ShaderStorage<U, Space: CoordinateSpace, Pixel, T>: Shader<U, Space, Pixel> + Sized {
+    /// Converts a shader given to [Surface::set_shader] into storage
+    fn store(shader: T) -> Self;
+}
+
+impl<U, Space: CoordinateSpace, Pixel, T: Shader<U, Space, Pixel> + 'static> ShaderStorage<U, Space, Pixel, T> for Box<dyn Shader<U, Space, Pixel>> {
+    fn store(shader: T) -> Self {
+        Box::new(shader)
+    }
+}
+
+/// Declares an enum of shaders that can be used as the [ShaderStorage] of a [BufferedSurfacePool], which renders each shader through a `match` instead of a vtable
+///
+/// Each variant wraps a single named shader type. Passing any of those types, or the enum itself, to [Surface::set_shader] stores it in the matching variant. Any other type of shader fails to compile, which includes closures as they cannot be named.
+///
+/// ```
+/// use figments::prelude::*;
+/// use figments::shader_kind;
+/// use figments::mappings::linear::LinearSpace;
+/// use rgb::Rgb;
+///
+/// struct Solid(Rgb<u8>);
+/// impl Shader<(), LinearSpace, Rgb<u8>> for Solid {
+///     fn draw(&self, _coords: &Coordinates<LinearSpace>, _uniforms: &()) -> Rgb<u8> {
+///         self.0
+///     }
+/// }
+///
+/// struct Ramp;
+/// impl Shader<(), LinearSpace, Rgb<u8>> for Ramp {
+///     fn draw(&self, coords: &Coordinates<LinearSpace>, _uniforms: &()) -> Rgb<u8> {
+///         Rgb::new(coords.x as u8, 0, 0)
+///     }
+/// }
+///
+/// shader_kind! {
+///     /// Every shader used by this program
+///     pub enum Effects: Shader<(), LinearSpace, Rgb<u8>> {
+///         Solid(Solid),
+///         Ramp(Ramp)
+///     }
+/// }
+///
+/// let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>, Effects> = BufferedSurfacePool::new();
+/// let mut sfc = pool.new_surface(Rectangle::everything()).unwrap();
+/// sfc.set_shader(Ramp);
+/// pool.commit();
+///
+/// let mut pixbuf = [Rgb::new(0, 0, 0); 4];
+/// pool.render_to(&mut pixbuf, &());
+/// assert_eq!(pixbuf[3], Rgb::new(3, 0, 0));
+/// ```
+///
+/// Shaders that are not one of the variants are rejected by the compiler:
+///
+/// ```compile_fail
+/// use figments::prelude::*;
+/// use figments::shader_kind;
+/// use figments::mappings::linear::LinearSpace;
+/// use rgb::Rgb;
+///
+/// struct Solid(Rgb<u8>);
+/// impl Shader<(), LinearSpace, Rgb<u8>> for Solid {
+///     fn draw(&self, _coords: &Coordinates<LinearSpace>, _uniforms: &()) -> Rgb<u8> {
+///         self.0
+///     }
+/// }
+///
+/// shader_kind! {
+///     pub enum Effects: Shader<(), LinearSpace, Rgb<u8>> {
+///         Solid(Solid)
+///     }
+/// }
+///
+/// let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>, Effects> = BufferedSurfacePool::new();
+/// let mut sfc = pool.new_surface(Rectangle::everything()).unwrap();
+/// sfc.set_shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(255, 0, 0));
+/// ```
+#[macro_export]
+macro_rules! shader_kind {
+    ($(#[$meta:meta])* $vis:vis enum $name:ident: Shader<$uniforms:ty, $space:ty, $pixel:ty> { $($variant:ident($shader:ty)),+ $(,)? }) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($variant($shader)),+
+        }
+
+        impl $crate::render::Shader<$uniforms, $space, $pixel> for $name {
+            #[inline]
+            fn draw(&self, coords: &$crate::geometry::Coordinates<$space>, uniforms: &$uniforms) -> $pixel {
+                match self {
+                    $(Self::$variant(shader) => shader.draw(coords, uniforms)),+
+                }
+            }
+        }
+
+        impl $crate::surface::ShaderStorage<$uniforms, $space, $pixel, $name> for $name {
+            fn store(shader: $name) -> Self {
+                shader
+            }
+        }
+
+        $(
+            impl From<$shader> for $name {
+                fn from(shader: $shader) -> Self {
+                    Self::$variant(shader)
+                }
+            }
+
+            impl $crate::surface::ShaderStorage<$uniforms, $space, $pixel, $shader> for $name {
+                fn store(shader: $shader) -> Self {
+                    Self::$variant(shader)
+                }
+            }
+        )+
+    };
+}
+
 /// A buffer pool that does nothing. Useful for testing.
 pub struct NullBufferPool<U, Space: CoordinateSpace, P>(NullSurface<U, Space, P>);
 /// A surface that does nothing. Useful for testing.
@@ -488,17 +1335,27 @@ impl<U, Space: CoordinateSpace, P> Surface for NullSurface<U, Space, P> {
 
     type Pixel = P;
 
-    fn set_shader<T: Shader<Self::Uniforms, Self::CoordinateSpace, Self::Pixel> + 'static>(&mut self, shader: T) {}
+    type Shaders = Box<dyn Shader<U, Space, P>>;
+
+    fn set_shader<T: Shader<Self::Uniforms, Self::CoordinateSpace, Self::Pixel> + 'static>(&mut self, shader: T) where Self::Shaders: ShaderStorage<Self::Uniforms, Self::CoordinateSpace, Self::Pixel, T> {}
 
     fn clear_shader(&mut self) {}
 
     fn set_rect(&mut self, rect: Rectangle<Self::CoordinateSpace>) {}
 
-    fn set_opacity(&mut self, opacity: Fract8) {}
+    fn set_opacity(&mut self, opacity: impl Into<Fract8>) {}
 
     fn set_visible(&mut self, visible: bool) {}
 
     fn set_offset(&mut self, offset: Coordinates<Self::CoordinateSpace>) {}
+
+    fn set_scale(&mut self, x: u8, y: u8) {}
+
+    fn set_virtual_offset(&mut self, offset: VirtualCoordinates) {}
+
+    fn set_local_coordinates(&mut self, local: bool) {}
+
+    fn set_group(&mut self, group: Option<u8>) {}
 }
 
 #[expect(unused_variables)]
@@ -523,6 +1380,7 @@ impl<U: Default, Space: CoordinateSpace, P> RenderSource<U, Space, P, P> for Nul
 mod test {
     use super::*;
     use crate::mappings::linear::LinearSpace;
+    use rgb::Rgba;
     #[test]
     fn test_shaderchain() {
         let mut c: ShaderChain<(), LinearSpace, Rgb<u8>> = Default::default();
@@ -551,4 +1409,622 @@ mod test {
         let mut pixbuf = [Rgb::default(); 1];
         pool.render_to(&mut pixbuf[..], &());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_render_with_decay_leaves_trail() {
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let mut sfc = SurfaceBuilder::build(&mut pool)
+            .shader(|_: &Coordinates<LinearSpace>, _: &()| { Rgb::new(200, 100, 0) })
+            .finish()
+            .unwrap();
+        let mut pixbuf = [Rgb::default(); 4];
+
+        // Move a single pixel wide surface along the strip, fading the previous frames by half each time
+        for x in 0..3 {
+            sfc.set_rect(Rectangle::single(Coordinates::new(x, 0)));
+            pool.commit();
+            pool.render_with_decay(&mut pixbuf[..], &(), Fract8::from_raw(128));
+        }
+
+        assert_eq!(pixbuf[2], Rgb::new(200, 100, 0), "The surface should be drawn at full brightness");
+        assert!(pixbuf[1].r < pixbuf[2].r && pixbuf[1].r > 0, "The previous frame should have faded: {pixbuf:?}");
+        assert!(pixbuf[0].r < pixbuf[1].r && pixbuf[0].r > 0, "The oldest frame should have faded the most: {pixbuf:?}");
+        assert_eq!(pixbuf[3], Rgb::default(), "The surface has not reached the end of the strip: {pixbuf:?}");
+    }
+
+    #[derive(Default, Debug, Clone, Copy)]
+    struct RadialSpace {}
+    impl CoordinateSpace for RadialSpace {
+        // X is the distance from the center, and Y is the angle
+        type Data = u8;
+    }
+
+    #[test]
+    fn test_mapped_surface_draws_radial_shader() {
+        const CENTER: usize = 8;
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let mut sfc = MappedSurface::new(
+            pool.new_surface(Rectangle::everything()).unwrap(),
+            |coords: &Coordinates<LinearSpace>| Coordinates::<RadialSpace>::new(coords.x.abs_diff(CENTER) as u8, 0)
+        );
+        sfc.set_shader(|coords: &Coordinates<RadialSpace>, _: &()| Rgb::new(255 - coords.x * 16, 0, coords.x));
+        // The rest of the surface API still passes through to the pool
+        sfc.set_rect(Rectangle::new_from_coordinates(0, 0, CENTER * 2, 0));
+        pool.commit();
+
+        let mut pixbuf = [Rgb::default(); CENTER * 2 + 2];
+        pool.render_to(&mut pixbuf[..], &());
+
+        assert_eq!(pixbuf[CENTER], Rgb::new(255, 0, 0), "The center of the radial shader should land on the center of the strip");
+        for distance in 1..=CENTER {
+            assert_eq!(pixbuf[CENTER - distance], pixbuf[CENTER + distance], "The radial shader should be symmetric around the center: {pixbuf:?}");
+            assert_eq!(pixbuf[CENTER + distance].b, distance as u8);
+        }
+        assert_eq!(pixbuf[CENTER * 2 + 1], Rgb::default(), "Pixels outside the surface should be untouched");
+    }
+
+    fn count_edges(pixbuf: &[Rgb<u8>]) -> usize {
+        pixbuf.windows(2).filter(|pair| pair[0] != pair[1]).count()
+    }
+
+    #[test]
+    fn test_scale_changes_frequency() {
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        // Alternates between black and white every 8 pixels
+        let mut sfc = SurfaceBuilder::build(&mut pool)
+            .shader(|coords: &Coordinates<LinearSpace>, _: &()| if (coords.x / 8) % 2 == 0 { Rgb::new(0, 0, 0) } else { Rgb::new(255, 255, 255) })
+            .finish()
+            .unwrap();
+        pool.commit();
+
+        let mut unscaled = [Rgb::default(); 64];
+        pool.render_to(&mut unscaled[..], &());
+
+        sfc.set_scale(4, 1);
+        pool.commit();
+        let mut scaled = [Rgb::default(); 64];
+        pool.render_to(&mut scaled[..], &());
+
+        assert_eq!(count_edges(&unscaled), 7);
+        assert_eq!(count_edges(&scaled), 31, "Scaling by 4 should make the pattern repeat 4 times as often: {scaled:?}");
+        assert_eq!(scaled[2], unscaled[8]);
+    }
+
+    #[test]
+    fn test_virtual_offset_scrolls_subpixel() {
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let mut sfc = SurfaceBuilder::build(&mut pool)
+            .shader(|coords: &Coordinates<LinearSpace>, _: &()| Rgb::new(coords.x as u8 * 16, 0, 0))
+            .finish()
+            .unwrap();
+        pool.commit();
+
+        let mut whole = [Rgb::default(); 8];
+        pool.render_to(&mut whole[..], &());
+
+        // A quarter of a pixel to the right, which the y offset should not disturb on a single row
+        sfc.set_virtual_offset(VirtualCoordinates::new(64, 32));
+        pool.commit();
+        let mut shifted = [Rgb::default(); 8];
+        pool.render_to(&mut shifted[..], &());
+
+        for (x, (whole, shifted)) in whole.iter().zip(shifted.iter()).enumerate() {
+            assert_eq!(shifted.r, whole.r + 4, "Pixel {x} should move a quarter of the way towards its neighbor: {shifted:?}");
+        }
+
+        sfc.set_virtual_offset(VirtualCoordinates::top_left());
+        pool.commit();
+        pool.render_to(&mut shifted[..], &());
+        assert_eq!(shifted, whole, "Removing the offset should restore the original pattern");
+    }
+
+    #[test]
+    fn test_group_visibility() {
+        const BACKGROUND: u8 = 1;
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        // Two surfaces in the background group, and one that is not in any group
+        for (x, group) in [(0, Some(BACKGROUND)), (1, None), (2, Some(BACKGROUND))] {
+            let mut sfc = SurfaceBuilder::build(&mut pool)
+                .rect(Rectangle::single(Coordinates::new(x, 0)))
+                .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(255, 255, 255))
+                .finish()
+                .unwrap();
+            sfc.set_group(group);
+        }
+        pool.commit();
+
+        let render = |pool: &BufferedSurfacePool<(), LinearSpace, Rgb<u8>>| {
+            let mut pixbuf = [Rgb::default(); 3];
+            pool.render_to(&mut pixbuf[..], &());
+            pixbuf.map(|pix| pix.r)
+        };
+        assert_eq!(render(&pool), [255, 255, 255]);
+
+        pool.set_group_visible(BACKGROUND, false);
+        assert_eq!(render(&pool), [0, 255, 0], "Only the background group should be hidden");
+
+        pool.set_group_visible(BACKGROUND, true);
+        pool.set_group_opacity(BACKGROUND, Fract8::from_raw(128));
+        assert_eq!(render(&pool), [128, 255, 128]);
+
+        pool.set_all_visible(false);
+        assert_eq!(render(&pool), [0, 0, 0]);
+
+        // Groups that don't exist do nothing
+        pool.set_all_visible(true);
+        pool.set_group_visible(42, false);
+        assert_eq!(render(&pool), [128, 255, 128]);
+    }
+
+    #[test]
+    fn test_render_frame_blanks_first() {
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let mut sfc = SurfaceBuilder::build(&mut pool)
+            .rect(Rectangle::new_from_coordinates(1, 0, 2, 0))
+            .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(0, 255, 0))
+            .opacity(Fract8::from_raw(128))
+            .finish()
+            .unwrap();
+        pool.commit();
+
+        // Leftovers from a previous frame
+        let mut pixbuf = [Rgb::new(255, 0, 0); 4];
+        pool.render_frame(&mut pixbuf[..], &());
+        assert_eq!(pixbuf, [Rgb::new(0, 0, 0), Rgb::new(0, 128, 0), Rgb::new(0, 128, 0), Rgb::new(0, 0, 0)]);
+
+        // Rendering again produces the exact same frame, instead of compositing over the previous one
+        sfc.set_visible(true);
+        pool.commit();
+        pool.render_frame(&mut pixbuf[..], &());
+        assert_eq!(pixbuf[1], Rgb::new(0, 128, 0));
+    }
+
+    #[test]
+    fn test_shader_alpha_combines_with_surface_opacity() {
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgba<u8>> = Default::default();
+        let _sfc = SurfaceBuilder::build(&mut pool)
+            .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgba::new(255, 255, 255, 128))
+            .opacity(Fract8::from_raw(128))
+            .finish()
+            .unwrap();
+        pool.commit();
+
+        let mut pixbuf = [Rgb::new(0u8, 0, 0); 2];
+        pool.render_to(&mut pixbuf[..], &());
+        assert_eq!(pixbuf, [Rgb::new(64, 64, 64); 2], "A half transparent pixel on a half opaque surface should be a quarter of the way to white");
+    }
+
+    #[test]
+    fn test_reset_surface_matches_fresh_surface() {
+        let shader = |coords: &Coordinates<LinearSpace>, _: &()| Rgb::new(coords.x as u8 * 10, 0, 0);
+        let render = |pool: &BufferedSurfacePool<(), LinearSpace, Rgb<u8>>| {
+            let mut pixbuf = [Rgb::new(0u8, 0, 0); 8];
+            pool.render_to(&mut pixbuf[..], &());
+            pixbuf
+        };
+
+        let mut fresh_pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let mut fresh = fresh_pool.new_surface(Rectangle::everything()).unwrap();
+        fresh.set_shader(shader);
+        fresh_pool.commit();
+
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let mut sfc = SurfaceBuilder::build(&mut pool)
+            .rect(Rectangle::new_from_coordinates(2, 0, 5, 0))
+            .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(0, 255, 0))
+            .opacity(64)
+            .finish()
+            .unwrap();
+        sfc.set_offset(Coordinates::new(3, 0));
+        sfc.set_scale(2, 1);
+        sfc.set_group(Some(1));
+        pool.commit();
+        sfc.set_visible(false);
+        pool.commit();
+
+        sfc.reset();
+        pool.commit();
+        assert_eq!(render(&pool), [Rgb::new(0, 0, 0); 8], "A reset surface should not have a shader");
+
+        // Changes made after the reset in the same batch still apply on top of it
+        sfc.reset();
+        sfc.set_shader(shader);
+        pool.commit();
+        assert_eq!(render(&pool), render(&fresh_pool));
+
+        // The surface also left its old group
+        pool.set_group_visible(1, false);
+        assert_eq!(render(&pool), render(&fresh_pool));
+    }
+
+    #[test]
+    fn test_capacity_limited_pool_runs_out_of_slots() {
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = BufferedSurfacePool::with_capacity(2);
+        let _first = SurfaceBuilder::build(&mut pool)
+            .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(255, 0, 0))
+            .finish()
+            .unwrap();
+        let _second = pool.new_surface(Rectangle::everything()).unwrap();
+
+        let third = SurfaceBuilder::build(&mut pool)
+            .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(0, 255, 0))
+            .finish();
+        assert_eq!(third.unwrap_err(), SurfaceError::OutOfSlots);
+
+        // The failed surface must not have been added to the pool
+        pool.commit();
+        let mut pixbuf = [Rgb::new(0u8, 0, 0); 2];
+        pool.render_to(&mut pixbuf[..], &());
+        assert_eq!(pixbuf, [Rgb::new(255, 0, 0); 2]);
+    }
+
+    #[test]
+    fn test_upside_down_rect_is_invalid() {
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let result = pool.new_surface(Rectangle::new_from_coordinates(8, 0, 4, 0));
+        assert_eq!(result.unwrap_err(), SurfaceError::InvalidRect);
+        assert!(pool.new_surface(Rectangle::single(Coordinates::new(4, 0))).is_ok(), "A single pixel is still a valid rect");
+    }
+
+    #[test]
+    fn test_restore_snapshot_undoes_changes() {
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let mut surfaces = [(0, 1), (2, 3)].map(|(left, right)| {
+            SurfaceBuilder::build(&mut pool)
+                .rect(Rectangle::new_from_coordinates(left, 0, right, 0))
+                .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(255, 255, 255))
+                .opacity(128)
+                .finish()
+                .unwrap()
+        });
+        pool.commit();
+
+        let render = |pool: &BufferedSurfacePool<(), LinearSpace, Rgb<u8>>| {
+            let mut pixbuf = [Rgb::default(); 4];
+            pool.render_to(&mut pixbuf[..], &());
+            pixbuf.map(|pix| pix.r)
+        };
+        let scene = pool.snapshot();
+        let expected = render(&pool);
+        assert_eq!(scene.len(), 2);
+
+        surfaces[0].set_opacity(255);
+        surfaces[0].set_rect(Rectangle::new_from_coordinates(0, 0, 3, 0));
+        surfaces[1].set_visible(false);
+        surfaces[1].set_group(Some(1));
+        pool.commit();
+        assert_eq!(render(&pool), [255, 255, 255, 255]);
+
+        pool.restore(&scene);
+        assert_eq!(render(&pool), expected, "Every surface should be back to its saved state, with the same shader");
+        pool.set_group_visible(1, false);
+        assert_eq!(render(&pool), expected, "The group should also have been restored");
+
+        // Surfaces that didn't exist yet when the snapshot was taken are left alone
+        let _extra = SurfaceBuilder::build(&mut pool)
+            .rect(Rectangle::single(Coordinates::new(3, 0)))
+            .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(1, 1, 1))
+            .finish()
+            .unwrap();
+        pool.commit();
+        pool.restore(&scene);
+        assert_eq!(render(&pool), [expected[0], expected[1], expected[2], 1]);
+
+        // A surface that reuses the slot of a closed one is also newer than the snapshot
+        let [first, _] = surfaces;
+        first.close();
+        pool.commit();
+        let _replacement = SurfaceBuilder::build(&mut pool)
+            .rect(Rectangle::new_from_coordinates(0, 0, 1, 0))
+            .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(2, 2, 2))
+            .finish()
+            .unwrap();
+        pool.commit();
+        pool.restore(&scene);
+        assert_eq!(render(&pool), [2, 2, expected[2], 1], "The replacement surface should not get the closed surface's state");
+    }
+
+    #[test]
+    fn test_restore_skips_slots_that_were_free_in_the_snapshot() {
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let popup = SurfaceBuilder::build(&mut pool)
+            .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(255, 0, 0))
+            .finish()
+            .unwrap();
+        pool.commit();
+        popup.close();
+        pool.commit();
+
+        // The snapshot only has the hidden placeholder for the free slot
+        let scene = pool.snapshot();
+        let _replacement = SurfaceBuilder::build(&mut pool)
+            .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(0, 255, 0))
+            .finish()
+            .unwrap();
+        pool.commit();
+        pool.restore(&scene);
+
+        let mut pixbuf = [Rgb::default(); 4];
+        pool.render_to(&mut pixbuf[..], &());
+        assert_eq!(pixbuf, [Rgb::new(0, 255, 0); 4], "The surface that reused the free slot should not be hidden by the restore");
+    }
+
+    #[test]
+    fn test_z_order_picks_top_layer() {
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let mut layers = [Rgb::new(255, 0, 0), Rgb::new(0, 255, 0), Rgb::new(0, 0, 255)].map(|color| {
+            SurfaceBuilder::build(&mut pool)
+                .shader(move |_: &Coordinates<LinearSpace>, _: &()| color)
+                .finish()
+                .unwrap()
+        });
+        pool.commit();
+
+        let render = |pool: &BufferedSurfacePool<(), LinearSpace, Rgb<u8>>| {
+            let mut pixbuf = [Rgb::default(); 1];
+            pool.render_frame(&mut pixbuf[..], &());
+            pixbuf[0]
+        };
+        assert_eq!(render(&pool), Rgb::new(0, 0, 255), "Surfaces with the same z-order should be drawn in the order they were created");
+
+        layers[0].set_z_order(2);
+        layers[2].set_z_order(-1);
+        pool.commit();
+        assert_eq!(render(&pool), Rgb::new(255, 0, 0), "The first surface should have been moved to the top");
+
+        layers[0].set_z_order(0);
+        pool.commit();
+        assert_eq!(render(&pool), Rgb::new(0, 255, 0), "A tie between the first two surfaces should fall back to the order they were created in");
+
+        // The last update to a surface before a commit wins
+        layers[2].set_z_order(5);
+        layers[2].set_z_order(1);
+        layers[1].reset();
+        pool.commit();
+        assert_eq!(render(&pool), Rgb::new(0, 0, 255));
+    }
+
+    #[test]
+    fn test_closed_surfaces_are_recycled() {
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let render = |pool: &BufferedSurfacePool<(), LinearSpace, Rgb<u8>>| {
+            let mut pixbuf = [Rgb::default(); 1];
+            pool.render_frame(&mut pixbuf[..], &());
+            pixbuf[0]
+        };
+        let _background = SurfaceBuilder::build(&mut pool)
+            .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(0, 0, 255))
+            .finish()
+            .unwrap();
+
+        for frame in 0..100u8 {
+            let popup = SurfaceBuilder::build(&mut pool)
+                .shader(move |_: &Coordinates<LinearSpace>, _: &()| Rgb::new(frame, 0, 0))
+                .finish()
+                .unwrap();
+            pool.commit();
+            assert_eq!(render(&pool), Rgb::new(frame, 0, 0), "The new surface should be drawn on top of the background");
+            popup.close();
+            pool.commit();
+            assert_eq!(render(&pool), Rgb::new(0, 0, 255), "A closed surface should stop rendering");
+        }
+        assert_eq!(pool.pool.bindings.len(), 2, "Every new surface should have reused the slot of the one before it");
+
+        // A handle to a closed surface can't reach the surface that took over its slot
+        let popup = pool.new_surface(Rectangle::everything()).unwrap();
+        let mut stale = BufferedSurface { updater: Arc::clone(&popup.updater), slot: popup.slot, generation: popup.generation };
+        popup.close();
+        pool.commit();
+        let mut replacement = pool.new_surface(Rectangle::everything()).unwrap();
+        assert_eq!(replacement.slot, stale.slot);
+        replacement.set_shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(0, 255, 0));
+        stale.set_opacity(0);
+        stale.close();
+        pool.commit();
+        assert_eq!(render(&pool), Rgb::new(0, 255, 0), "Updates from the closed surface should have been ignored");
+    }
+
+    #[test]
+    fn test_blend_modes_on_overlapping_surfaces() {
+        let render = |mode: BlendMode, opacity: u8| {
+            let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+            let _under = SurfaceBuilder::build(&mut pool)
+                .rect(Rectangle::new_from_coordinates(0, 0, 1, 0))
+                .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(200, 100, 0))
+                .finish()
+                .unwrap();
+            let mut over = SurfaceBuilder::build(&mut pool)
+                .rect(Rectangle::new_from_coordinates(1, 0, 2, 0))
+                .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(100, 128, 255))
+                .opacity(opacity)
+                .finish()
+                .unwrap();
+            over.set_blend_mode(mode);
+            pool.commit();
+            let mut pixbuf = [Rgb::default(); 3];
+            pool.render_frame(&mut pixbuf[..], &());
+            assert_eq!(pixbuf[0], Rgb::new(200, 100, 0), "Pixels outside of the top surface should not change");
+            (pixbuf[1], pixbuf[2])
+        };
+
+        // The last pixel only has black underneath it
+        assert_eq!(render(BlendMode::Normal, 255), (Rgb::new(100, 128, 255), Rgb::new(100, 128, 255)));
+        assert_eq!(render(BlendMode::Add, 255), (Rgb::new(255, 228, 255), Rgb::new(100, 128, 255)));
+        assert_eq!(render(BlendMode::Multiply, 255), (Rgb::new(78, 50, 0), Rgb::new(0, 0, 0)), "Multiplying by black should stay black");
+        assert_eq!(render(BlendMode::Screen, 255), (Rgb::new(222, 178, 255), Rgb::new(100, 128, 255)), "Screening over black should keep the original color");
+
+        // The opacity of the surface fades between the layer underneath and the blended result
+        assert_eq!(render(BlendMode::Add, 0).0, Rgb::new(200, 100, 0));
+        assert_eq!(render(BlendMode::Multiply, 128).0, Rgb::new(200, 100, 0).blend8(Rgb::new(78, 50, 0), Fract8::from_raw(128)));
+    }
+
+    fn render_with_opacity(opacity: impl Into<Fract8>) -> [Rgb<u8>; 2] {
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let mut sfc = SurfaceBuilder::build(&mut pool)
+            .shader(|_: &Coordinates<LinearSpace>, _: &()| Rgb::new(255, 255, 255))
+            .opacity(200)
+            .finish()
+            .unwrap();
+        pool.commit();
+        let mut pixbuf = [Rgb::new(0u8, 0, 0); 2];
+        pool.render_to(&mut pixbuf[..], &());
+        assert_eq!(pixbuf, [Rgb::new(200, 200, 200); 2], "The builder should accept a raw opacity");
+
+        sfc.set_opacity(opacity);
+        pool.commit();
+        pool.render_frame(&mut pixbuf[..], &());
+        pixbuf
+    }
+
+    #[test]
+    fn test_raw_opacity_matches_fract8() {
+        for opacity in [0, 1, 127, 128, 255] {
+            assert_eq!(
+                render_with_opacity(opacity),
+                render_with_opacity(Fract8::from_raw(opacity)),
+                "A raw opacity of {opacity} should render the same as the equivalent Fract8"
+            );
+        }
+    }
+
+    struct Ramp(u8);
+    impl Shader<(), LinearSpace, Rgb<u8>> for Ramp {
+        fn draw(&self, coords: &Coordinates<LinearSpace>, _: &()) -> Rgb<u8> {
+            Rgb::new((coords.x as u8).wrapping_mul(self.0), 0, 0)
+        }
+    }
+
+    struct Solid(Rgb<u8>);
+    impl Shader<(), LinearSpace, Rgb<u8>> for Solid {
+        fn draw(&self, _: &Coordinates<LinearSpace>, _: &()) -> Rgb<u8> {
+            self.0
+        }
+    }
+
+    crate::shader_kind! {
+        enum TestKind: Shader<(), LinearSpace, Rgb<u8>> {
+            Ramp(Ramp),
+            Solid(Solid),
+        }
+    }
+
+    #[test]
+    fn test_shader_kind_matches_boxed_shaders() {
+        let mut boxed: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let mut kinds: BufferedSurfacePool<(), LinearSpace, Rgb<u8>, TestKind> = BufferedSurfacePool::new();
+
+        let mut boxed_sfcs = [boxed.new_surface(Rectangle::everything()).unwrap(), boxed.new_surface(Rectangle::new_from_coordinates(2, 0, 5, 0)).unwrap()];
+        let mut kind_sfcs = [kinds.new_surface(Rectangle::everything()).unwrap(), kinds.new_surface(Rectangle::new_from_coordinates(2, 0, 5, 0)).unwrap()];
+
+        boxed_sfcs[0].set_shader(Ramp(16));
+        kind_sfcs[0].set_shader(Ramp(16));
+        boxed_sfcs[1].set_shader(Solid(Rgb::new(0, 0, 255)));
+        kind_sfcs[1].set_shader(TestKind::from(Solid(Rgb::new(0, 0, 255))));
+        boxed_sfcs[1].set_opacity(Fract8::from_raw(200));
+        kind_sfcs[1].set_opacity(Fract8::from_raw(200));
+        boxed.commit();
+        kinds.commit();
+
+        let mut boxed_pixbuf = [Rgb::new(0u8, 0, 0); 8];
+        let mut kind_pixbuf = boxed_pixbuf;
+        boxed.render_to(&mut boxed_pixbuf[..], &());
+        kinds.render_to(&mut kind_pixbuf[..], &());
+        assert_eq!(boxed_pixbuf, kind_pixbuf, "The enum storage should render exactly like the boxed storage");
+        assert_ne!(kind_pixbuf[3], Rgb::new(0, 0, 0));
+    }
+
+    #[test]
+    fn test_local_coordinates_fill_rect() {
+        use crate::mappings::MatrixSampler;
+
+        let mut pool: BufferedSurfacePool<(), Virtual, Rgb<u8>> = Default::default();
+        let mut sfc = SurfaceBuilder::build(&mut pool)
+            .rect(Rectangle::new_from_coordinates(64, 0, 112, 0))
+            .shader(|coords: &VirtualCoordinates, _: &()| Rgb::new(coords.x, 0, 0))
+            .finish()
+            .unwrap();
+        pool.commit();
+
+        let mut pixbuf = [[Rgb::new(0u8, 0, 0); 16]; 1];
+        pool.render_frame(&mut MatrixSampler::new(&mut pixbuf), &());
+        assert_eq!(pixbuf[0][4..=7], [Rgb::new(64, 0, 0), Rgb::new(80, 0, 0), Rgb::new(96, 0, 0), Rgb::new(112, 0, 0)], "Global coordinates should match the display");
+
+        sfc.set_local_coordinates(true);
+        pool.commit();
+        pool.render_frame(&mut MatrixSampler::new(&mut pixbuf), &());
+        assert_eq!(pixbuf[0][4..=7], [Rgb::new(0, 0, 0), Rgb::new(85, 0, 0), Rgb::new(170, 0, 0), Rgb::new(255, 0, 0)], "The gradient should span the rect from edge to edge");
+        assert_eq!(pixbuf[0][8], Rgb::new(0, 0, 0), "Nothing should be drawn outside of the rect");
+    }
+
+    #[test]
+    fn test_transition_steps_frame_by_frame() {
+        let red = |_: &Coordinates<LinearSpace>, _: &()| Rgb::new(255, 0, 0);
+        let blue = |_: &Coordinates<LinearSpace>, _: &()| Rgb::new(0, 0, 255);
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let mut sfc = pool.new_surface(Rectangle::everything()).unwrap();
+        sfc.set_shader(red);
+        pool.commit();
+
+        sfc.transition_to(blue, 4);
+        pool.commit();
+        let mut pixbuf = [Rgb::new(0u8, 0, 0); 2];
+        let mut frames = [Rgb::new(0u8, 0, 0); 4];
+        for (idx, frame) in frames.iter_mut().enumerate() {
+            if idx > 0 {
+                pool.commit();
+            }
+            pool.render_frame(&mut pixbuf[..], &());
+            *frame = pixbuf[0];
+            pool.render_frame(&mut pixbuf[..], &());
+            assert_eq!(pixbuf[0], *frame, "Rendering again without a commit should not move the fade ahead");
+        }
+        assert_eq!(frames, [Rgb::new(192, 0, 63), Rgb::new(128, 0, 127), Rgb::new(64, 0, 191), Rgb::new(0, 0, 255)]);
+        assert!(pool.pool.bindings[0].transition.is_some(), "The old shader should be kept until the next commit");
+
+        pool.commit();
+        assert!(pool.pool.bindings[0].transition.is_none(), "The old shader should be removed once the fade is finished");
+        pool.render_frame(&mut pixbuf[..], &());
+        assert_eq!(pixbuf, [Rgb::new(0, 0, 255); 2]);
+
+        // A surface without a shader fades in over whatever is underneath it
+        sfc.clear_shader();
+        pool.commit();
+        sfc.transition_to(red, 2);
+        pool.commit();
+        let mut pixbuf = [Rgb::new(0u8, 255, 0); 1];
+        pool.render_to(&mut pixbuf[..], &());
+        assert_eq!(pixbuf, [Rgb::new(127, 128, 0)]);
+
+        // Replacing the shader outright cancels the fade
+        sfc.set_shader(blue);
+        pool.commit();
+        assert!(pool.pool.bindings[0].transition.is_none());
+    }
+
+    #[test]
+    fn test_interrupted_transition_continues_from_blend() {
+        let red = |_: &Coordinates<LinearSpace>, _: &()| Rgb::new(255, 0, 0);
+        let green = |_: &Coordinates<LinearSpace>, _: &()| Rgb::new(0, 255, 0);
+        let blue = |_: &Coordinates<LinearSpace>, _: &()| Rgb::new(0, 0, 255);
+        let mut pool: BufferedSurfacePool<(), LinearSpace, Rgb<u8>> = Default::default();
+        let mut sfc = pool.new_surface(Rectangle::everything()).unwrap();
+        sfc.set_shader(red);
+        pool.commit();
+
+        sfc.transition_to(blue, 4);
+        pool.commit();
+        pool.commit();
+        let mut pixbuf = [Rgb::new(0u8, 0, 0); 1];
+        pool.render_frame(&mut pixbuf[..], &());
+        assert_eq!(pixbuf, [Rgb::new(128, 0, 127)], "The first fade should be halfway done");
+
+        // The second fade starts from the half red, half blue that is on screen
+        sfc.transition_to(green, 2);
+        pool.commit();
+        pool.render_frame(&mut pixbuf[..], &());
+        assert_eq!(pixbuf, [Rgb::new(64, 127, 64)]);
+        pool.commit();
+        pool.render_frame(&mut pixbuf[..], &());
+        assert_eq!(pixbuf, [Rgb::new(0, 255, 0)]);
+    }
+}